@@ -0,0 +1,20 @@
+package geometry.shape;
+
+public class Circle {
+    static double pi = 3.14159;
+
+    float radius;
+
+    Circle(float radius) {
+        this.radius = radius;
+    }
+
+    float computeArea() {
+        return (float) (pi * radius * radius);
+    }
+
+    public static void main(String[] args) {
+        Circle circle = new Circle(12.0f);
+        circle.computeArea();
+    }
+}
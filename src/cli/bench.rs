@@ -0,0 +1,132 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::mpsc::channel;
+use std::time::{Duration, Instant};
+
+use crate::vm::class::name::ClassName;
+use crate::vm::exec::class_loader::ClassLoader;
+use crate::vm::exec::class_source::CompositeSource;
+use crate::vm::exec::error::ExecError;
+use crate::vm::exec::interpreter::Interpreter;
+use crate::vm::exec::vm::Vm;
+
+
+/// A message sent from the virtual machine callbacks.
+enum BenchMsg {
+    Update,
+    Ended,
+    Error(ExecError),
+}
+
+
+/// A result of a single run of a benchmark.
+struct Measurement {
+    instructions: u64,
+    elapsed: Duration,
+}
+
+
+impl Measurement {
+    /// Returns the number of instructions per second.
+    fn ips(&self) -> f64 {
+        self.instructions as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+
+/// Runs the main method of every class on the class path by both the stepping thread
+/// and the interpreter, prints the instructions per second of each and returns
+/// the process exit status.
+pub fn bench(class_path: Vec<PathBuf>) -> i32 {
+    let class_names = match ClassLoader::new(CompositeSource::class_path(class_path.clone())).main_classes() {
+        Ok(class_names) => class_names,
+        Err(error) => {
+            eprintln!("error: can not read the class path: {}", error);
+            return 1;
+        }
+    };
+
+    println!("{:<24} {:>12} {:>14} {:>14} {:>8}", "class", "instructions", "stepped ips", "interpreted ips", "speedup");
+
+    let mut status = 0;
+
+    for class_name in class_names {
+        let result = step(class_path.clone(), &class_name)
+            .and_then(|stepped| Ok((stepped, interpret(class_path.clone(), &class_name)?)));
+
+        match result {
+            Ok((stepped, interpreted)) => {
+                println!(
+                    "{:<24} {:>12} {:>14.0} {:>14.0} {:>7.1}x",
+                    class_name.to_string(),
+                    interpreted.instructions,
+                    stepped.ips(),
+                    interpreted.ips(),
+                    interpreted.ips() / stepped.ips(),
+                );
+            }
+            Err(error) => {
+                eprintln!("error: {}: {}", class_name, error);
+                status = 1;
+            }
+        }
+    }
+
+    status
+}
+
+
+/// Runs the main method by the thread stepped on every update.
+fn step(class_path: Vec<PathBuf>, class_name: &ClassName) -> Result<Measurement, ExecError> {
+    let vm = Arc::new(Vm::new(CompositeSource::class_path(class_path)));
+    let (tx, rx) = channel();
+
+    let s = tx.clone();
+    vm.set_update_callback(Some(Box::new(move || {
+        s.send(BenchMsg::Update).unwrap();
+    })));
+    let s = tx.clone();
+    vm.set_end_callback(Some(Box::new(move || {
+        s.send(BenchMsg::Ended).unwrap();
+    })));
+    let s = tx;
+    vm.set_error_callback(Some(Box::new(move |error, _| {
+        s.send(BenchMsg::Error(error)).unwrap();
+    })));
+
+    let start = Instant::now();
+    vm.clone().start(class_name.clone(), Vec::new());
+
+    let mut instructions = 0;
+    let mut result = Ok(());
+
+    while let Ok(msg) = rx.recv() {
+        match msg {
+            BenchMsg::Update => {
+                instructions += 1;
+                vm.next();
+            }
+            BenchMsg::Error(error) => result = Err(error),
+            BenchMsg::Ended => break,
+        }
+    }
+
+    vm.join();
+    let elapsed = start.elapsed();
+
+    result.map(|_| Measurement { instructions, elapsed })
+}
+
+
+/// Runs the main method by the interpreter.
+fn interpret(class_path: Vec<PathBuf>, class_name: &ClassName) -> Result<Measurement, ExecError> {
+    let mut interpreter = Interpreter::new(Arc::new(Vm::new(CompositeSource::class_path(class_path))));
+
+    let start = Instant::now();
+    interpreter.run_main(class_name, Vec::new())?;
+
+    Ok(Measurement {
+        instructions: interpreter.executed(),
+        elapsed: start.elapsed(),
+    })
+}
@@ -0,0 +1,56 @@
+use std::path::PathBuf;
+
+use crate::vm::class::class::Class;
+use crate::vm::class::name::ClassName;
+use crate::vm::exec::class_loader::ClassLoader;
+use crate::vm::exec::class_source::CompositeSource;
+use crate::vm::verify::lint::Linter;
+
+
+/// Loads and verifies all the classes on the class path, reports errors
+/// and lint warnings and returns the process exit status.
+pub fn check(class_path: Vec<PathBuf>, main_class: Option<ClassName>) -> i32 {
+    let class_loader = ClassLoader::new(CompositeSource::class_path(class_path));
+
+    let class_names = match class_loader.class_names() {
+        Ok(class_names) => class_names,
+        Err(error) => {
+            eprintln!("error: can not read the class path: {}", error);
+            return 1;
+        }
+    };
+
+    let mut classes: Vec<Class> = Vec::new();
+    let mut errors = 0;
+
+    for class_name in &class_names {
+        match class_loader.load(class_name) {
+            Ok(class) => classes.push(class),
+            Err(error) => {
+                eprintln!("error: {}\n", error);
+                errors += 1;
+            }
+        }
+    }
+
+    if let Some(main_class) = &main_class {
+        if !class_names.contains(main_class) {
+            eprintln!("error: main class {} is not on the class path\n", main_class);
+            errors += 1;
+        }
+    }
+
+    let lints = Linter::new(&classes).lint(main_class.as_ref());
+
+    for lint in &lints {
+        eprintln!("warning: {}", lint);
+    }
+
+    eprintln!("checked {} classes: {} errors, {} warnings", class_names.len(), errors, lints.len());
+
+    if errors > 0 {
+        1
+    } else {
+        0
+    }
+}
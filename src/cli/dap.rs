@@ -0,0 +1,760 @@
+//! A debug adapter speaking the Debug Adapter Protocol over the standard input and output,
+//! so that the programs can be debugged from an editor.
+//!
+//! The program is launched with the arguments `classPath` (a list of entries or a string
+//! separated by ':'), `mainClass`, `args` (strings parsed as the parameters of the main method)
+//! and `stopOnEntry`. Breakpoints are set on the lines of the `.mvm` class files,
+//! every instruction is a step.
+
+use std::collections::{HashMap, HashSet};
+use std::convert::TryInto;
+use std::fs;
+use std::io;
+use std::io::{BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc::{channel, Sender};
+use std::thread;
+
+use serde_json::{json, Value as Json};
+
+use crate::cli::protocol::{read_message, write_message};
+use crate::cli::run::parse_args;
+use crate::vm::class::class::Class;
+use crate::vm::class::instance::Instance;
+use crate::vm::class::name::ClassName;
+use crate::vm::exec::class_source::CompositeSource;
+use crate::vm::exec::error::ExecError;
+use crate::vm::exec::stack_trace::StackTrace;
+use crate::vm::exec::vm::Vm;
+use crate::vm::memory::frame::Frame;
+use crate::vm::memory::locals::Slot;
+use crate::vm::parse::error::CreateClassError;
+use crate::vm::parse::parser::ClassFileParser;
+use crate::vm::types::reference::Reference;
+use crate::vm::types::value::Value;
+
+
+/// The id of the only thread of the program.
+const THREAD_ID: i64 = 1;
+
+
+/// A message sent from the input reader or the virtual machine callbacks.
+enum DapMsg {
+    Request(Json),
+    Closed,
+    Update,
+    Ended,
+    Error(ExecError, StackTrace),
+}
+
+
+/// How the execution continues when the thread is ready for the next step.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Resume {
+    /// The thread is paused, it is not stepped.
+    Paused,
+    /// The thread runs until a breakpoint.
+    Continue,
+    /// The thread executes a single instruction.
+    StepIn,
+    /// The thread runs until it gets back to the frame of the given depth or below it.
+    StepOver(usize),
+    /// The thread runs until it returns from the frame of the given depth.
+    StepOut(usize),
+    /// The thread stops for the given reason on the next step.
+    Stop(&'static str),
+}
+
+
+/// Variables the client can ask for by their reference.
+enum Variables {
+    Locals(Arc<Frame>),
+    Operands(Arc<Frame>),
+    Statics(Arc<Class>),
+    Fields(Instance),
+}
+
+
+/// Serves a single debugging session on the standard input and output
+/// and returns the process exit status.
+pub fn dap() -> i32 {
+    serve(io::stdin(), io::stdout())
+}
+
+
+/// Serves a single debugging session, reading the requests from the input
+/// and writing the responses and events to the output, until the client
+/// disconnects or closes the input. Returns the process exit status.
+pub fn serve(input: impl Read + Send + 'static, output: impl Write) -> i32 {
+    let (tx, rx) = channel();
+
+    thread::spawn({
+        let tx = tx.clone();
+        move || {
+            let mut reader = BufReader::new(input);
+
+            loop {
+                match read_message(&mut reader) {
+                    Ok(Some(request)) => {
+                        if tx.send(DapMsg::Request(request)).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(error) => {
+                        eprintln!("error: can not read the request: {}", error);
+                        break;
+                    }
+                }
+            }
+
+            tx.send(DapMsg::Closed).ok();
+        }
+    });
+
+    let mut adapter = DebugAdapter::new(output, tx);
+    let mut status = 0;
+
+    while let Ok(msg) = rx.recv() {
+        let result = match msg {
+            DapMsg::Request(request) => adapter.request(&request),
+            DapMsg::Update => adapter.update().map(|_| true),
+            DapMsg::Error(error, trace) => adapter.error(error, trace).map(|_| true),
+            DapMsg::Ended => adapter.ended().map(|_| true),
+            DapMsg::Closed => Ok(false),
+        };
+
+        match result {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(error) => {
+                eprintln!("error: can not write the response: {}", error);
+                status = 1;
+                break;
+            }
+        }
+    }
+
+    adapter.stop();
+    status
+}
+
+
+/// The state of a debugging session.
+struct DebugAdapter<W: Write> {
+    output: W,
+    seq: i64,
+    tx: Sender<DapMsg>,
+    vm: Option<Arc<Vm>>,
+    launch: Option<(ClassName, Vec<Value>)>,
+    stop_on_entry: bool,
+    running: bool,
+    failed: bool,
+    resume: Resume,
+    breakpoints: HashMap<PathBuf, HashSet<usize>>,
+    class_files: HashMap<ClassName, Option<PathBuf>>,
+    frames: Vec<Arc<Frame>>,
+    variables: Vec<Variables>,
+}
+
+
+impl<W: Write> DebugAdapter<W> {
+    fn new(output: W, tx: Sender<DapMsg>) -> Self {
+        DebugAdapter {
+            output,
+            seq: 0,
+            tx,
+            vm: None,
+            launch: None,
+            stop_on_entry: false,
+            running: false,
+            failed: false,
+            resume: Resume::Paused,
+            breakpoints: HashMap::new(),
+            class_files: HashMap::new(),
+            frames: Vec::new(),
+            variables: Vec::new(),
+        }
+    }
+
+    /// Handles the request and responds to it.
+    /// Returns false if the session ends.
+    fn request(&mut self, request: &Json) -> Result<bool, io::Error> {
+        let command = request["command"].as_str().unwrap_or_default();
+        let args = &request["arguments"];
+
+        let result = match command {
+            "initialize" => Ok(json!({ "supportsConfigurationDoneRequest": true })),
+            "launch" => self.launch(args),
+            "setBreakpoints" => self.set_breakpoints(args),
+            "setExceptionBreakpoints" => Ok(json!({ "breakpoints": [] })),
+            "configurationDone" => self.configuration_done(),
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })),
+            "stackTrace" => self.stack_trace(),
+            "scopes" => self.scopes(args),
+            "variables" => self.variables(args),
+            "continue" => self.resume(Resume::Continue).map(|_| json!({ "allThreadsContinued": true })),
+            "next" => self.resume(Resume::StepOver(self.depth())).map(|_| Json::Null),
+            "stepIn" => self.resume(Resume::StepIn).map(|_| Json::Null),
+            "stepOut" => self.resume(Resume::StepOut(self.depth())).map(|_| Json::Null),
+            "pause" => self.pause().map(|_| Json::Null),
+            "disconnect" | "terminate" => Ok(Json::Null),
+            _ => Err(format!("unsupported request: {}", command)),
+        };
+
+        let initialized = command == "initialize" && result.is_ok();
+        self.respond(request, result)?;
+
+        if initialized {
+            self.event("initialized", Json::Null)?;
+        }
+
+        Ok(command != "disconnect" && command != "terminate")
+    }
+
+    /// Creates the virtual machine and loads the main class, the program is started
+    /// when the configuration is done.
+    fn launch(&mut self, args: &Json) -> Result<Json, String> {
+        if self.vm.is_some() {
+            return Err("the program is already launched".to_string());
+        }
+
+        let class_path: Vec<PathBuf> = match &args["classPath"] {
+            Json::String(class_path) => class_path.split(':').filter(|entry| !entry.is_empty()).map(PathBuf::from).collect(),
+            Json::Array(entries) => entries.iter().filter_map(Json::as_str).map(PathBuf::from).collect(),
+            _ => return Err("the class path is missing".to_string()),
+        };
+
+        let main_class = args["mainClass"].as_str().ok_or("the main class is missing")?;
+        let main_class = ClassName::new(main_class).map_err(|error| error.to_string())?;
+
+        let program_args: Vec<&str> = args["args"].as_array()
+                                                  .map(|args| args.iter().filter_map(Json::as_str).collect())
+                                                  .unwrap_or_default();
+
+        let vm = Arc::new(Vm::new(CompositeSource::class_path(class_path)));
+        let program_args = parse_args(&vm, &main_class, &program_args)?;
+
+        let s = self.tx.clone();
+        vm.set_update_callback(Some(Box::new(move || {
+            s.send(DapMsg::Update).ok();
+        })));
+        let s = self.tx.clone();
+        vm.set_end_callback(Some(Box::new(move || {
+            s.send(DapMsg::Ended).ok();
+        })));
+        let s = self.tx.clone();
+        vm.set_error_callback(Some(Box::new(move |error, trace| {
+            s.send(DapMsg::Error(error, trace)).ok();
+        })));
+
+        self.vm = Some(vm);
+        self.launch = Some((main_class, program_args));
+        self.stop_on_entry = args["stopOnEntry"].as_bool().unwrap_or(false);
+
+        Ok(Json::Null)
+    }
+
+    /// Replaces the breakpoints of the class file. Only the lines with an instruction are verified.
+    fn set_breakpoints(&mut self, args: &Json) -> Result<Json, String> {
+        let path = args["source"]["path"].as_str().ok_or("the source path is missing")?;
+        let path = canonical(Path::new(path));
+
+        let lines: Vec<usize> = args["breakpoints"].as_array()
+                                                   .map(|breakpoints| breakpoints.iter()
+                                                                                 .filter_map(|breakpoint| breakpoint["line"].as_u64())
+                                                                                 .map(|line| line as usize)
+                                                                                 .collect())
+                                                   .unwrap_or_default();
+
+        let instruction_lines = instruction_lines(&path);
+
+        let breakpoints: Vec<_> = lines.iter()
+                                       .map(|line| match &instruction_lines {
+                                           Ok(instructions) if instructions.contains(line) => json!({ "verified": true, "line": line }),
+                                           Ok(_) => json!({ "verified": false, "line": line, "message": "no instruction on the line" }),
+                                           Err(error) => json!({ "verified": false, "line": line, "message": error }),
+                                       })
+                                       .collect();
+
+        self.breakpoints.insert(path, lines.into_iter().collect());
+
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    /// Starts the launched program.
+    fn configuration_done(&mut self) -> Result<Json, String> {
+        if let (Some(vm), Some((main_class, args))) = (&self.vm, self.launch.take()) {
+            self.resume = if self.stop_on_entry { Resume::Stop("entry") } else { Resume::Continue };
+            self.running = true;
+            vm.clone().start(main_class, args);
+        }
+
+        Ok(Json::Null)
+    }
+
+    /// Returns the frames of the paused program, the innermost first.
+    fn stack_trace(&mut self) -> Result<Json, String> {
+        let vm = self.paused_vm()?;
+        self.frames = vm.frames().unwrap_or_default();
+
+        let frames: Vec<_> = self.frames.iter()
+                                 .enumerate()
+                                 .rev()
+                                 .map(|(index, frame)| {
+                                     let line = frame.method().code().line(frame.pc());
+
+                                     let mut json = json!({
+                                         "id": index + 1,
+                                         "name": format!("{}.{} ({})", frame.class().name(), frame.method().signature().name(), frame.method().signature().params_desc()),
+                                         "line": line.unwrap_or(0),
+                                         "column": if line.is_some() { 1 } else { 0 },
+                                     });
+
+                                     if let Some(file) = vm.class_file(frame.class().name()) {
+                                         json["source"] = json!({
+                                             "name": file.file_name().map(|name| name.to_string_lossy()).unwrap_or_default(),
+                                             "path": file,
+                                         });
+                                     }
+
+                                     json
+                                 })
+                                 .collect();
+
+        Ok(json!({ "stackFrames": frames, "totalFrames": frames.len() }))
+    }
+
+    /// Returns the local variables, the operand stack and the static fields of the frame.
+    fn scopes(&mut self, args: &Json) -> Result<Json, String> {
+        let frame = args["frameId"].as_u64()
+                                   .and_then(|id| self.frames.get((id as usize).wrapping_sub(1)))
+                                   .cloned()
+                                   .ok_or("unknown frame")?;
+
+        let scopes = json!({ "scopes": [
+            { "name": "Locals", "variablesReference": self.add_variables(Variables::Locals(frame.clone())), "expensive": false },
+            { "name": "Operand stack", "variablesReference": self.add_variables(Variables::Operands(frame.clone())), "expensive": false },
+            { "name": "Static fields", "variablesReference": self.add_variables(Variables::Statics(frame.class().clone())), "expensive": false },
+        ]});
+
+        Ok(scopes)
+    }
+
+    /// Returns the variables of the reference, instances have a reference to their fields.
+    fn variables(&mut self, args: &Json) -> Result<Json, String> {
+        let index = args["variablesReference"].as_u64().unwrap_or(0) as usize;
+
+        let values: Vec<(String, Value)> = match self.variables.get(index.wrapping_sub(1)).ok_or("unknown variables")? {
+            Variables::Locals(frame) => {
+                frame.locals().values()
+                     .into_iter()
+                     .enumerate()
+                     .filter_map(|(index, slot)| match slot {
+                         Slot::Value(value) => Some((index.to_string(), value)),
+                         Slot::Undefined => None,
+                     })
+                     .collect()
+            }
+            Variables::Operands(frame) => {
+                frame.stack().values()
+                     .into_iter()
+                     .enumerate()
+                     .map(|(index, value)| (index.to_string(), value))
+                     .collect()
+            }
+            Variables::Statics(class) => {
+                class.fields()
+                     .filter(|field| field.is_static())
+                     .map(|field| (field.signature().to_string(), class.static_field_value(field.signature()).unwrap()))
+                     .collect()
+            }
+            Variables::Fields(instance) => {
+                instance.class().fields()
+                        .filter(|field| !field.is_static())
+                        .map(|field| (field.signature().to_string(), instance.class().instance_field_value(instance, field.signature()).unwrap()))
+                        .collect()
+            }
+        };
+
+        let variables: Vec<_> = values.into_iter()
+                                      .map(|(name, value)| {
+                                          let reference = match &value {
+                                              Value::Reference(Reference::Instance(instance)) => self.add_variables(Variables::Fields(instance.clone())),
+                                              _ => 0,
+                                          };
+
+                                          json!({
+                                              "name": name,
+                                              "value": value.to_string(),
+                                              "type": value.value_type().to_string(),
+                                              "variablesReference": reference,
+                                          })
+                                      })
+                                      .collect();
+
+        Ok(json!({ "variables": variables }))
+    }
+
+    /// Resumes the paused program.
+    fn resume(&mut self, resume: Resume) -> Result<(), String> {
+        let vm = self.paused_vm()?;
+
+        self.resume = resume;
+        self.frames.clear();
+        self.variables.clear();
+        vm.next();
+
+        Ok(())
+    }
+
+    /// Pauses the running program on its next step.
+    fn pause(&mut self) -> Result<(), String> {
+        if self.running && self.resume != Resume::Paused {
+            self.resume = Resume::Stop("pause");
+        }
+
+        Ok(())
+    }
+
+    /// Decides whether the thread ready for the next step is stepped or stopped.
+    fn update(&mut self) -> Result<(), io::Error> {
+        let vm = match &self.vm {
+            Some(vm) => vm.clone(),
+            None => return Ok(()),
+        };
+
+        let frames = vm.frames().unwrap_or_default();
+        let depth = frames.len();
+
+        let frame = match frames.last() {
+            Some(frame) => frame,
+            None => return Ok(()),
+        };
+
+        let breakpoint = self.is_breakpoint(&vm, frame);
+
+        let reason = match self.resume {
+            Resume::Paused => return Ok(()),
+            Resume::Stop(reason) => Some(reason),
+            Resume::StepIn => Some("step"),
+            Resume::StepOver(target) if depth <= target => Some("step"),
+            Resume::StepOut(target) if depth < target => Some("step"),
+            _ if breakpoint => Some("breakpoint"),
+            _ => None,
+        };
+
+        match reason {
+            None => vm.next(),
+            Some(reason) => {
+                self.resume = Resume::Paused;
+                self.event("stopped", json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reports the error the program failed with together with its stack trace.
+    fn error(&mut self, error: ExecError, trace: StackTrace) -> Result<(), io::Error> {
+        self.failed = true;
+        self.event("output", json!({ "category": "stderr", "output": format!("error: {}\n{}", error, trace) }))
+    }
+
+    /// Reports the end of the program with its exit status.
+    fn ended(&mut self) -> Result<(), io::Error> {
+        let status = match &self.vm {
+            _ if self.failed => 1,
+            Some(vm) => vm.exit_status().unwrap_or(0),
+            None => 0,
+        };
+
+        self.running = false;
+        self.resume = Resume::Paused;
+
+        self.event("exited", json!({ "exitCode": status }))?;
+        self.event("terminated", Json::Null)
+    }
+
+    /// Stops the program if it is still running.
+    fn stop(&mut self) {
+        if let Some(vm) = &self.vm {
+            if self.running {
+                vm.stop();
+            }
+        }
+    }
+
+    /// Returns the virtual machine of the program if it is paused.
+    fn paused_vm(&self) -> Result<Arc<Vm>, String> {
+        match &self.vm {
+            Some(vm) if self.running && self.resume == Resume::Paused => Ok(vm.clone()),
+            _ => Err("the program is not paused".to_string()),
+        }
+    }
+
+    /// Returns the number of the frames of the program.
+    fn depth(&self) -> usize {
+        self.vm.as_ref().and_then(|vm| vm.frames()).map(|frames| frames.len()).unwrap_or(0)
+    }
+
+    /// Returns true if there is a breakpoint on the line of the current instruction of the frame.
+    fn is_breakpoint(&mut self, vm: &Vm, frame: &Frame) -> bool {
+        if self.breakpoints.values().all(HashSet::is_empty) {
+            return false;
+        }
+
+        let line = match frame.method().code().line(frame.pc()) {
+            Some(line) => line,
+            None => return false,
+        };
+
+        let file = self.class_files.entry(frame.class().name().clone())
+                       .or_insert_with(|| vm.class_file(frame.class().name()).map(|file| canonical(&file)));
+
+        match file {
+            Some(file) => self.breakpoints.get(file).is_some_and(|lines| lines.contains(&line)),
+            None => false,
+        }
+    }
+
+    fn add_variables(&mut self, variables: Variables) -> usize {
+        self.variables.push(variables);
+        self.variables.len()
+    }
+
+    fn respond(&mut self, request: &Json, result: Result<Json, String>) -> Result<(), io::Error> {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": result.is_ok(),
+        });
+
+        match result {
+            Ok(Json::Null) => {}
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = Json::String(message),
+        }
+
+        self.send(response)
+    }
+
+    fn event(&mut self, event: &str, body: Json) -> Result<(), io::Error> {
+        let mut message = json!({ "type": "event", "event": event });
+
+        if !body.is_null() {
+            message["body"] = body;
+        }
+
+        self.send(message)
+    }
+
+    fn send(&mut self, mut message: Json) -> Result<(), io::Error> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        write_message(&mut self.output, &message)
+    }
+}
+
+
+/// Returns the lines of the instructions of all methods of the class file.
+fn instruction_lines(file: &Path) -> Result<HashSet<usize>, String> {
+    let source = fs::read_to_string(file).map_err(|error| error.to_string())?;
+    let class: Class = ClassFileParser::new(&source).parse()
+                                                    .map_err(|error| error.to_string())?
+                                                    .try_into()
+                                                    .map_err(|error: CreateClassError| error.to_string())?;
+
+    Ok(class.methods()
+            .flat_map(|method| {
+                let code = method.code();
+                (0..code.instructions().len() as isize).filter_map(move |index| code.line(index))
+            })
+            .collect())
+}
+
+
+/// Returns the canonical form of the path, or the path itself if it does not exist.
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+    use std::fs;
+    use std::io;
+    use std::io::{BufReader, Cursor, Read, Write};
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use std::thread;
+
+    use serde_json::{json, Value as Json};
+
+    use crate::cli::dap::serve;
+    use crate::cli::protocol::{read_message, write_message};
+
+
+    const COUNT: &str = "\
+Count
+METHOD
+static int main () 1
+    ICONST_0
+    ISTORE_0
+loop:
+    IINC 0 1
+    ILOAD_0
+    ICONST_3
+    IF_ICMPLT loop
+    ILOAD_0
+    IRETURN
+END
+";
+
+
+    /// One end of an in-memory pipe.
+    struct PipeReader {
+        rx: Receiver<Vec<u8>>,
+        buffer: Cursor<Vec<u8>>,
+    }
+
+
+    impl Read for PipeReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.buffer.position() as usize == self.buffer.get_ref().len() {
+                match self.rx.recv() {
+                    Ok(data) => self.buffer = Cursor::new(data),
+                    Err(_) => return Ok(0),
+                }
+            }
+
+            self.buffer.read(buf)
+        }
+    }
+
+
+    /// The other end of an in-memory pipe.
+    struct PipeWriter(Sender<Vec<u8>>);
+
+
+    impl Write for PipeWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.send(buf.to_vec()).map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+
+    fn pipe() -> (PipeWriter, PipeReader) {
+        let (tx, rx) = channel();
+        (PipeWriter(tx), PipeReader { rx, buffer: Cursor::new(Vec::new()) })
+    }
+
+
+    /// A client sending requests and waiting for their responses and the events.
+    struct Client {
+        requests: PipeWriter,
+        messages: BufReader<PipeReader>,
+        events: VecDeque<Json>,
+        seq: i64,
+    }
+
+
+    impl Client {
+        fn request(&mut self, command: &str, args: Json) -> Json {
+            self.seq += 1;
+            write_message(&mut self.requests, &json!({ "seq": self.seq, "type": "request", "command": command, "arguments": args })).unwrap();
+
+            loop {
+                let message = read_message(&mut self.messages).unwrap().expect("response expected");
+
+                if message["type"] == "response" {
+                    assert_eq!(message["request_seq"], self.seq);
+                    assert_eq!(message["success"], true, "{}", message);
+                    return message["body"].clone();
+                }
+
+                self.events.push_back(message);
+            }
+        }
+
+        fn event(&mut self, event: &str) -> Json {
+            let message = match self.events.pop_front() {
+                Some(message) => message,
+                None => read_message(&mut self.messages).unwrap().expect("event expected"),
+            };
+
+            assert_eq!(message["event"], event, "{}", message);
+            message["body"].clone()
+        }
+
+        fn top_frame(&mut self) -> Json {
+            self.request("stackTrace", json!({ "threadId": 1 }))["stackFrames"][0].clone()
+        }
+
+        fn local(&mut self, frame: &Json, index: usize) -> Json {
+            let scopes = self.request("scopes", json!({ "frameId": frame["id"] }));
+            let locals = self.request("variables", json!({ "variablesReference": scopes["scopes"][0]["variablesReference"] }));
+            locals["variables"][index]["value"].clone()
+        }
+    }
+
+
+    #[test]
+    fn scripted_session() {
+        let class_path = std::env::temp_dir().join(format!("mvm-dap-{}", std::process::id()));
+        fs::create_dir_all(&class_path).unwrap();
+        let file = class_path.join("Count.mvm");
+        fs::write(&file, COUNT).unwrap();
+
+        let (requests, input) = pipe();
+        let (output, messages) = pipe();
+        let server = thread::spawn(move || serve(input, output));
+
+        let mut client = Client { requests, messages: BufReader::new(messages), events: VecDeque::new(), seq: 0 };
+
+        client.request("initialize", json!({ "adapterID": "mvm" }));
+        client.event("initialized");
+        client.request("launch", json!({ "classPath": [class_path], "mainClass": "Count", "stopOnEntry": true }));
+
+        let breakpoints = client.request("setBreakpoints", json!({ "source": { "path": file }, "breakpoints": [{ "line": 8 }, { "line": 6 }] }));
+        assert_eq!(breakpoints["breakpoints"][0]["verified"], true);
+        assert_eq!(breakpoints["breakpoints"][1]["verified"], false);
+
+        client.request("configurationDone", Json::Null);
+        assert_eq!(client.event("stopped")["reason"], "entry");
+        assert_eq!(client.top_frame()["line"], 4);
+
+        client.request("continue", json!({ "threadId": 1 }));
+        assert_eq!(client.event("stopped")["reason"], "breakpoint");
+        let frame = client.top_frame();
+        assert_eq!(frame["line"], 8);
+        assert_eq!(frame["name"], "Count.main ()");
+        assert_eq!(client.local(&frame, 0), "1");
+
+        client.request("next", json!({ "threadId": 1 }));
+        assert_eq!(client.event("stopped")["reason"], "step");
+        assert_eq!(client.top_frame()["line"], 9);
+
+        client.request("continue", json!({ "threadId": 1 }));
+        assert_eq!(client.event("stopped")["reason"], "breakpoint");
+        let frame = client.top_frame();
+        assert_eq!(client.local(&frame, 0), "2");
+
+        client.request("setBreakpoints", json!({ "source": { "path": file }, "breakpoints": [] }));
+        client.request("continue", json!({ "threadId": 1 }));
+        assert_eq!(client.event("exited")["exitCode"], 3);
+        client.event("terminated");
+
+        client.request("disconnect", Json::Null);
+        assert_eq!(server.join().unwrap(), 0);
+
+        fs::remove_dir_all(&class_path).unwrap();
+    }
+}
@@ -0,0 +1,637 @@
+//! A language server for `.mvm` class files speaking the Language Server Protocol
+//! over the standard input and output.
+//!
+//! The documents are checked by the class file parser and the verifier on every change.
+//! The completion offers instruction mnemonics and the classes, fields and methods
+//! found on the class path, which is given on the command line or is the workspace root.
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fs;
+use std::io;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value as Json};
+
+use crate::cli::protocol::{read_message, write_message};
+use crate::vm::bytecode::info::OperandType;
+use crate::vm::bytecode::instruction::Instruction;
+use crate::vm::class::class::Class;
+use crate::vm::class::name::{ClassName, FieldName};
+use crate::vm::class::signature::MethodSig;
+use crate::vm::exec::class_loader::ClassLoader;
+use crate::vm::exec::class_source::{ClassSource, CompositeSource, MemorySource};
+use crate::vm::parse::classfile::ClassInfo;
+use crate::vm::parse::error::CreateClassError;
+use crate::vm::parse::parser::ClassFileParser;
+use crate::vm::verify::verifier::Verifier;
+
+
+/// The completion item kinds of the protocol.
+const COMPLETION_METHOD: i64 = 2;
+const COMPLETION_FIELD: i64 = 5;
+const COMPLETION_CLASS: i64 = 7;
+const COMPLETION_KEYWORD: i64 = 14;
+const COMPLETION_OPERATOR: i64 = 24;
+
+/// The diagnostic severity of errors.
+const SEVERITY_ERROR: i64 = 1;
+
+/// The keywords starting the class file entries, the source lines and ending the methods.
+const KEYWORDS: [&str; 5] = ["FIELD", "METHOD", "END", "SOURCE", "LINE"];
+
+
+/// Serves the language server on the standard input and output with the class path
+/// and returns the process exit status.
+pub fn lsp(class_path: Vec<PathBuf>) -> i32 {
+    let stdin = io::stdin();
+    serve(stdin.lock(), io::stdout(), class_path)
+}
+
+
+/// Serves the language server, reading the messages from the input and writing
+/// the responses and notifications to the output, until the client sends `exit`.
+/// Returns the process exit status, which is 0 only if the server was shut down first.
+pub fn serve(mut input: impl BufRead, output: impl Write, class_path: Vec<PathBuf>) -> i32 {
+    let mut server = LanguageServer::new(output, class_path);
+
+    loop {
+        let message = match read_message(&mut input) {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            Err(error) => {
+                eprintln!("error: can not read the message: {}", error);
+                break;
+            }
+        };
+
+        match server.handle(&message) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(error) => {
+                eprintln!("error: can not write the response: {}", error);
+                return 1;
+            }
+        }
+    }
+
+    if server.shut_down { 0 } else { 1 }
+}
+
+
+/// The state of the language server.
+struct LanguageServer<W: Write> {
+    output: W,
+    class_path: Vec<PathBuf>,
+    documents: HashMap<String, String>,
+    shut_down: bool,
+}
+
+
+impl<W: Write> LanguageServer<W> {
+    fn new(output: W, class_path: Vec<PathBuf>) -> Self {
+        LanguageServer {
+            output,
+            class_path,
+            documents: HashMap::new(),
+            shut_down: false,
+        }
+    }
+
+    /// Handles the request or the notification.
+    /// Returns false if the server exits.
+    fn handle(&mut self, message: &Json) -> Result<bool, io::Error> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+
+        let result = match method {
+            "initialize" => self.initialize(params),
+            "shutdown" => {
+                self.shut_down = true;
+                Json::Null
+            }
+            "exit" => return Ok(false),
+            "textDocument/didOpen" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.update(uri, text.to_string())?;
+                return Ok(true);
+            }
+            "textDocument/didChange" => {
+                // the whole document is synchronized, so the last change is the text
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let text = params["contentChanges"].as_array()
+                                                   .and_then(|changes| changes.last())
+                                                   .and_then(|change| change["text"].as_str())
+                                                   .unwrap_or_default();
+                self.update(uri, text.to_string())?;
+                return Ok(true);
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                self.documents.remove(uri);
+                self.notify("textDocument/publishDiagnostics", json!({ "uri": uri, "diagnostics": [] }))?;
+                return Ok(true);
+            }
+            "textDocument/completion" => self.completion(params),
+            "textDocument/hover" => self.hover(params),
+            "textDocument/definition" => self.definition(params),
+            _ if message["id"].is_null() => return Ok(true),
+            _ => {
+                let response = json!({
+                    "jsonrpc": "2.0",
+                    "id": message["id"],
+                    "error": { "code": -32601, "message": format!("unsupported method: {}", method) },
+                });
+                write_message(&mut self.output, &response)?;
+                return Ok(true);
+            }
+        };
+
+        let response = json!({ "jsonrpc": "2.0", "id": message["id"], "result": result });
+        write_message(&mut self.output, &response)?;
+        Ok(true)
+    }
+
+    /// Uses the workspace root as the class path if none was given and returns the capabilities.
+    fn initialize(&mut self, params: &Json) -> Json {
+        if self.class_path.is_empty() {
+            let root = params["rootUri"].as_str()
+                                        .and_then(uri_to_path)
+                                        .or_else(|| params["rootPath"].as_str().map(PathBuf::from));
+
+            self.class_path.extend(root);
+        }
+
+        json!({
+            "capabilities": {
+                "textDocumentSync": 1,
+                "completionProvider": { "triggerCharacters": [" "] },
+                "hoverProvider": true,
+                "definitionProvider": true,
+            },
+            "serverInfo": { "name": "mvm" },
+        })
+    }
+
+    /// Stores the text of the document and publishes its diagnostics.
+    fn update(&mut self, uri: &str, text: String) -> Result<(), io::Error> {
+        let diagnostics = diagnostics(&text);
+        self.documents.insert(uri.to_string(), text);
+        self.notify("textDocument/publishDiagnostics", json!({ "uri": uri, "diagnostics": diagnostics }))
+    }
+
+    /// Completes the mnemonic at the start of a line, or the class or the member
+    /// referenced by the instruction.
+    fn completion(&self, params: &Json) -> Json {
+        let (line, column) = match self.position(params) {
+            Some(position) => position,
+            None => return json!([]),
+        };
+
+        let typed: String = line.chars().take(column).collect();
+        let tokens = instruction_tokens(&typed);
+
+        // the index of the token being typed
+        let index = if typed.ends_with(char::is_whitespace) || tokens.is_empty() {
+            tokens.len()
+        } else {
+            tokens.len() - 1
+        };
+
+        if index == 0 {
+            let keywords = KEYWORDS.iter().map(|keyword| json!({ "label": keyword, "kind": COMPLETION_KEYWORD }));
+            let mnemonics = Instruction::infos().iter().map(|info| {
+                json!({ "label": info.mnemonic(), "kind": COMPLETION_OPERATOR, "detail": info.description() })
+            });
+
+            return keywords.chain(mnemonics).collect();
+        }
+
+        let loader = self.class_loader();
+
+        match (tokens[0], index) {
+            ("NEW", 1) | ("GETSTATIC", 2) | ("PUTSTATIC", 2) | ("GETFIELD", 2) | ("PUTFIELD", 2) |
+            ("INVOKESTATIC", 2) | ("INVOKEVIRTUAL", 2) => {
+                let mut class_names = loader.class_names().unwrap_or_default();
+                class_names.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+                class_names.dedup();
+
+                class_names.iter()
+                           .map(|name| json!({ "label": name.as_ref(), "kind": COMPLETION_CLASS }))
+                           .collect()
+            }
+            (mnemonic, 3) => {
+                let class = match ClassName::new(tokens[2]).ok().and_then(|name| loader.load(&name).ok()) {
+                    Some(class) => class,
+                    None => return json!([]),
+                };
+
+                member_completion(&class, mnemonic)
+            }
+            _ => json!([]),
+        }
+    }
+
+    /// Describes the instruction under the cursor by its metadata.
+    fn hover(&self, params: &Json) -> Json {
+        let (line, column) = match self.position(params) {
+            Some(position) => position,
+            None => return Json::Null,
+        };
+
+        let tokens = instruction_tokens(line);
+        let mnemonic = match tokens.first() {
+            Some(mnemonic) => *mnemonic,
+            None => return Json::Null,
+        };
+
+        let start = line.find(mnemonic).map(|offset| line[..offset].chars().count()).unwrap_or(0);
+
+        if column < start || column > start + mnemonic.chars().count() {
+            return Json::Null;
+        }
+
+        let info = match Instruction::info_of(mnemonic) {
+            Some(info) => info,
+            None => return Json::Null,
+        };
+
+        let mut value = format!("**{}** ({:#04x}, {})\n\n{}", mnemonic, info.opcode(), info.category(), info.description());
+
+        if !info.operands().is_empty() {
+            let operands: Vec<String> = info.operands().iter().map(|operand| operand.to_string()).collect();
+            value.push_str(&format!("\n\nOperands: {}", operands.join(", ")));
+        }
+
+        // labels can not be resolved on a single line, but the target does not change the stack effect
+        let instruction = match ClassFileParser::new(&tokens.join(" ")).parse_single_instruction() {
+            Err(_) if info.operands().contains(&OperandType::BranchOffset) => {
+                ClassFileParser::new(&format!("{} 0", mnemonic)).parse_single_instruction()
+            }
+            instruction => instruction,
+        };
+
+        if let Some(effect) = instruction.ok().and_then(|instruction| instruction.stack_effect()) {
+            value.push_str(&format!("\n\nStack: `{}`", effect));
+        }
+
+        json!({ "contents": { "kind": "markdown", "value": value } })
+    }
+
+    /// Returns the location of the `FIELD` or `METHOD` entry referenced by the instruction
+    /// on the line, or of the class created by `NEW`.
+    fn definition(&self, params: &Json) -> Json {
+        let line = match self.position(params) {
+            Some((line, _)) => line,
+            None => return Json::Null,
+        };
+
+        let instruction = instruction_tokens(line).join(" ");
+
+        let (class_name, target) = match ClassFileParser::new(&instruction).parse_single_instruction() {
+            Ok(Instruction::GETSTATIC(field_ref)) | Ok(Instruction::PUTSTATIC(field_ref)) |
+            Ok(Instruction::GETFIELD(field_ref)) | Ok(Instruction::PUTFIELD(field_ref)) => {
+                (field_ref.class_name().clone(), Definition::Field(field_ref.signature().name().clone()))
+            }
+            Ok(Instruction::INVOKESTATIC(method_ref)) | Ok(Instruction::INVOKEVIRTUAL(method_ref)) => {
+                (method_ref.class_name().clone(), Definition::Method(method_ref.signature().clone()))
+            }
+            Ok(Instruction::NEW(class_name)) => (class_name, Definition::Class),
+            _ => return Json::Null,
+        };
+
+        let file = match self.class_loader().class_file(&class_name) {
+            Some(file) => file,
+            None => return Json::Null,
+        };
+
+        let uri = path_to_uri(&file);
+
+        let text = match self.documents.get(&uri) {
+            Some(text) => text.clone(),
+            None => match fs::read_to_string(&file) {
+                Ok(text) => text,
+                Err(_) => return Json::Null,
+            },
+        };
+
+        let line = match ClassFileParser::new(&text).parse().ok().and_then(|info| target.line(&info)) {
+            Some(line) => line,
+            None => return Json::Null,
+        };
+
+        json!({ "uri": uri, "range": range(line, 1, 0) })
+    }
+
+    /// Returns the line and the character of the position in the document of the request.
+    fn position<'a>(&'a self, params: &Json) -> Option<(&'a str, usize)> {
+        let text = self.documents.get(params["textDocument"]["uri"].as_str()?)?;
+        let line = text.lines().nth(params["position"]["line"].as_u64()? as usize).unwrap_or("");
+        let column = params["position"]["character"].as_u64()? as usize;
+
+        Some((line, column))
+    }
+
+    /// Returns the class loader reading the open documents first, then the class path.
+    fn class_loader(&self) -> ClassLoader {
+        let mut documents = MemorySource::default();
+
+        for text in self.documents.values() {
+            let name = text.lines()
+                           .map(|line| line.split("//").next().unwrap().trim())
+                           .find(|line| !line.is_empty())
+                           .and_then(|line| ClassName::new(line).ok());
+
+            if let Some(name) = name {
+                documents = documents.with_class(name, text.clone());
+            }
+        }
+
+        ClassLoader::new(DocumentSource { documents, class_path: CompositeSource::class_path(self.class_path.clone()) })
+    }
+
+    fn notify(&mut self, method: &str, params: Json) -> Result<(), io::Error> {
+        write_message(&mut self.output, &json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+    }
+}
+
+
+/// A definition referenced by an instruction.
+enum Definition {
+    Field(FieldName),
+    Method(MethodSig),
+    Class,
+}
+
+
+impl Definition {
+    /// Returns the line of the definition in the class file.
+    fn line(&self, info: &ClassInfo) -> Option<usize> {
+        match self {
+            Definition::Field(name) => info.field_line(name),
+            Definition::Method(signature) => info.method_line(signature),
+            Definition::Class => Some(1),
+        }
+    }
+}
+
+
+/// A class source reading the open documents before the class path,
+/// but locating the class files on the class path.
+struct DocumentSource {
+    documents: MemorySource,
+    class_path: CompositeSource,
+}
+
+
+impl ClassSource for DocumentSource {
+    fn read(&self, name: &ClassName) -> Result<Option<String>, io::Error> {
+        match self.documents.read(name)? {
+            Some(source) => Ok(Some(source)),
+            None => self.class_path.read(name),
+        }
+    }
+
+    fn class_names(&self) -> Result<Vec<ClassName>, io::Error> {
+        let mut names = self.documents.class_names()?;
+        names.extend(self.class_path.class_names()?);
+        Ok(names)
+    }
+
+    fn entry(&self, name: &ClassName) -> Option<PathBuf> {
+        self.class_path.entry(name)
+    }
+
+    fn source_file(&self, name: &ClassName) -> Option<PathBuf> {
+        self.class_path.source_file(name)
+    }
+}
+
+
+/// Returns the diagnostics of the parse errors of the class file,
+/// or of the verification error if it parses.
+fn diagnostics(text: &str) -> Vec<Json> {
+    let info = match ClassFileParser::new(text).parse() {
+        Ok(info) => info,
+        Err(errors) => {
+            return errors.errors()
+                         .iter()
+                         .map(|error| {
+                             let span = error.span();
+                             diagnostic(range(span.line(), span.column(), span.width()), error.kind().to_string())
+                         })
+                         .collect();
+        }
+    };
+
+    let class: Result<Class, CreateClassError> = info.try_into();
+
+    let class = match class {
+        Ok(class) => class,
+        Err(error) => return vec![diagnostic(range(1, 1, 0), error.to_string())],
+    };
+
+    match Verifier::new(&class).verify() {
+        Ok(()) => Vec::new(),
+        Err(error) => {
+            let line = class.methods()
+                            .find(|method| method.signature() == error.method())
+                            .and_then(|method| method.code().line(error.pc() as isize))
+                            .unwrap_or(1);
+
+            vec![diagnostic(range(line, 1, 0), format!("{}: {}", error.instruction(), error.kind()))]
+        }
+    }
+}
+
+
+fn diagnostic(range: Json, message: String) -> Json {
+    json!({ "range": range, "severity": SEVERITY_ERROR, "source": "mvm", "message": message })
+}
+
+
+/// Returns the range of the given width starting at the line and the column counted from 1.
+fn range(line: usize, column: usize, width: usize) -> Json {
+    let (line, column) = (line.saturating_sub(1), column.saturating_sub(1));
+
+    json!({
+        "start": { "line": line, "character": column },
+        "end": { "line": line, "character": column + width },
+    })
+}
+
+
+/// Returns the completion of the fields or methods of the class accessed by the instruction.
+fn member_completion(class: &Class, mnemonic: &str) -> Json {
+    let items: Vec<Json> = match mnemonic {
+        "GETSTATIC" | "PUTSTATIC" | "GETFIELD" | "PUTFIELD" => {
+            let is_static = mnemonic.ends_with("STATIC");
+
+            class.fields()
+                 .filter(|field| field.is_static() == is_static)
+                 .map(|field| json!({
+                     "label": field.signature().name().as_ref(),
+                     "kind": COMPLETION_FIELD,
+                     "detail": field.signature().to_string(),
+                 }))
+                 .collect()
+        }
+        "INVOKESTATIC" | "INVOKEVIRTUAL" => {
+            let is_static = mnemonic == "INVOKESTATIC";
+
+            class.methods()
+                 .filter(|method| method.is_static() == is_static)
+                 .map(|method| {
+                     let signature = method.signature();
+
+                     json!({
+                         "label": signature.name().as_ref(),
+                         "kind": COMPLETION_METHOD,
+                         "detail": signature.to_string(),
+                         "insertText": format!("{} ({})", signature.name(), signature.params_desc()),
+                     })
+                 })
+                 .collect()
+        }
+        _ => Vec::new(),
+    };
+
+    Json::Array(items)
+}
+
+
+/// Returns the tokens of the instruction on the line, without the comment and the label.
+fn instruction_tokens(line: &str) -> Vec<&str> {
+    let code = line.split("//").next().unwrap();
+    let mut tokens: Vec<_> = code.split_whitespace().collect();
+
+    if tokens.first().is_some_and(|token| token.ends_with(':')) {
+        tokens.remove(0);
+    }
+
+    tokens
+}
+
+
+/// Returns the path of the `file` URI.
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::new();
+    let mut chars = path.bytes();
+
+    while let Some(byte) = chars.next() {
+        match byte {
+            b'%' => {
+                let hex = [chars.next()?, chars.next()?];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            _ => bytes.push(byte),
+        }
+    }
+
+    String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+
+/// Returns the `file` URI of the path.
+fn path_to_uri(path: &Path) -> String {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let mut uri = String::from("file://");
+
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => uri.push(byte as char),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    uri
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::io::Cursor;
+
+    use serde_json::{json, Value as Json};
+
+    use crate::cli::lsp::{path_to_uri, serve};
+    use crate::cli::protocol::{read_message, write_message};
+
+
+    const SHAPES: &str = "\
+Shapes
+FIELD
+static int count
+METHOD
+static int area (int,int) 2
+    ILOAD_0
+    ILOAD_1
+    IMUL
+    IRETURN
+END
+";
+
+
+    #[test]
+    fn session() {
+        let class_path = std::env::temp_dir().join(format!("mvm-lsp-{}", std::process::id()));
+        fs::create_dir_all(&class_path).unwrap();
+        fs::write(class_path.join("Shapes.mvm"), SHAPES).unwrap();
+
+        let uri = path_to_uri(&class_path) + "/Main.mvm";
+        let text = "Main\nMETHOD\nstatic int main () 0\n    ICONST_2\n    ICONST_3\n    INVOKESTATIC int Shapes area (int,int)\n    GETSTATIC int Shapes count\n    IADD\n    FRETURN\nEND\n";
+        let position = |line: usize, character: usize| json!({ "textDocument": { "uri": uri }, "position": { "line": line, "character": character } });
+
+        let messages = vec![
+            json!({ "id": 1, "method": "initialize", "params": { "rootUri": path_to_uri(&class_path) } }),
+            json!({ "method": "textDocument/didOpen", "params": { "textDocument": { "uri": uri, "text": text } } }),
+            json!({ "id": 2, "method": "textDocument/hover", "params": position(7, 5) }),
+            json!({ "id": 3, "method": "textDocument/definition", "params": position(5, 20) }),
+            json!({ "id": 4, "method": "textDocument/definition", "params": position(6, 20) }),
+            json!({ "method": "textDocument/didChange", "params": { "textDocument": { "uri": uri }, "contentChanges": [{ "text": "Main\nMETHOD\nstatic void main () 0\n    IADDD\nEND\n" }] } }),
+            json!({ "id": 5, "method": "textDocument/completion", "params": position(3, 7) }),
+            json!({ "id": 6, "method": "shutdown" }),
+            json!({ "method": "exit" }),
+        ];
+
+        let mut input = Vec::new();
+        for message in &messages {
+            write_message(&mut input, message).unwrap();
+        }
+
+        let mut output = Vec::new();
+        assert_eq!(serve(Cursor::new(input), &mut output, Vec::new()), 0);
+
+        let mut output = Cursor::new(output);
+        let mut responses = Vec::new();
+        while let Some(message) = read_message(&mut output).unwrap() {
+            responses.push(message);
+        }
+
+        let result = |id: i64| responses.iter().find(|response| response["id"] == id).unwrap()["result"].clone();
+        let diagnostics: Vec<Json> = responses.iter()
+                                              .filter(|message| message["method"] == "textDocument/publishDiagnostics")
+                                              .map(|message| message["params"]["diagnostics"].clone())
+                                              .collect();
+
+        assert_eq!(result(1)["capabilities"]["hoverProvider"], true);
+
+        // the verifier rejects returning an int by FRETURN
+        assert_eq!(diagnostics[0][0]["range"]["start"]["line"], 8);
+        assert!(diagnostics[0][0]["message"].as_str().unwrap().starts_with("FRETURN"));
+
+        assert_eq!(result(2)["contents"]["value"], "**IADD** (0x60, arithmetic)\n\nAdd two ints.\n\nStack: `…, int, int → …, int`");
+        assert_eq!(result(3)["range"]["start"]["line"], 3);
+        assert_eq!(result(4)["range"]["start"]["line"], 1);
+        assert!(result(3)["uri"].as_str().unwrap().ends_with("/Shapes.mvm"));
+
+        assert_eq!(diagnostics[1][0]["range"]["start"], json!({ "line": 3, "character": 4 }));
+        assert!(result(5).as_array().unwrap().iter().any(|item| item["label"] == "IADD"));
+
+        fs::remove_dir_all(&class_path).unwrap();
+    }
+}
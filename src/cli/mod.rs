@@ -0,0 +1,150 @@
+//! Command line interface.
+
+use std::path::PathBuf;
+
+use crate::vm::class::name::ClassName;
+use crate::vm::exec::config::VmConfig;
+
+mod bench;
+mod check;
+mod dap;
+mod lsp;
+mod protocol;
+mod repl;
+mod run;
+
+
+const USAGE: &str = "\
+Usage:
+    mvm                                  start the graphical interface
+    mvm run [options] <class path> <main class> [arguments]
+                                         run the main class without the graphical interface
+    mvm check <class path> [main class]  verify all classes on the class path and report warnings
+    mvm bench <class path>               compare the speed of stepping and interpreting the main classes
+    mvm repl [class path]                execute instructions typed in one by one on a scratch frame
+    mvm dap                              serve the Debug Adapter Protocol on the standard input and output
+    mvm lsp [class path]                 serve the Language Server Protocol for .mvm files on the standard input and output
+
+The class path is a list of directories and .zip or .jar archives separated by ':'.
+The arguments are passed to the parameters of the main method, which may return
+the int exit status of the program or end it by calling System.exit (int).
+
+Run options:
+    --max-objects <n>    fail when the program allocates more than n objects
+    --max-bytes <n>      fail when the objects of the program take more than n bytes
+    --max-depth <n>      fail when the program has more than n nested calls
+    --max-stack <n>      fail when an operand stack holds more than n slots
+    --stats              print the allocations of every class when the program ends
+    --heap-dump <file>   write the heap when the program ends, as DOT for .dot or .gv files, otherwise as JSON";
+
+
+/// Runs the command given by the command line arguments (without the program name)
+/// and returns the process exit status.
+pub fn main(args: Vec<String>) -> i32 {
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["run", args @ ..] => run_command(args),
+        ["check", class_path] => check::check(parse_class_path(class_path), None),
+        ["check", class_path, main_class] => {
+            let main_class = match ClassName::new(*main_class) {
+                Ok(main_class) => main_class,
+                Err(error) => {
+                    eprintln!("error: {}", error);
+                    return 2;
+                }
+            };
+
+            check::check(parse_class_path(class_path), Some(main_class))
+        }
+        ["bench", class_path] => bench::bench(parse_class_path(class_path)),
+        ["repl"] => repl::repl(Vec::new()),
+        ["repl", class_path] => repl::repl(parse_class_path(class_path)),
+        ["dap"] => dap::dap(),
+        ["lsp"] => lsp::lsp(Vec::new()),
+        ["lsp", class_path] => lsp::lsp(parse_class_path(class_path)),
+        ["help"] | ["--help"] | ["-h"] => {
+            println!("{}", USAGE);
+            0
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            2
+        }
+    }
+}
+
+
+/// Parses the options and arguments of the run command and runs it.
+fn run_command(args: &[&str]) -> i32 {
+    let mut args = args.iter();
+    let mut positional = Vec::new();
+    let mut limits: Vec<(&str, usize)> = Vec::new();
+    let mut stats = false;
+    let mut heap_dump = None;
+
+    while let Some(arg) = args.next() {
+        // everything after the main class is a program argument
+        if positional.len() >= 2 {
+            positional.push(*arg);
+            continue;
+        }
+
+        match *arg {
+            "--stats" => stats = true,
+            "--heap-dump" => match args.next() {
+                Some(path) => heap_dump = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("error: --heap-dump expects a file");
+                    return 2;
+                }
+            },
+            "--max-objects" | "--max-bytes" | "--max-depth" | "--max-stack" => {
+                match args.next().map(|value| value.parse()) {
+                    Some(Ok(value)) => limits.push((arg, value)),
+                    _ => {
+                        eprintln!("error: {} expects a number", arg);
+                        return 2;
+                    }
+                }
+            }
+            _ => positional.push(*arg),
+        }
+    }
+
+    let (class_path, main_class, program_args) = match positional.as_slice() {
+        [class_path, main_class, program_args @ ..] => (class_path, main_class, program_args),
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+
+    let main_class = match ClassName::new(*main_class) {
+        Ok(main_class) => main_class,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            return 2;
+        }
+    };
+
+    let mut config = VmConfig::new();
+
+    for (option, value) in limits {
+        config = match option {
+            "--max-objects" => config.with_max_heap_objects(Some(value)),
+            "--max-bytes" => config.with_max_heap_bytes(Some(value)),
+            "--max-depth" => config.with_max_frame_depth(value),
+            _ => config.with_max_operand_stack(value),
+        };
+    }
+
+    run::run(parse_class_path(class_path), config, main_class, program_args, stats, heap_dump.as_deref())
+}
+
+
+/// Splits the class path into its entries.
+fn parse_class_path(class_path: &str) -> Vec<PathBuf> {
+    class_path.split(':')
+              .filter(|entry| !entry.is_empty())
+              .map(PathBuf::from)
+              .collect()
+}
@@ -0,0 +1,100 @@
+//! The base protocol shared by the debug adapter and the language server:
+//! JSON messages preceded by a `Content-Length` header.
+
+use std::io;
+use std::io::{BufRead, Write};
+
+use serde_json::Value;
+
+
+/// The largest accepted content, larger messages are rejected before their content is read.
+const MAX_CONTENT_LENGTH: usize = 16 * 1024 * 1024;
+
+
+/// Reads the next message, returns `None` at the end of the input.
+///
+/// # Errors
+///
+/// Returns `io::Error` if the input can not be read, the header has no content length,
+/// the content is longer than 16 MiB or it is not a valid JSON.
+pub fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>, io::Error> {
+    let mut length = None;
+    let mut has_header = false;
+
+    loop {
+        let mut header = String::new();
+
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim_end();
+
+        if header.is_empty() {
+            // a blank line between messages
+            if !has_header {
+                continue;
+            }
+
+            break;
+        }
+
+        has_header = true;
+
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = Some(value.trim().parse::<usize>().map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?);
+        }
+    }
+
+    let length = length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header"))?;
+
+    if length > MAX_CONTENT_LENGTH {
+        return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                  format!("content length {} exceeds the limit of {} bytes", length, MAX_CONTENT_LENGTH)));
+    }
+
+    let mut content = vec![0; length];
+    reader.read_exact(&mut content)?;
+
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+
+/// Writes the message with its header and flushes the output.
+///
+/// # Errors
+///
+/// Returns `io::Error` if the output can not be written.
+pub fn write_message(writer: &mut impl Write, message: &Value) -> Result<(), io::Error> {
+    let content = message.to_string();
+
+    write!(writer, "Content-Length: {}\r\n\r\n{}", content.len(), content)?;
+    writer.flush()
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use std::io;
+
+    use serde_json::json;
+
+    use crate::cli::protocol::read_message;
+
+
+    #[test]
+    fn read() {
+        let mut input = "\r\nContent-Length: 9\r\n\r\n{\"id\": 1}".as_bytes();
+        assert_eq!(read_message(&mut input).unwrap(), Some(json!({ "id": 1 })));
+        assert_eq!(read_message(&mut input).unwrap(), None);
+
+        let mut input = "Content-Type: application/json\r\n\r\n{}".as_bytes();
+        assert_eq!(read_message(&mut input).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        let mut input = "Content-Length: 4294967296\r\n\r\n{}".as_bytes();
+        assert_eq!(read_message(&mut input).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
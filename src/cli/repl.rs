@@ -0,0 +1,132 @@
+use std::io;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::vm::bytecode::instruction::Instruction;
+use crate::vm::class::class::Class;
+use crate::vm::class::code::Code;
+use crate::vm::class::descriptor::{ParamsDesc, ReturnDesc};
+use crate::vm::class::method::Method;
+use crate::vm::class::name::{ClassName, MethodName};
+use crate::vm::class::signature::MethodSig;
+use crate::vm::exec::class_source::CompositeSource;
+use crate::vm::exec::thread::Thread;
+use crate::vm::exec::vm::Vm;
+use crate::vm::memory::frame::Frame;
+use crate::vm::memory::locals::Slot;
+use crate::vm::parse::parser::ClassFileParser;
+
+
+/// The number of the local variables of the scratch frame.
+const LOCALS_SIZE: usize = 16;
+
+const HELP: &str = "\
+Type an instruction to execute it on the scratch frame, e.g. ICONST_1 or INVOKESTATIC int Test f (int).
+Branch targets are offsets, which are executed, but do not jump anywhere.
+    :reset    start with an empty frame
+    :quit     quit";
+
+
+/// Reads instruction lines from the standard input, executes them on a scratch frame
+/// and prints the frame after each of them. Classes are loaded from the class path.
+/// Returns the process exit status.
+pub fn repl(class_path: Vec<PathBuf>) -> i32 {
+    let vm = Arc::new(Vm::new(CompositeSource::class_path(class_path)));
+    let mut thread = Thread::detached(vm.clone(), scratch_frame(&vm));
+
+    println!("{}", HELP);
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    loop {
+        print!("> ");
+        io::stdout().flush().ok();
+
+        let line = match lines.next() {
+            None => break,
+            Some(Ok(line)) => line,
+            Some(Err(error)) => {
+                eprintln!("error: can not read the input: {}", error);
+                return 1;
+            }
+        };
+
+        match line.trim() {
+            "" => continue,
+            ":quit" => break,
+            ":help" => {
+                println!("{}", HELP);
+                continue;
+            }
+            ":reset" => {
+                thread = Thread::detached(vm.clone(), scratch_frame(&vm));
+                print_frame(&thread);
+                continue;
+            }
+            _ => {}
+        }
+
+        let instruction = match ClassFileParser::new(&line).parse_single_instruction() {
+            Ok(instruction) => instruction,
+            Err(errors) => {
+                for error in errors.errors() {
+                    print!("{}", error.render());
+                }
+                continue;
+            }
+        };
+
+        if let Err(error) = thread.evaluate(&instruction) {
+            println!("error: {}", error);
+        }
+
+        // the status is taken, so it is reported only for the instruction which exited
+        let exit_status = vm.take_exit_status();
+
+        if thread.stack().is_empty() {
+            match exit_status {
+                Some(status) => println!("exited with status {}, starting with an empty frame", status),
+                None => println!("returned from the frame, starting with an empty frame"),
+            }
+
+            thread = Thread::detached(vm.clone(), scratch_frame(&vm));
+        }
+
+        print_frame(&thread);
+    }
+
+    0
+}
+
+
+/// Creates an empty frame of a static void method without any code.
+fn scratch_frame(vm: &Vm) -> Frame {
+    let signature = MethodSig::new(ReturnDesc::Void, MethodName::new("repl").unwrap(), ParamsDesc::empty()).unwrap();
+    let code = Code::new(LOCALS_SIZE, vec![Instruction::RETURN], Vec::new()).unwrap();
+    let method = Method::new(signature, true, code).unwrap();
+    let class = Arc::new(Class::new(ClassName::new("Repl").unwrap(), Vec::new(), vec![method]).unwrap());
+    let method = class.methods().next().unwrap().clone();
+
+    Frame::new(class, method, vm.config().max_operand_stack())
+}
+
+
+/// Prints the operand stack, the top value last, and the defined local variables.
+fn print_frame(thread: &Thread) {
+    let frame = thread.stack().current().unwrap();
+
+    let stack: Vec<_> = frame.stack().values().iter().map(|value| value.to_string()).collect();
+    println!("stack:  [{}]", stack.join(", "));
+
+    let locals: Vec<_> = frame.locals().values()
+                              .iter()
+                              .enumerate()
+                              .filter_map(|(index, slot)| match slot {
+                                  Slot::Value(value) => Some(format!("{}: {}", index, value)),
+                                  Slot::Undefined => None,
+                              })
+                              .collect();
+    println!("locals: [{}]", locals.join(", "));
+}
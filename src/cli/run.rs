@@ -0,0 +1,112 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::vm::class::descriptor::TypeDesc;
+use crate::vm::class::name::ClassName;
+use crate::vm::exec::class_source::CompositeSource;
+use crate::vm::exec::config::VmConfig;
+use crate::vm::exec::heap_dump::HeapDump;
+use crate::vm::exec::interpreter::Interpreter;
+use crate::vm::exec::stack_trace::StackTrace;
+use crate::vm::exec::vm::Vm;
+use crate::vm::types::double::Double;
+use crate::vm::types::float::Float;
+use crate::vm::types::int::Int;
+use crate::vm::types::long::Long;
+use crate::vm::types::value::Value;
+
+
+/// Runs the main class with the program arguments to the end without any interaction
+/// and returns the process exit status, which is the status the program exited with or 0.
+/// If the program fails, the error is printed together with the stack trace.
+/// If `stats` is true, the allocations are printed when the program ends.
+/// If `heap_dump` is given, the heap is written to it when the program ends.
+pub fn run(class_path: Vec<PathBuf>, config: VmConfig, main_class: ClassName, args: &[&str], stats: bool, heap_dump: Option<&Path>) -> i32 {
+    let vm = Arc::new(Vm::with_config(CompositeSource::class_path(class_path), config));
+    let mut interpreter = Interpreter::new(vm.clone());
+
+    let args = match parse_args(&vm, &main_class, args) {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            return 2;
+        }
+    };
+
+    let status = match interpreter.run_main(&main_class, args) {
+        Ok(()) => vm.exit_status().unwrap_or(0),
+        Err(error) => {
+            let trace = interpreter.snapshot().map(|frames| StackTrace::capture(&frames)).unwrap_or_default();
+            eprint!("error: {}\n{}", error, trace);
+            1
+        }
+    };
+
+    if stats {
+        print_allocations(&vm);
+    }
+
+    if let Some(path) = heap_dump {
+        let frames = match interpreter.snapshot() {
+            Ok(frames) => frames,
+            Err(error) => {
+                eprintln!("error: can not capture the frames for the heap dump: {}", error);
+                return 1;
+            }
+        };
+
+        if let Err(error) = HeapDump::with_frames(&vm, &frames).write(path) {
+            eprintln!("error: can not write the heap dump: {}", error);
+            return 1;
+        }
+    }
+
+    status
+}
+
+
+/// Parses the program arguments as the values of the parameters of the main method.
+pub(super) fn parse_args(vm: &Vm, main_class: &ClassName, args: &[&str]) -> Result<Vec<Value>, String> {
+    let class = vm.resolve_class(main_class).map_err(|error| error.to_string())?;
+    let method = class.main_method().map_err(|error| error.to_string())?;
+    let params = method.signature().params_desc().type_descs();
+
+    if params.len() != args.len() {
+        return Err(format!("the main method {} expects {} arguments, got {}", method.signature(), params.len(), args.len()));
+    }
+
+    params.iter()
+          .zip(args)
+          .map(|(type_desc, arg)| {
+              let invalid = || format!("'{}' is not a valid {}", arg, type_desc);
+
+              Ok(match type_desc {
+                  TypeDesc::Int => Int::new(arg.parse().map_err(|_| invalid())?).into(),
+                  TypeDesc::Long => Long::new(arg.parse().map_err(|_| invalid())?).into(),
+                  TypeDesc::Float => Float::new(arg.parse().map_err(|_| invalid())?).into(),
+                  TypeDesc::Double => Double::new(arg.parse().map_err(|_| invalid())?).into(),
+                  TypeDesc::Reference(_) => return Err(invalid()),
+              })
+          })
+          .collect()
+}
+
+
+/// Prints the number and size of the allocated objects of every class.
+fn print_allocations(vm: &Vm) {
+    let mut allocations: Vec<_> = vm.allocations().into_iter().collect();
+    allocations.sort_by_key(|(class_name, _)| class_name.to_string());
+
+    eprintln!("{:<32} {:>10} {:>12}", "class", "objects", "bytes");
+
+    for (class_name, allocation) in &allocations {
+        eprintln!("{:<32} {:>10} {:>12}", class_name.to_string(), allocation.count(), allocation.bytes());
+    }
+
+    eprintln!(
+        "{:<32} {:>10} {:>12}",
+        "total",
+        allocations.iter().map(|(_, allocation)| allocation.count()).sum::<usize>(),
+        allocations.iter().map(|(_, allocation)| allocation.bytes()).sum::<usize>(),
+    );
+}
@@ -0,0 +1,176 @@
+//! Changes of the displayed values made by the last executed instruction.
+
+use gtk::{CellLayoutExt, CellRendererText, TreeView, TreeViewExt};
+use gtk::prelude::ObjectExt;
+
+
+/// The model column with the background color of a row.
+pub const BACKGROUND_COLUMN: u32 = 3;
+
+/// The model column telling whether the row shows a removed entry.
+pub const REMOVED_COLUMN: u32 = 4;
+
+
+/// A change of a displayed entry since the previous step.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Change {
+    Unchanged,
+    New,
+    Changed,
+    Removed,
+}
+
+
+impl Change {
+    /// Returns the background color of a row with the change.
+    pub fn background(self) -> Option<&'static str> {
+        match self {
+            Change::Unchanged => None,
+            Change::New => Some("#d6f5d6"),
+            Change::Changed => Some("#fff2b3"),
+            Change::Removed => Some("#f5d6d6"),
+        }
+    }
+
+    pub fn is_removed(self) -> bool {
+        self == Change::Removed
+    }
+}
+
+
+/// Compares the current entries with the previous ones by their keys.
+/// The removed entries follow the current ones. Without the previous entries
+/// nothing is marked as changed.
+pub fn diff<K, V>(previous: Option<&[(K, V)]>, current: &[(K, V)]) -> Vec<(K, V, Change)>
+    where K: PartialEq + Clone, V: PartialEq + Clone {
+    let previous = match previous {
+        Some(previous) => previous,
+        None => return current.iter().map(|(key, value)| (key.clone(), value.clone(), Change::Unchanged)).collect(),
+    };
+
+    let current_entries = current.iter().map(|(key, value)| {
+        let change = match previous.iter().find(|(previous_key, _)| previous_key == key) {
+            None => Change::New,
+            Some((_, previous_value)) if previous_value != value => Change::Changed,
+            Some(_) => Change::Unchanged,
+        };
+        (key.clone(), value.clone(), change)
+    });
+
+    let removed_entries = previous.iter()
+                                  .filter(|(key, _)| !current.iter().any(|(current_key, _)| current_key == key))
+                                  .map(|(key, value)| (key.clone(), value.clone(), Change::Removed));
+
+    current_entries.chain(removed_entries).collect()
+}
+
+
+/// Shows the changes stored in the model of the tree view on all of its cells.
+/// Must be called after the columns are appended.
+pub fn show_changes(tree_view: &TreeView) {
+    for column in tree_view.get_columns() {
+        for cell in column.get_cells() {
+            column.add_attribute(&cell, "cell-background", BACKGROUND_COLUMN as i32);
+
+            if cell.is::<CellRendererText>() {
+                column.add_attribute(&cell, "strikethrough", REMOVED_COLUMN as i32);
+            }
+        }
+    }
+}
+
+
+/// Returns a one line summary of the effect of an executed instruction,
+/// e.g. `IADD: 3 + 4 -> 7` for an arithmetic instruction
+/// or `ISTORE 1: 7 -> local 1 = 7` for the others.
+pub fn summary(instruction: &str, operands: &[String], results: &[String]) -> String {
+    let instruction = instruction.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let operands = match (operator(&instruction), operands) {
+        (Some(operator), [left, right]) => format!("{} {} {}", left, operator, right),
+        _ => operands.join(", "),
+    };
+
+    match (operands.is_empty(), results.is_empty()) {
+        (true, true) => instruction,
+        (false, true) => format!("{}: {}", instruction, operands),
+        (true, false) => format!("{}: -> {}", instruction, results.join(", ")),
+        (false, false) => format!("{}: {} -> {}", instruction, operands, results.join(", ")),
+    }
+}
+
+
+/// Returns the operator of a binary arithmetic instruction.
+fn operator(instruction: &str) -> Option<&'static str> {
+    if !instruction.starts_with(|c| "ILFD".contains(c)) {
+        return None;
+    }
+
+    match &instruction[1..] {
+        "ADD" => Some("+"),
+        "SUB" => Some("-"),
+        "MUL" => Some("*"),
+        "DIV" => Some("/"),
+        "REM" => Some("%"),
+        "AND" => Some("&"),
+        "OR" => Some("|"),
+        "XOR" => Some("^"),
+        "SHL" => Some("<<"),
+        "SHR" => Some(">>"),
+        "USHR" => Some(">>>"),
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::gui::changes::{Change, diff, summary};
+
+
+    fn entries(values: &[(usize, &str)]) -> Vec<(usize, String)> {
+        values.iter().map(|(key, value)| (*key, value.to_string())).collect()
+    }
+
+
+    #[test]
+    fn locals() {
+        let previous = entries(&[(0, "1"), (1, "2"), (2, "3")]);
+        let current = entries(&[(0, "1"), (1, "5"), (3, "4")]);
+
+        let changes: Vec<_> = diff(Some(&previous), &current).into_iter().map(|(key, _, change)| (key, change)).collect();
+        assert_eq!(changes, vec![(0, Change::Unchanged), (1, Change::Changed), (3, Change::New), (2, Change::Removed)]);
+
+        let changes = diff(None, &current);
+        assert!(changes.iter().all(|(_, _, change)| *change == Change::Unchanged));
+    }
+
+
+    #[test]
+    fn stack() {
+        // IADD pops two values and pushes their sum on the position of the first
+        let previous = entries(&[(0, "9"), (1, "3"), (2, "4")]);
+        let current = entries(&[(0, "9"), (1, "7")]);
+
+        let changes = diff(Some(&previous), &current);
+        assert_eq!(changes, vec![
+            (0, "9".to_string(), Change::Unchanged),
+            (1, "7".to_string(), Change::Changed),
+            (2, "4".to_string(), Change::Removed),
+        ]);
+        assert!(changes[2].2.is_removed());
+    }
+
+
+    #[test]
+    fn summaries() {
+        let strings = |values: &[&str]| values.iter().map(|value| value.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(summary("IADD", &strings(&["3", "4"]), &strings(&["7"])), "IADD: 3 + 4 -> 7");
+        assert_eq!(summary("LUSHR", &strings(&["8", "1"]), &strings(&["4"])), "LUSHR: 8 >>> 1 -> 4");
+        assert_eq!(summary("ISTORE   1", &strings(&["7"]), &[]), "ISTORE 1: 7");
+        assert_eq!(summary("ICONST_1", &[], &strings(&["1"])), "ICONST_1: -> 1");
+        assert_eq!(summary("NOP", &[], &[]), "NOP");
+        assert_eq!(summary("I2L", &strings(&["1"]), &strings(&["1"])), "I2L: 1 -> 1");
+    }
+}
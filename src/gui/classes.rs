@@ -1,4 +1,6 @@
 use std::boxed::Box as StdBox;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use gtk::{
@@ -10,11 +12,14 @@ use relm::{connect, Relm, Update, Widget};
 use relm_derive::Msg;
 
 use crate::vm::class::class::Class;
+use crate::vm::class::name::ClassName;
+use crate::vm::exec::config::Allocations;
 
 
 #[derive(Msg)]
 pub enum ClassesMsg {
-    Update(Vec<Arc<Class>>),
+    Update(Vec<Arc<Class>>, HashMap<ClassName, Allocations>, HashMap<ClassName, PathBuf>),
+    Stale(Vec<ClassName>),
     ClassActivated(Arc<Class>),
     RowActivated(usize),
     Unselect,
@@ -23,6 +28,9 @@ pub enum ClassesMsg {
 
 pub struct ClassesModel {
     classes: Vec<Arc<Class>>,
+    allocations: HashMap<ClassName, Allocations>,
+    entries: HashMap<ClassName, PathBuf>,
+    stale: Vec<ClassName>,
 }
 
 
@@ -41,22 +49,25 @@ impl Update for ClassesView {
 
     fn model(_: &Relm<Self>, _: ()) -> ClassesModel {
         ClassesModel {
-            classes: Vec::new()
+            classes: Vec::new(),
+            allocations: HashMap::new(),
+            entries: HashMap::new(),
+            stale: Vec::new(),
         }
     }
 
     fn update(&mut self, event: ClassesMsg) {
         match event {
-            ClassesMsg::Update(classes) => {
-                for row in self.list_view.get_children() {
-                    self.list_view.remove(&row);
-                }
-                self.model.classes.clear();
-
-                for class in classes {
-                    let row = ClassesRow::new(&class);
-                    self.list_view.add(&row.root);
-                    self.model.classes.push(class);
+            ClassesMsg::Update(classes, allocations, entries) => {
+                self.model.classes = classes;
+                self.model.allocations = allocations;
+                self.model.entries = entries;
+                self.show_classes();
+            }
+            ClassesMsg::Stale(stale) => {
+                if stale != self.model.stale {
+                    self.model.stale = stale;
+                    self.show_classes();
                 }
             }
             ClassesMsg::ClassActivated(_) => {
@@ -73,6 +84,23 @@ impl Update for ClassesView {
 }
 
 
+impl ClassesView {
+    fn show_classes(&self) {
+        for row in self.list_view.get_children() {
+            self.list_view.remove(&row);
+        }
+
+        for class in &self.model.classes {
+            let allocations = self.model.allocations.get(class.name()).copied().unwrap_or_default();
+            let entry = self.model.entries.get(class.name());
+            let is_stale = self.model.stale.contains(class.name());
+            let row = ClassesRow::new(class, &allocations, entry, is_stale);
+            self.list_view.add(&row.root);
+        }
+    }
+}
+
+
 impl Widget for ClassesView {
     type Root = Box;
 
@@ -141,16 +169,37 @@ struct ClassesRow {
 
 
 impl ClassesRow {
-    fn new(class: &Arc<Class>) -> ClassesRow {
+    fn new(class: &Arc<Class>, allocations: &Allocations, entry: Option<&PathBuf>, is_stale: bool) -> ClassesRow {
         let class_name_str = class.name().to_string();
 
         let class_label = Label::new(Some(&class_name_str));
         class_label.get_style_context().add_class("frame-name");
 
+        let allocations_str = format!("{} objects, {} B", allocations.count(), allocations.bytes());
+        let allocations_label = Label::new(Some(&allocations_str));
+        allocations_label.get_style_context().add_class("allocations");
+
         let frame = Box::new(Orientation::Horizontal, 5);
         frame.set_property_margin(5);
         frame.pack_start(&class_label, false, false, 0);
 
+        // the class path entry is shortened to its file name, the full path is in the tooltip
+        if let Some(entry) = entry {
+            let entry_name = entry.file_name().unwrap_or_else(|| entry.as_os_str()).to_string_lossy();
+            let entry_label = Label::new(Some(&entry_name));
+            entry_label.get_style_context().add_class("class-entry");
+            entry_label.set_tooltip_text(Some(&entry.to_string_lossy()));
+            frame.pack_start(&entry_label, false, false, 0);
+        }
+
+        if is_stale {
+            let stale_label = Label::new(Some("changed on disk"));
+            stale_label.get_style_context().add_class("stale");
+            frame.pack_start(&stale_label, false, false, 0);
+        }
+
+        frame.pack_end(&allocations_label, false, false, 0);
+
         let root = ListBoxRow::new();
         root.add(&frame);
         root.show_all();
@@ -0,0 +1,337 @@
+use std::cell::RefCell;
+use std::cmp::min;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use gtk::{Align, Box, BoxExt, Button, ButtonExt, ComboBoxExt, ComboBoxText, ComboBoxTextExt, ContainerExt, Justification, Label, LabelExt, NONE_ADJUSTMENT, Orientation, ScrolledWindow, StyleContextExt, TextBuffer, TextBufferExt, TextIter, TextTag, TextTagExt, TextTagTable, TextTagTableExt, TextView, TextViewExt, TextWindowType, WidgetExt};
+use gtk::prelude::ComboBoxExtManual;
+use relm::{connect, Relm, Update, Widget};
+use relm_derive::Msg;
+
+use crate::vm::class::name::ClassName;
+use crate::vm::exec::class_loader::ClassLoader;
+use crate::vm::exec::class_source::CompositeSource;
+use crate::vm::parse::error::Span;
+use crate::vm::parse::highlight::{highlight, TokenKind};
+use crate::vm::parse::parser::ClassFileParser;
+
+
+#[derive(Msg)]
+pub enum EditorMsg {
+    ChangeClassPath(Vec<PathBuf>),
+    OpenClass(ClassName),
+    FileSelected,
+    Changed,
+    Save,
+    Saved,
+}
+
+
+pub struct EditorModel {
+    files: Vec<(ClassName, PathBuf)>,
+    opened: Option<PathBuf>,
+    /// Unsaved sources of the files switched away from.
+    drafts: HashMap<PathBuf, String>,
+    errors: Rc<RefCell<Vec<(Span, String)>>>,
+}
+
+
+pub struct EditorView {
+    root: Box,
+    relm: Relm<EditorView>,
+    model: EditorModel,
+    file_chooser: ComboBoxText,
+    buffer: TextBuffer,
+    errors_label: Label,
+    save_button: Button,
+}
+
+
+impl Update for EditorView {
+    type Model = EditorModel;
+    type ModelParam = ();
+    type Msg = EditorMsg;
+
+    fn model(_: &Relm<Self>, _: ()) -> EditorModel {
+        EditorModel {
+            files: Vec::new(),
+            opened: None,
+            drafts: HashMap::new(),
+            errors: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    fn update(&mut self, event: EditorMsg) {
+        match event {
+            EditorMsg::ChangeClassPath(class_path) => {
+                let class_loader = ClassLoader::new(CompositeSource::class_path(class_path));
+
+                self.model.files = class_loader.class_names()
+                                               .unwrap_or_default()
+                                               .into_iter()
+                                               .filter_map(|name| class_loader.class_file(&name).map(|path| (name, path)))
+                                               .collect();
+
+                self.file_chooser.remove_all();
+                for (name, _) in &self.model.files {
+                    self.file_chooser.append_text(name.as_ref());
+                }
+
+                // keep the opened file if it is still on the class path
+                self.keep_draft();
+                let opened = self.model.opened.take().and_then(|opened| {
+                    self.model.files.iter().position(|(_, path)| *path == opened)
+                });
+                self.file_chooser.set_active(opened.or(Some(0)).map(|index| index as u32));
+            }
+            EditorMsg::OpenClass(name) => {
+                if let Some(index) = self.model.files.iter().position(|(file_name, _)| *file_name == name) {
+                    self.file_chooser.set_active(Some(index as u32));
+                }
+            }
+            EditorMsg::FileSelected => {
+                let path = self.file_chooser.get_active()
+                               .and_then(|index| self.model.files.get(index as usize))
+                               .map(|(_, path)| path.clone());
+
+                let path = match path {
+                    Some(path) if Some(&path) != self.model.opened.as_ref() => path,
+                    _ => return,
+                };
+
+                self.keep_draft();
+
+                if let Some(draft) = self.model.drafts.remove(&path) {
+                    self.model.opened = Some(path);
+                    self.buffer.set_text(&draft);
+                    self.buffer.set_modified(true);
+                    self.save_button.set_sensitive(true);
+                    return;
+                }
+
+                match fs::read_to_string(&path) {
+                    Ok(source) => {
+                        self.model.opened = Some(path);
+                        self.buffer.set_text(&source);
+                        self.buffer.set_modified(false);
+                        self.save_button.set_sensitive(true);
+                    }
+                    Err(error) => {
+                        self.model.opened = None;
+                        self.buffer.set_text("");
+                        self.save_button.set_sensitive(false);
+                        self.errors_label.set_label(&format!("can not read the class file: {}", error));
+                    }
+                }
+            }
+            EditorMsg::Changed => {
+                let (start, end) = self.buffer.get_bounds();
+                let source = self.buffer.get_text(&start, &end, true).map(|text| text.to_string()).unwrap_or_default();
+
+                self.buffer.remove_all_tags(&start, &end);
+
+                for token in highlight(&source) {
+                    if let Some((start, end)) = span_iters(&self.buffer, token.span()) {
+                        self.buffer.apply_tag_by_name(tag_name(token.kind()), &start, &end);
+                    }
+                }
+
+                let errors: Vec<_> = match ClassFileParser::new(&source).parse() {
+                    Ok(_) => Vec::new(),
+                    Err(errors) => errors.errors().iter().map(|error| (error.span(), error.to_string())).collect(),
+                };
+
+                for (span, _) in &errors {
+                    if let Some((start, end)) = span_iters(&self.buffer, *span) {
+                        self.buffer.apply_tag_by_name("error", &start, &end);
+                    }
+                }
+
+                let messages: Vec<_> = errors.iter().map(|(_, message)| message.as_str()).collect();
+                self.errors_label.set_label(&messages.join("\n"));
+                *self.model.errors.borrow_mut() = errors;
+            }
+            EditorMsg::Save => {
+                let path = match &self.model.opened {
+                    Some(path) => path,
+                    None => return,
+                };
+
+                let (start, end) = self.buffer.get_bounds();
+                let source = self.buffer.get_text(&start, &end, true).map(|text| text.to_string()).unwrap_or_default();
+
+                match fs::write(path, source) {
+                    Ok(()) => {
+                        self.buffer.set_modified(false);
+                        self.relm.stream().emit(EditorMsg::Saved);
+                    }
+                    Err(error) => self.errors_label.set_label(&format!("can not write the class file: {}", error)),
+                }
+            }
+            EditorMsg::Saved => {}
+        }
+    }
+}
+
+
+impl EditorView {
+    /// Keeps the source of the opened file if it has unsaved changes,
+    /// so they are restored when the file is selected again.
+    fn keep_draft(&mut self) {
+        if let Some(opened) = &self.model.opened {
+            if self.buffer.get_modified() {
+                let (start, end) = self.buffer.get_bounds();
+                let source = self.buffer.get_text(&start, &end, true).map(|text| text.to_string()).unwrap_or_default();
+                self.model.drafts.insert(opened.clone(), source);
+            }
+        }
+    }
+}
+
+
+impl Widget for EditorView {
+    type Root = Box;
+
+    fn root(&self) -> Self::Root {
+        self.root.clone()
+    }
+
+    fn view(relm: &Relm<Self>, model: Self::Model) -> Self {
+        let tag_table = TextTagTable::new();
+        for (name, foreground, weight) in &[("keyword", "#8959a8", 700),
+                                            ("instruction", "steelblue", 400),
+                                            ("descriptor", "#3e999f", 400),
+                                            ("number", "#f5871f", 400),
+                                            ("label", "steelblue", 700),
+                                            ("comment", "gray", 400)] {
+            let tag = TextTag::new(Some(name));
+            tag.set_property_foreground(Some(foreground));
+            tag.set_property_weight(*weight);
+            tag_table.add(&tag);
+        }
+
+        let error_tag = TextTag::new(Some("error"));
+        error_tag.set_property_background(Some("#f5d6d6"));
+        tag_table.add(&error_tag);
+
+        let buffer = TextBuffer::new(Some(&tag_table));
+        let text_view = TextView::with_buffer(&buffer);
+        text_view.set_monospace(true);
+        text_view.set_left_margin(10);
+        text_view.set_top_margin(10);
+        text_view.get_style_context().add_class("source-editor");
+
+        // show the parse errors on the marked parts of the source
+        let errors = model.errors.clone();
+        text_view.set_has_tooltip(true);
+        text_view.connect_query_tooltip(move |text_view, x, y, _, tooltip| {
+            let (x, y) = text_view.window_to_buffer_coords(TextWindowType::Widget, x, y);
+            let iter = match text_view.get_iter_at_location(x, y) {
+                Some(iter) => iter,
+                None => return false,
+            };
+
+            let line = iter.get_line() as usize + 1;
+            let column = iter.get_line_offset() as usize + 1;
+            let errors = errors.borrow();
+            let message = errors.iter().find(|(span, _)| {
+                span.line() == line && (span.width() == 0 || (span.column()..span.column() + span.width()).contains(&column))
+            });
+
+            match message {
+                Some((_, message)) => {
+                    tooltip.set_text(Some(message));
+                    true
+                }
+                None => false,
+            }
+        });
+
+        let scrolled = ScrolledWindow::new(NONE_ADJUSTMENT, NONE_ADJUSTMENT);
+        scrolled.add(&text_view);
+
+        let label = Label::new(Some("Source"));
+        label.get_style_context().add_class("panel-heading");
+        label.set_justify(Justification::Center);
+
+        let file_chooser = ComboBoxText::new();
+        let save_button = Button::with_label("Save");
+        save_button.set_sensitive(false);
+
+        let file_box = Box::new(Orientation::Horizontal, 5);
+        file_box.set_property_margin(10);
+        file_box.pack_start(&file_chooser, true, true, 0);
+        file_box.pack_start(&save_button, false, false, 0);
+
+        let errors_label = Label::new(None);
+        errors_label.get_style_context().add_class("error-details");
+        errors_label.set_halign(Align::Start);
+        errors_label.set_selectable(true);
+        errors_label.set_margin_start(10);
+        errors_label.set_margin_end(10);
+
+        let root = Box::new(Orientation::Vertical, 0);
+        root.pack_start(&label, false, false, 10);
+        root.pack_start(&file_box, false, true, 0);
+        root.pack_start(&scrolled, true, true, 0);
+        root.pack_start(&errors_label, false, true, 5);
+
+        connect!(relm, file_chooser, connect_changed(_), EditorMsg::FileSelected);
+        connect!(relm, buffer, connect_changed(_), EditorMsg::Changed);
+        connect!(relm, save_button, connect_clicked(_), EditorMsg::Save);
+
+        EditorView {
+            root,
+            relm: relm.clone(),
+            model,
+            file_chooser,
+            buffer,
+            errors_label,
+            save_button,
+        }
+    }
+}
+
+
+fn tag_name(kind: TokenKind) -> &'static str {
+    match kind {
+        TokenKind::Keyword => "keyword",
+        TokenKind::Instruction => "instruction",
+        TokenKind::Descriptor => "descriptor",
+        TokenKind::Number => "number",
+        TokenKind::Label => "label",
+        TokenKind::Comment => "comment",
+    }
+}
+
+
+/// Returns the iterators at the start and the end of the span, limited to its line.
+/// A span of zero width marks the whole line.
+fn span_iters(buffer: &TextBuffer, span: Span) -> Option<(TextIter, TextIter)> {
+    let line = span.line().checked_sub(1)? as i32;
+    if line >= buffer.get_line_count() {
+        return None;
+    }
+
+    let line_start = buffer.get_iter_at_line(line);
+    let mut line_end = line_start.clone();
+    if !line_end.ends_line() {
+        line_end.forward_to_line_end();
+    }
+
+    if span.width() == 0 {
+        return Some((line_start, line_end));
+    }
+
+    let length = line_end.get_line_offset() as usize;
+    let column = min(span.column().saturating_sub(1), length);
+
+    let mut start = line_start.clone();
+    start.set_line_offset(column as i32);
+    let mut end = line_start;
+    end.set_line_offset(min(column + span.width(), length) as i32);
+
+    Some((start, end))
+}
@@ -5,18 +5,26 @@ use gtk::prelude::{GtkListStoreExtManual, StaticType};
 use relm::{Relm, Update, Widget};
 use relm_derive::Msg;
 
+use crate::gui::changes::{BACKGROUND_COLUMN, diff, REMOVED_COLUMN, show_changes};
+use crate::gui::value_edit::ValueColumn;
 use crate::vm::class::class::Class;
 use crate::vm::class::field::Field;
 use crate::vm::class::instance::Instance;
+use crate::vm::class::signature::FieldSig;
 
 
 #[derive(Msg)]
 pub enum FieldsMsg {
     Update,
     ChangeViewed(Viewed),
+    Editable(bool),
+    Instances(Vec<Instance>),
+    Edited(usize, String),
+    FieldEdited(Viewed, FieldSig, String),
 }
 
 
+#[derive(Clone)]
 pub enum Viewed {
     Class(Arc<Class>),
     Instance(Instance),
@@ -26,6 +34,8 @@ pub enum Viewed {
 
 pub struct FieldsModel {
     viewed: Viewed,
+    signatures: Vec<FieldSig>,
+    previous: Option<Vec<(FieldSig, String)>>,
 }
 
 
@@ -35,6 +45,7 @@ pub struct FieldsView {
     relm: Relm<FieldsView>,
     list_store: ListStore,
     _tree_view: TreeView,
+    value_column: ValueColumn,
     name: Label,
 }
 
@@ -46,7 +57,9 @@ impl Update for FieldsView {
 
     fn model(_: &Relm<Self>, _: ()) -> FieldsModel {
         FieldsModel {
-            viewed: Viewed::None
+            viewed: Viewed::None,
+            signatures: Vec::new(),
+            previous: None,
         }
     }
 
@@ -81,19 +94,39 @@ impl Update for FieldsView {
                 };
 
                 self.list_store.clear();
+                self.model.signatures.clear();
 
-                for (sig, value) in fields {
+                let fields = fields.into_iter()
+                                   .map(|(sig, value)| (sig.clone(), value.to_string()))
+                                   .collect::<Vec<_>>();
+
+                for (sig, value, change) in diff(self.model.previous.as_deref(), &fields) {
                     self.list_store.insert_with_values(None,
-                                                       &[0, 1, 2],
+                                                       &[0, 1, 2, BACKGROUND_COLUMN, REMOVED_COLUMN],
                                                        &[&sig.type_desc().to_string(),
                                                            &sig.name().to_string(),
-                                                           &value.to_string()]);
+                                                           &value,
+                                                           &change.background(),
+                                                           &change.is_removed()]);
+                    self.model.signatures.push(sig);
                 }
+
+                self.model.previous = Some(fields);
             }
             FieldsMsg::ChangeViewed(viewed) => {
                 self.model.viewed = viewed;
+                self.model.previous = None;
                 self.relm.stream().emit(FieldsMsg::Update);
             }
+            FieldsMsg::Editable(editable) => self.value_column.set_editable(editable),
+            FieldsMsg::Instances(instances) => self.value_column.set_instances(&instances),
+            FieldsMsg::Edited(index, text) => {
+                if let Some(sig) = self.model.signatures.get(index) {
+                    let viewed = self.model.viewed.clone();
+                    self.relm.stream().emit(FieldsMsg::FieldEdited(viewed, sig.clone(), text));
+                }
+            }
+            FieldsMsg::FieldEdited(..) => {}
         }
     }
 }
@@ -125,16 +158,16 @@ impl Widget for FieldsView {
         name_column.add_attribute(&name_cell, "text", 1);
         tree_view.append_column(&name_column);
 
-        let value_column = gtk::TreeViewColumn::new();
-        value_column.set_title("value");
-        let value_cell = gtk::CellRendererText::new();
-        value_column.pack_start(&value_cell, true);
-        value_column.add_attribute(&value_cell, "text", 2);
-        tree_view.append_column(&value_column);
+        let stream = relm.stream().clone();
+        let value_column = ValueColumn::new(&tree_view, 2, move |index, text| {
+            stream.emit(FieldsMsg::Edited(index, text))
+        });
 
-        let list_store = gtk::ListStore::new(&[String::static_type(), String::static_type(), String::static_type()]);
+        let list_store = gtk::ListStore::new(&[String::static_type(), String::static_type(), String::static_type(),
+            String::static_type(), bool::static_type()]);
 
         tree_view.set_model(Some(&list_store));
+        show_changes(&tree_view);
         tree_view.set_grid_lines(TreeViewGridLines::Both);
         tree_view.get_selection().set_mode(SelectionMode::None);
 
@@ -171,6 +204,7 @@ impl Widget for FieldsView {
             relm: relm.clone(),
             list_store,
             _tree_view: tree_view,
+            value_column,
             name,
         }
     }
@@ -38,8 +38,10 @@ impl Update for InstructionsView {
                     self.list_view.remove(&row);
                 }
 
-                for instruction in method.code().instructions() {
-                    let row = InstructionsRow::new(&instruction);
+                let code = method.code();
+                for (index, instruction) in code.instructions().enumerate() {
+                    let labels: Vec<_> = code.labels_at(index).collect();
+                    let row = InstructionsRow::new(&labels, instruction, &code.instruction_str(index).unwrap());
                     self.list_view.add(&row.root);
                 }
 
@@ -115,20 +117,44 @@ struct InstructionsRow {
 
 
 impl InstructionsRow {
-    fn new(instruction: &Instruction) -> InstructionsRow {
-        let instruction_str = instruction.to_string();
-
-        let instruction_label = Label::new(Some(&instruction_str));
+    fn new(labels: &[&str], instruction: &Instruction, instruction_str: &str) -> InstructionsRow {
+        let instruction_label = Label::new(Some(instruction_str));
         instruction_label.get_style_context().add_class("instruction");
 
         let instruction_box = Box::new(Orientation::Horizontal, 5);
         instruction_box.set_property_margin(5);
+
+        if !labels.is_empty() {
+            let labels_str = labels.iter().map(|label| format!("{}:", label)).collect::<Vec<_>>().join(" ");
+            let labels_label = Label::new(Some(&labels_str));
+            labels_label.get_style_context().add_class("instruction-label");
+            instruction_box.pack_start(&labels_label, false, false, 0);
+        }
+
         instruction_box.pack_start(&instruction_label, false, false, 0);
 
         let root = ListBoxRow::new();
         root.add(&instruction_box);
+        root.set_tooltip_text(Some(&Self::tooltip(instruction)));
         root.show_all();
 
         InstructionsRow { root }
     }
-}
\ No newline at end of file
+
+    /// Describes the instruction by its metadata.
+    fn tooltip(instruction: &Instruction) -> String {
+        let info = instruction.info();
+        let mut tooltip = format!("{} ({:#04x}, {})\n{}", info.mnemonic(), info.opcode(), info.category(), info.description());
+
+        if !info.operands().is_empty() {
+            let operands: Vec<String> = info.operands().iter().map(|operand| operand.to_string()).collect();
+            tooltip.push_str(&format!("\nOperands: {}", operands.join(", ")));
+        }
+
+        if let Some(effect) = instruction.stack_effect() {
+            tooltip.push_str(&format!("\nStack: {}", effect));
+        }
+
+        tooltip
+    }
+}
@@ -0,0 +1,139 @@
+use std::convert::TryFrom;
+use std::fs;
+use std::path::PathBuf;
+
+use gtk::{Box, BoxExt, ContainerExt, Justification, Label, LabelExt, NONE_ADJUSTMENT, Orientation, ScrolledWindow, StyleContextExt, TextBuffer, TextBufferExt, TextTag, TextTagExt, TextTagTable, TextTagTableExt, TextView, TextViewExt, WidgetExt};
+use relm::{Relm, Update, Widget};
+use relm_derive::Msg;
+
+
+#[derive(Msg)]
+pub enum JavaSourceMsg {
+    ChangeViewed(Option<PathBuf>),
+    SelectLine(Option<usize>),
+}
+
+
+pub struct JavaSourceModel {
+    opened: Option<PathBuf>,
+}
+
+
+/// The Java source file the viewed class was compiled from,
+/// with the line of the current instruction highlighted.
+pub struct JavaSourceView {
+    root: Box,
+    model: JavaSourceModel,
+    heading: Label,
+    text_view: TextView,
+    buffer: TextBuffer,
+}
+
+
+impl Update for JavaSourceView {
+    type Model = JavaSourceModel;
+    type ModelParam = ();
+    type Msg = JavaSourceMsg;
+
+    fn model(_: &Relm<Self>, _: ()) -> JavaSourceModel {
+        JavaSourceModel {
+            opened: None,
+        }
+    }
+
+    fn update(&mut self, event: JavaSourceMsg) {
+        match event {
+            JavaSourceMsg::ChangeViewed(path) => {
+                if path == self.model.opened {
+                    return;
+                }
+
+                let source = path.as_ref().map(|path| (path, fs::read_to_string(path)));
+
+                match source {
+                    Some((path, Ok(source))) => {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        self.heading.set_label(&name);
+                        self.buffer.set_text(&source);
+                    }
+                    Some((path, Err(error))) => {
+                        self.heading.set_label("Java source");
+                        self.buffer.set_text(&format!("can not read {}: {}", path.display(), error));
+                    }
+                    None => {
+                        self.heading.set_label("Java source");
+                        self.buffer.set_text("");
+                    }
+                }
+
+                self.model.opened = path;
+            }
+            JavaSourceMsg::SelectLine(line) => {
+                let (start, end) = self.buffer.get_bounds();
+                self.buffer.remove_tag_by_name("current", &start, &end);
+
+                let line = match line.and_then(|line| line.checked_sub(1)).and_then(|line| i32::try_from(line).ok()) {
+                    Some(line) if self.model.opened.is_some() && line < self.buffer.get_line_count() => line,
+                    _ => return,
+                };
+
+                let start = self.buffer.get_iter_at_line(line);
+                let mut end = start.clone();
+                end.forward_line();
+                self.buffer.apply_tag_by_name("current", &start, &end);
+
+                // the mark keeps the position until the text is laid out
+                if let Some(mark) = self.buffer.get_mark("current") {
+                    self.buffer.move_mark(&mark, &start);
+                    self.text_view.scroll_to_mark(&mark, 0.1, false, 0.0, 0.0);
+                }
+            }
+        }
+    }
+}
+
+
+impl Widget for JavaSourceView {
+    type Root = Box;
+
+    fn root(&self) -> Self::Root {
+        self.root.clone()
+    }
+
+    fn view(_: &Relm<Self>, model: Self::Model) -> Self {
+        let tag_table = TextTagTable::new();
+        let current_tag = TextTag::new(Some("current"));
+        current_tag.set_property_background(Some("#d6e4f5"));
+        tag_table.add(&current_tag);
+
+        let buffer = TextBuffer::new(Some(&tag_table));
+        buffer.create_mark(Some("current"), &buffer.get_start_iter(), true);
+
+        let text_view = TextView::with_buffer(&buffer);
+        text_view.set_monospace(true);
+        text_view.set_editable(false);
+        text_view.set_cursor_visible(false);
+        text_view.set_left_margin(10);
+        text_view.set_top_margin(10);
+
+        let scrolled = ScrolledWindow::new(NONE_ADJUSTMENT, NONE_ADJUSTMENT);
+        scrolled.add(&text_view);
+
+        let label = Label::new(Some("Java source"));
+        label.get_style_context().add_class("panel-heading");
+        label.set_justify(Justification::Center);
+
+        let root = Box::new(Orientation::Vertical, 0);
+        root.pack_start(&label, false, false, 10);
+        root.pack_start(&scrolled, true, true, 0);
+        root.set_size_request(250, -1);
+
+        JavaSourceView {
+            root,
+            model,
+            heading: label,
+            text_view,
+            buffer,
+        }
+    }
+}
@@ -3,12 +3,19 @@ use gtk::prelude::{GtkListStoreExtManual, StaticType};
 use relm::{Relm, Update, Widget};
 use relm_derive::Msg;
 
+use crate::gui::changes::{BACKGROUND_COLUMN, diff, REMOVED_COLUMN, show_changes};
+use crate::gui::value_edit::ValueColumn;
+use crate::vm::class::instance::Instance;
 use crate::vm::memory::locals::Slot;
 
 
 #[derive(Msg)]
 pub enum LocalsMsg {
-    Update(Vec<Slot>)
+    // the values of the local variables and their values before the last step
+    Update(Vec<Slot>, Option<Vec<Slot>>),
+    Editable(bool),
+    Instances(Vec<Instance>),
+    Edited(usize, String),
 }
 
 
@@ -16,6 +23,7 @@ pub struct LocalsView {
     root: Box,
     list_store: ListStore,
     _tree_view: TreeView,
+    value_column: ValueColumn,
 }
 
 
@@ -28,25 +36,30 @@ impl Update for LocalsView {
 
     fn update(&mut self, event: LocalsMsg) {
         match event {
-            LocalsMsg::Update(values) => {
+            LocalsMsg::Update(values, previous) => {
                 self.list_store.clear();
 
-                for (index, value) in values.iter().enumerate() {
-                    let index = index.to_string();
-                    match value {
-                        Slot::Undefined => {
-                            self.list_store.insert_with_values(None,
-                                                               &[0, 1, 2],
-                                                               &[&index, &"", &"UNDEFINED"]);
-                        }
-                        Slot::Value(value) => {
-                            self.list_store.insert_with_values(None,
-                                                               &[0, 1, 2],
-                                                               &[&index, &value.value_type().to_string(), &value.to_string()]);
-                        }
-                    }
+                let entries = |values: &[Slot]| {
+                    values.iter().enumerate().map(|(index, value)| {
+                        let texts = match value {
+                            Slot::Undefined => (String::new(), "UNDEFINED".to_owned()),
+                            Slot::Value(value) => (value.value_type().to_string(), value.to_string()),
+                        };
+                        (index, texts)
+                    }).collect::<Vec<_>>()
+                };
+                let previous = previous.as_deref().map(entries);
+
+                for (index, (value_type, value), change) in diff(previous.as_deref(), &entries(&values)) {
+                    self.list_store.insert_with_values(None,
+                                                       &[0, 1, 2, BACKGROUND_COLUMN, REMOVED_COLUMN],
+                                                       &[&index.to_string(), &value_type, &value,
+                                                           &change.background(), &change.is_removed()]);
                 }
             }
+            LocalsMsg::Editable(editable) => self.value_column.set_editable(editable),
+            LocalsMsg::Instances(instances) => self.value_column.set_instances(&instances),
+            LocalsMsg::Edited(..) => {}
         }
     }
 }
@@ -59,7 +72,7 @@ impl Widget for LocalsView {
         self.root.clone()
     }
 
-    fn view(_: &Relm<Self>, _: Self::Model) -> Self {
+    fn view(relm: &Relm<Self>, _: Self::Model) -> Self {
         let tree_view = gtk::TreeView::new();
 
         let index_column = gtk::TreeViewColumn::new();
@@ -78,16 +91,16 @@ impl Widget for LocalsView {
         type_column.add_attribute(&type_cell, "text", 1);
         tree_view.append_column(&type_column);
 
-        let value_column = gtk::TreeViewColumn::new();
-        value_column.set_title("value");
-        let value_cell = gtk::CellRendererText::new();
-        value_column.pack_start(&value_cell, true);
-        value_column.add_attribute(&value_cell, "text", 2);
-        tree_view.append_column(&value_column);
+        let stream = relm.stream().clone();
+        let value_column = ValueColumn::new(&tree_view, 2, move |index, text| {
+            stream.emit(LocalsMsg::Edited(index, text))
+        });
 
-        let list_store = gtk::ListStore::new(&[String::static_type(), String::static_type(), String::static_type()]);
+        let list_store = gtk::ListStore::new(&[String::static_type(), String::static_type(), String::static_type(),
+            String::static_type(), bool::static_type()]);
 
         tree_view.set_model(Some(&list_store));
+        show_changes(&tree_view);
         tree_view.set_grid_lines(TreeViewGridLines::Both);
         tree_view.get_selection().set_mode(SelectionMode::None);
 
@@ -116,6 +129,7 @@ impl Widget for LocalsView {
             root,
             list_store,
             _tree_view: tree_view,
+            value_column,
         }
     }
 }
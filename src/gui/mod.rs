@@ -4,12 +4,18 @@ pub use window::AppWindow;
 mod frame_stack;
 mod header;
 mod landing;
+mod changes;
 mod locals;
+mod value_edit;
 mod operand_stack;
 mod instances;
+mod object_graph;
+mod recent;
 mod classes;
+mod editor;
 mod fields;
 mod instructions;
+mod java_source;
 mod vm;
 mod window;
 
@@ -0,0 +1,377 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+
+use cairo::{Context, FontSlant, FontWeight};
+use gdk::EventMask;
+use gtk::{
+    Box, BoxExt, ContainerExt, DrawingArea, Inhibit, Justification, Label, LabelExt,
+    NONE_ADJUSTMENT, Orientation, ScrolledWindow, StyleContextExt, WidgetExt,
+};
+use gtk::prelude::WidgetExtManual;
+use relm::{Relm, Update, Widget};
+use relm_derive::Msg;
+
+use crate::vm::class::instance::InstanceId;
+use crate::vm::exec::heap_dump::{HeapDump, HeapObject, RootKind};
+use crate::vm::types::reference::Reference;
+use crate::vm::types::value::Value;
+
+
+#[derive(Msg)]
+pub enum ObjectGraphMsg {
+    Update(HeapDump),
+    InstanceActivated(InstanceId),
+    Clicked(f64, f64),
+}
+
+
+pub struct ObjectGraphModel {
+    graph: Rc<RefCell<Graph>>,
+}
+
+
+pub struct ObjectGraphView {
+    root: Box,
+    relm: Relm<ObjectGraphView>,
+    model: ObjectGraphModel,
+    drawing_area: DrawingArea,
+}
+
+
+impl Update for ObjectGraphView {
+    type Model = ObjectGraphModel;
+    type ModelParam = ();
+    type Msg = ObjectGraphMsg;
+
+    fn model(_: &Relm<Self>, _: ()) -> ObjectGraphModel {
+        ObjectGraphModel {
+            graph: Rc::new(RefCell::new(Graph::default()))
+        }
+    }
+
+    fn update(&mut self, event: ObjectGraphMsg) {
+        match event {
+            ObjectGraphMsg::Update(dump) => {
+                self.model.graph.borrow_mut().update(dump);
+                self.drawing_area.queue_draw();
+            }
+            ObjectGraphMsg::InstanceActivated(_) => {
+                // just to notify listeners
+            }
+            ObjectGraphMsg::Clicked(x, y) => {
+                let id = self.model.graph.borrow().object_at(x, y);
+
+                if let Some(id) = id {
+                    self.relm.stream().emit(ObjectGraphMsg::InstanceActivated(id));
+                }
+            }
+        }
+    }
+}
+
+
+impl Widget for ObjectGraphView {
+    type Root = Box;
+
+    fn root(&self) -> Self::Root {
+        self.root.clone()
+    }
+
+    fn view(relm: &Relm<Self>, model: Self::Model) -> Self {
+        let drawing_area = DrawingArea::new();
+        drawing_area.add_events(EventMask::BUTTON_PRESS_MASK);
+
+        let graph = model.graph.clone();
+        drawing_area.connect_draw(move |area, context| {
+            let (width, height) = graph.borrow_mut().draw(context);
+            area.set_size_request(width as i32, height as i32);
+            Inhibit(false)
+        });
+
+        let stream = relm.stream().clone();
+        drawing_area.connect_button_press_event(move |_, event| {
+            let (x, y) = event.get_position();
+            stream.emit(ObjectGraphMsg::Clicked(x, y));
+            Inhibit(false)
+        });
+
+        let scrolled = ScrolledWindow::new(NONE_ADJUSTMENT, NONE_ADJUSTMENT);
+        scrolled.add(&drawing_area);
+
+        let label = Label::new(Some("Object graph"));
+        label.get_style_context().add_class("panel-heading");
+        label.set_justify(Justification::Center);
+
+        let root = Box::new(Orientation::Vertical, 0);
+        root.pack_start(&label, false, false, 10);
+        root.pack_start(&scrolled, true, true, 0);
+        root.set_size_request(350, -1);
+
+        ObjectGraphView {
+            root,
+            relm: relm.clone(),
+            model,
+            drawing_area,
+        }
+    }
+}
+
+
+/// The drawn heap dump with the objects changed by the last update.
+#[derive(Default)]
+struct Graph {
+    dump: Option<HeapDump>,
+    values: HashMap<InstanceId, Vec<String>>,
+    changed: HashSet<InstanceId>,
+    boxes: Vec<(InstanceId, Rect)>,
+}
+
+
+#[derive(Debug, Copy, Clone)]
+struct Rect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+
+impl Rect {
+    fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.height
+    }
+}
+
+
+impl Graph {
+    const MARGIN: f64 = 10.0;
+    const PADDING: f64 = 6.0;
+    const ROW_HEIGHT: f64 = 18.0;
+    const COLUMN_GAP: f64 = 60.0;
+    const BOX_GAP: f64 = 20.0;
+    const FONT_SIZE: f64 = 12.0;
+
+    /// Replaces the drawn dump and marks the objects which are new or have a changed field.
+    fn update(&mut self, dump: HeapDump) {
+        let values: HashMap<_, _> = dump.objects()
+                                        .iter()
+                                        .map(|object| (object.id(), field_strings(object)))
+                                        .collect();
+
+        self.changed = values.iter()
+                             .filter(|(id, fields)| self.values.get(id) != Some(fields))
+                             .map(|(id, _)| *id)
+                             .collect();
+        self.values = values;
+        self.dump = Some(dump);
+    }
+
+    /// Returns the id of the object drawn at the given position.
+    fn object_at(&self, x: f64, y: f64) -> Option<InstanceId> {
+        self.boxes.iter()
+            .find(|(_, rect)| rect.contains(x, y))
+            .map(|(id, _)| *id)
+    }
+
+    /// Draws the roots in the first column and the objects in columns by their distance
+    /// from the roots, returns the size of the drawing.
+    fn draw(&mut self, context: &Context) -> (f64, f64) {
+        self.boxes.clear();
+
+        let dump = match &self.dump {
+            None => return (0.0, 0.0),
+            Some(dump) => dump,
+        };
+
+        context.select_font_face("monospace", FontSlant::Normal, FontWeight::Normal);
+        context.set_font_size(Self::FONT_SIZE);
+        context.set_line_width(1.0);
+
+        let text_width = |text: &str| context.text_extents(text).x_advance;
+
+        // root labels
+        let root_labels: Vec<_> = dump.roots().iter().map(|root| root_label(root.kind())).collect();
+        let roots_width = root_labels.iter().map(|label| text_width(label)).fold(0.0, f64::max);
+
+        // columns of objects
+        let columns = columns(dump);
+        let mut positions = HashMap::new();
+        let mut x = Self::MARGIN + roots_width + if root_labels.is_empty() { 0.0 } else { Self::COLUMN_GAP };
+        let mut height: f64 = 0.0;
+
+        for column in &columns {
+            let mut y = Self::MARGIN;
+            let mut column_width: f64 = 0.0;
+
+            for object in column {
+                let header = format!("{}@{}", object.class_name(), object.id());
+                let width = object.fields()
+                                  .iter()
+                                  .map(|(field, value)| text_width(&format!("{} = {}", field.name(), value)))
+                                  .fold(text_width(&header), f64::max) + 2.0 * Self::PADDING;
+                let rect = Rect {
+                    x,
+                    y,
+                    width,
+                    height: (object.fields().len() + 1) as f64 * Self::ROW_HEIGHT + Self::PADDING,
+                };
+
+                positions.insert(object.id(), rect);
+                self.boxes.push((object.id(), rect));
+                column_width = column_width.max(width);
+                y += rect.height + Self::BOX_GAP;
+            }
+
+            height = height.max(y);
+            x += column_width + Self::COLUMN_GAP;
+        }
+
+        // objects
+        for column in &columns {
+            for object in column {
+                let rect = positions[&object.id()];
+                self.draw_object(context, object, rect);
+            }
+        }
+
+        // references from the fields
+        context.set_source_rgb(0.27, 0.51, 0.71);
+
+        for column in &columns {
+            for object in column {
+                let rect = positions[&object.id()];
+
+                for (index, (_, value)) in object.fields().iter().enumerate() {
+                    if let Value::Reference(Reference::Instance(instance)) = value {
+                        if let Some(target) = positions.get(&instance.id()) {
+                            let y = rect.y + (index + 1) as f64 * Self::ROW_HEIGHT + Self::ROW_HEIGHT / 2.0;
+                            draw_arrow(context, rect.x + rect.width, y, target.x, target.y + Self::ROW_HEIGHT / 2.0);
+                        }
+                    }
+                }
+            }
+        }
+
+        // roots and their references
+        for (index, (root, label)) in dump.roots().iter().zip(&root_labels).enumerate() {
+            let y = Self::MARGIN + index as f64 * Self::ROW_HEIGHT + Self::ROW_HEIGHT / 2.0;
+
+            context.set_source_rgb(0.0, 0.0, 0.0);
+            context.move_to(Self::MARGIN, y + Self::FONT_SIZE / 3.0);
+            context.show_text(label);
+
+            if let Some(target) = positions.get(&root.target()) {
+                context.set_source_rgb(0.7, 0.13, 0.13);
+                draw_arrow(context, Self::MARGIN + roots_width + 4.0, y, target.x, target.y + Self::ROW_HEIGHT / 2.0);
+            }
+        }
+
+        height = height.max(Self::MARGIN + root_labels.len() as f64 * Self::ROW_HEIGHT);
+        (x, height + Self::MARGIN)
+    }
+
+    fn draw_object(&self, context: &Context, object: &HeapObject, rect: Rect) {
+        if self.changed.contains(&object.id()) {
+            context.set_source_rgb(1.0, 0.95, 0.6);
+        } else {
+            context.set_source_rgb(1.0, 1.0, 1.0);
+        }
+
+        context.rectangle(rect.x, rect.y, rect.width, rect.height);
+        context.fill_preserve();
+        context.set_source_rgb(0.0, 0.0, 0.0);
+        context.set_line_width(if object.is_root() { 2.0 } else { 1.0 });
+        context.stroke();
+        context.set_line_width(1.0);
+
+        context.move_to(rect.x, rect.y + Self::ROW_HEIGHT);
+        context.line_to(rect.x + rect.width, rect.y + Self::ROW_HEIGHT);
+        context.stroke();
+
+        let text_y = |row: usize| rect.y + row as f64 * Self::ROW_HEIGHT + Self::ROW_HEIGHT / 2.0 + Self::FONT_SIZE / 3.0;
+
+        context.move_to(rect.x + Self::PADDING, text_y(0));
+        context.show_text(&format!("{}@{}", object.class_name(), object.id()));
+
+        for (index, (field, value)) in object.fields().iter().enumerate() {
+            context.move_to(rect.x + Self::PADDING, text_y(index + 1) + Self::PADDING / 2.0);
+            context.show_text(&format!("{} = {}", field.name(), value));
+        }
+    }
+}
+
+
+/// Returns the strings of the field values of the object to find out its changes.
+fn field_strings(object: &HeapObject) -> Vec<String> {
+    object.fields().iter().map(|(_, value)| value.to_string()).collect()
+}
+
+
+fn root_label(kind: &RootKind) -> String {
+    match kind {
+        RootKind::Static { class_name, field } => format!("{}.{}", class_name, field.name()),
+        RootKind::Local { frame, method, index } => format!("#{} {} local {}", frame, method.name(), index),
+        RootKind::Operand { frame, method, index } => format!("#{} {} stack {}", frame, method.name(), index),
+    }
+}
+
+
+/// Splits the objects into columns by their distance from the roots,
+/// the objects unreachable from the roots are in the last column.
+fn columns(dump: &HeapDump) -> Vec<Vec<&HeapObject>> {
+    let objects: HashMap<_, _> = dump.objects().iter().map(|object| (object.id(), object)).collect();
+    let mut depths = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    for root in dump.roots() {
+        if objects.contains_key(&root.target()) && !depths.contains_key(&root.target()) {
+            depths.insert(root.target(), 0);
+            queue.push_back(root.target());
+        }
+    }
+
+    while let Some(id) = queue.pop_front() {
+        let depth = depths[&id];
+
+        for (_, value) in objects[&id].fields() {
+            if let Value::Reference(Reference::Instance(instance)) = value {
+                let target = instance.id();
+
+                if objects.contains_key(&target) && !depths.contains_key(&target) {
+                    depths.insert(target, depth + 1);
+                    queue.push_back(target);
+                }
+            }
+        }
+    }
+
+    let unreachable = depths.values().max().map_or(0, |max| max + 1);
+    let mut columns = vec![Vec::new(); unreachable + 1];
+
+    for object in dump.objects() {
+        let depth = depths.get(&object.id()).copied().unwrap_or(unreachable);
+        columns[depth].push(object);
+    }
+
+    columns.retain(|column| !column.is_empty());
+    columns
+}
+
+
+/// Draws a line with an arrow head at its end.
+fn draw_arrow(context: &Context, x1: f64, y1: f64, x2: f64, y2: f64) {
+    const HEAD: f64 = 8.0;
+
+    let angle = (y2 - y1).atan2(x2 - x1);
+
+    context.move_to(x1, y1);
+    context.line_to(x2, y2);
+    context.stroke();
+
+    context.move_to(x2, y2);
+    context.line_to(x2 - HEAD * (angle - 0.4).cos(), y2 - HEAD * (angle - 0.4).sin());
+    context.line_to(x2 - HEAD * (angle + 0.4).cos(), y2 - HEAD * (angle + 0.4).sin());
+    context.close_path();
+    context.fill();
+}
@@ -3,12 +3,19 @@ use gtk::prelude::{GtkListStoreExtManual, StaticType};
 use relm::{Relm, Update, Widget};
 use relm_derive::Msg;
 
+use crate::gui::changes::{BACKGROUND_COLUMN, diff, REMOVED_COLUMN, show_changes};
+use crate::gui::value_edit::ValueColumn;
+use crate::vm::class::instance::Instance;
 use crate::vm::types::value::Value;
 
 
 #[derive(Msg)]
 pub enum OperandStackMsg {
-    Update(Vec<Value>)
+    // the values on the stack and their values before the last step
+    Update(Vec<Value>, Option<Vec<Value>>),
+    Editable(bool),
+    Instances(Vec<Instance>),
+    Edited(usize, String),
 }
 
 
@@ -16,6 +23,7 @@ pub struct OperandStackView {
     root: Box,
     list_store: ListStore,
     _tree_view: TreeView,
+    value_column: ValueColumn,
 }
 
 
@@ -28,17 +36,28 @@ impl Update for OperandStackView {
 
     fn update(&mut self, event: OperandStackMsg) {
         match event {
-            OperandStackMsg::Update(values) => {
+            OperandStackMsg::Update(values, previous) => {
                 self.list_store.clear();
 
-                for value in values {
+                let entries = |values: &[Value]| {
+                    values.iter().enumerate().map(|(index, value)| {
+                        (index, (value.value_type().category().size().to_string(),
+                                 value.value_type().to_string(),
+                                 value.to_string()))
+                    }).collect::<Vec<_>>()
+                };
+                let previous = previous.as_deref().map(entries);
+
+                for (_, (size, value_type, value), change) in diff(previous.as_deref(), &entries(&values)) {
                     self.list_store.insert_with_values(None,
-                                                       &[0, 1, 2],
-                                                       &[&value.value_type().category().size().to_string(),
-                                                           &value.value_type().to_string(),
-                                                           &value.to_string()]);
+                                                       &[0, 1, 2, BACKGROUND_COLUMN, REMOVED_COLUMN],
+                                                       &[&size, &value_type, &value,
+                                                           &change.background(), &change.is_removed()]);
                 }
             }
+            OperandStackMsg::Editable(editable) => self.value_column.set_editable(editable),
+            OperandStackMsg::Instances(instances) => self.value_column.set_instances(&instances),
+            OperandStackMsg::Edited(..) => {}
         }
     }
 }
@@ -51,7 +70,7 @@ impl Widget for OperandStackView {
         self.root.clone()
     }
 
-    fn view(_: &Relm<Self>, _: Self::Model) -> Self {
+    fn view(relm: &Relm<Self>, _: Self::Model) -> Self {
         let tree_view = gtk::TreeView::new();
 
         let size_column = gtk::TreeViewColumn::new();
@@ -70,16 +89,16 @@ impl Widget for OperandStackView {
         type_column.add_attribute(&type_cell, "text", 1);
         tree_view.append_column(&type_column);
 
-        let value_column = gtk::TreeViewColumn::new();
-        value_column.set_title("value");
-        let value_cell = gtk::CellRendererText::new();
-        value_column.pack_start(&value_cell, true);
-        value_column.add_attribute(&value_cell, "text", 2);
-        tree_view.append_column(&value_column);
+        let stream = relm.stream().clone();
+        let value_column = ValueColumn::new(&tree_view, 2, move |index, text| {
+            stream.emit(OperandStackMsg::Edited(index, text))
+        });
 
-        let list_store = gtk::ListStore::new(&[String::static_type(), String::static_type(), String::static_type()]);
+        let list_store = gtk::ListStore::new(&[String::static_type(), String::static_type(), String::static_type(),
+            String::static_type(), bool::static_type()]);
 
         tree_view.set_model(Some(&list_store));
+        show_changes(&tree_view);
         tree_view.set_grid_lines(TreeViewGridLines::Both);
         tree_view.get_selection().set_mode(SelectionMode::None);
 
@@ -108,6 +127,7 @@ impl Widget for OperandStackView {
             root,
             list_store,
             _tree_view: tree_view,
+            value_column,
         }
     }
 }
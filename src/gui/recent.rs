@@ -0,0 +1,173 @@
+//! Recently loaded configurations, remembered between the runs of the application.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::vm::class::name::ClassName;
+
+
+/// A main class together with the class path it was loaded from.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LoadConfig {
+    main_class: ClassName,
+    class_path: Vec<PathBuf>,
+}
+
+
+impl LoadConfig {
+    pub fn new(main_class: ClassName, class_path: Vec<PathBuf>) -> Self {
+        LoadConfig {
+            main_class,
+            class_path,
+        }
+    }
+
+    pub fn main_class(&self) -> &ClassName {
+        &self.main_class
+    }
+
+    pub fn class_path(&self) -> &[PathBuf] {
+        &self.class_path
+    }
+
+    /// Returns a short description of the configuration shown to the user.
+    pub fn label(&self) -> String {
+        let class_path: Vec<_> = self.class_path.iter().map(|path| path.to_string_lossy()).collect();
+        format!("{} ({})", self.main_class, class_path.join(":"))
+    }
+}
+
+
+/// The recently loaded configurations, the most recent first.
+///
+/// The configurations are stored in the file `mvm/recent` in the user configuration
+/// directory, one configuration per line, the main class and the class path entries
+/// separated by tabs. Configurations with a tab or a line break in a class path entry
+/// are only kept in memory.
+pub struct RecentConfigs {
+    file: Option<PathBuf>,
+    configs: Vec<LoadConfig>,
+}
+
+
+impl RecentConfigs {
+    /// The maximal number of remembered configurations.
+    const MAX_CONFIGS: usize = 10;
+
+    /// Reads the configurations stored in the user configuration directory.
+    /// Missing or invalid configurations are skipped.
+    pub fn load() -> Self {
+        let file = config_dir().map(|dir| dir.join("mvm").join("recent"));
+
+        let configs = file.as_ref()
+                          .and_then(|file| fs::read_to_string(file).ok())
+                          .map(|contents| contents.lines().filter_map(parse_config).collect())
+                          .unwrap_or_default();
+
+        RecentConfigs {
+            file,
+            configs,
+        }
+    }
+
+    pub fn configs(&self) -> &[LoadConfig] {
+        &self.configs
+    }
+
+    /// Makes the configuration the most recent one and stores the configurations.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the configurations can not be stored,
+    /// they are still kept in memory.
+    pub fn add(&mut self, config: LoadConfig) -> Result<(), io::Error> {
+        self.configs.retain(|recent| *recent != config);
+        self.configs.insert(0, config);
+        self.configs.truncate(Self::MAX_CONFIGS);
+
+        let file = match &self.file {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(file, self.configs.iter().filter_map(format_config).collect::<String>())
+    }
+}
+
+
+/// Returns the user configuration directory.
+fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+}
+
+
+fn parse_config(line: &str) -> Option<LoadConfig> {
+    let mut parts = line.split('\t');
+    let main_class = ClassName::new(parts.next()?).ok()?;
+    let class_path: Vec<_> = parts.filter(|part| !part.is_empty()).map(PathBuf::from).collect();
+
+    if class_path.is_empty() {
+        return None;
+    }
+
+    Some(LoadConfig::new(main_class, class_path))
+}
+
+
+/// Formats the configuration as a line, `None` if a class path entry contains a separator.
+fn format_config(config: &LoadConfig) -> Option<String> {
+    let mut line = config.main_class.to_string();
+
+    for entry in &config.class_path {
+        let entry = entry.to_string_lossy();
+
+        if entry.contains(['\t', '\n', '\r']) {
+            return None;
+        }
+
+        line.push('\t');
+        line.push_str(&entry);
+    }
+
+    line.push('\n');
+    Some(line)
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::gui::recent::{format_config, LoadConfig, parse_config};
+    use crate::vm::class::name::ClassName;
+
+
+    #[test]
+    fn round_trip() {
+        let config = LoadConfig::new(ClassName::new("a.Main").unwrap(), vec![PathBuf::from("classes"), PathBuf::from("lib/b c.jar")]);
+        let line = format_config(&config).unwrap();
+        assert_eq!(line, "a.Main\tclasses\tlib/b c.jar\n");
+        assert_eq!(parse_config(line.trim_end()), Some(config));
+
+        assert_eq!(parse_config("a.Main"), None);
+        assert_eq!(parse_config("a.Main\t"), None);
+    }
+
+
+    #[test]
+    fn separators() {
+        for entry in &["a\tb", "a\nb", "a\rb"] {
+            let config = LoadConfig::new(ClassName::new("Main").unwrap(), vec![PathBuf::from(entry)]);
+            assert_eq!(format_config(&config), None);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! Editing of values by the user when the program is paused.
+
+use gtk::{CellLayoutExt, CellRendererCombo, CellRendererComboExt, CellRendererTextExt, GtkListStoreExt, ListStore, TreeView, TreeViewColumn, TreeViewColumnExt, TreeViewExt};
+use gtk::prelude::{GtkListStoreExtManual, StaticType};
+
+use crate::vm::class::descriptor::TypeDesc;
+use crate::vm::class::instance::Instance;
+use crate::vm::types::double::Double;
+use crate::vm::types::float::Float;
+use crate::vm::types::int::Int;
+use crate::vm::types::long::Long;
+use crate::vm::types::reference::Reference;
+use crate::vm::types::value::{Value, ValueType};
+
+
+/// An editable value column of a tree view. A value is typed into the cell
+/// or a reference is picked from the existing instances.
+pub struct ValueColumn {
+    cell: CellRendererCombo,
+    choices: ListStore,
+}
+
+
+impl ValueColumn {
+    /// Appends the column showing the text of the given model column to the tree view.
+    /// `on_edited` is called with the index of the edited row and the new text.
+    pub fn new<F>(tree_view: &TreeView, model_column: i32, on_edited: F) -> Self
+                  where F: Fn(usize, String) + 'static {
+        let choices = ListStore::new(&[String::static_type()]);
+
+        let cell = CellRendererCombo::new();
+        cell.set_property_model(Some(&choices));
+        cell.set_property_text_column(0);
+        cell.set_property_has_entry(true);
+        cell.set_property_editable(false);
+        cell.connect_edited(move |_, path, text| {
+            if let Some(index) = path.get_indices().first() {
+                on_edited(*index as usize, text.to_owned());
+            }
+        });
+
+        let column = TreeViewColumn::new();
+        column.set_title("value");
+        column.pack_start(&cell, true);
+        column.add_attribute(&cell, "text", model_column);
+        tree_view.append_column(&column);
+
+        ValueColumn { cell, choices }
+    }
+
+    /// Allows or forbids editing of the values.
+    pub fn set_editable(&self, editable: bool) {
+        self.cell.set_property_editable(editable);
+    }
+
+    /// Replaces the references which can be picked by the references to the instances.
+    pub fn set_instances(&self, instances: &[Instance]) {
+        self.choices.clear();
+
+        for choice in reference_choices(instances) {
+            self.choices.insert_with_values(None, &[0], &[&choice]);
+        }
+    }
+}
+
+
+/// Parses the text as a value of the given type. References are written as `null`,
+/// or as an existing instance either in the displayed form `Class@id` or by its id.
+/// References to instances are checked to be assignable to the type. The class
+/// of a null reference is not known, so only `null` can replace it.
+pub fn parse_value(text: &str, value_type: &ValueType, instances: &[Instance]) -> Result<Value, String> {
+    let text = text.trim();
+    let invalid = || format!("'{}' is not a valid {}", text, value_type);
+
+    let value = match value_type {
+        ValueType::Int => Int::new(text.parse().map_err(|_| invalid())?).into(),
+        ValueType::Long => Long::new(text.parse().map_err(|_| invalid())?).into(),
+        ValueType::Float => Float::new(text.parse().map_err(|_| invalid())?).into(),
+        ValueType::Double => Double::new(text.parse().map_err(|_| invalid())?).into(),
+        ValueType::Null | ValueType::Reference(_) | ValueType::AnyReference => parse_reference(text, instances)?.into(),
+        ValueType::Void => return Err(invalid()),
+    };
+
+    match value_type {
+        ValueType::Reference(class_name) => {
+            let type_desc = TypeDesc::Reference(class_name.clone());
+
+            if !type_desc.is_assignable_with(&value) {
+                return Err(format!("{} can not be assigned to {}", value, type_desc));
+            }
+        }
+        ValueType::Null | ValueType::AnyReference if value.value_type() != ValueType::Null => {
+            return Err(format!("the class of the replaced reference is not known, {} can not be assigned", value));
+        }
+        _ => {}
+    }
+
+    Ok(value)
+}
+
+
+/// Parses the text as a value assignable to the given type descriptor.
+pub fn parse_field_value(text: &str, type_desc: &TypeDesc, instances: &[Instance]) -> Result<Value, String> {
+    let value = parse_value(text, &type_desc.value_type(), instances)?;
+
+    if !type_desc.is_assignable_with(&value) {
+        return Err(format!("{} can not be assigned to {}", value, type_desc));
+    }
+
+    Ok(value)
+}
+
+
+/// Returns the texts of the references which can be picked.
+fn reference_choices(instances: &[Instance]) -> Vec<String> {
+    std::iter::once(Reference::null().to_string())
+        .chain(instances.iter().map(|instance| Reference::new(instance.clone()).to_string()))
+        .collect()
+}
+
+
+fn parse_reference(text: &str, instances: &[Instance]) -> Result<Reference, String> {
+    if text == "null" {
+        return Ok(Reference::null());
+    }
+
+    let id = text.rsplit('@').next().unwrap_or(text);
+
+    instances.iter()
+             .find(|instance| instance.id().to_string() == id)
+             .map(|instance| Reference::new(instance.clone()))
+             .ok_or_else(|| format!("there is no instance '{}'", text))
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::gui::value_edit::parse_value;
+    use crate::vm::class::name::ClassName;
+    use crate::vm::exec::class_source::MemorySource;
+    use crate::vm::exec::vm::Vm;
+    use crate::vm::types::value::ValueType;
+
+
+    #[test]
+    fn references() {
+        let node = ClassName::new("Node").unwrap();
+        let other = ClassName::new("Other").unwrap();
+        let source = MemorySource::default()
+            .with_class(node.clone(), "Node")
+            .with_class(other.clone(), "Other");
+
+        let vm = Arc::new(Vm::new(source));
+        let node_instance = vm.create_instance(vm.resolve_class(&node).unwrap()).unwrap();
+        let other_instance = vm.create_instance(vm.resolve_class(&other).unwrap()).unwrap();
+        let instances = vec![node_instance.clone(), other_instance.clone()];
+
+        let node_type = ValueType::Reference(node);
+        let node_id = node_instance.id().to_string();
+        let other_id = other_instance.id().to_string();
+        assert!(parse_value(&node_id, &node_type, &instances).is_ok());
+        assert!(parse_value(&other_id, &node_type, &instances).is_err());
+        assert!(parse_value("null", &node_type, &instances).is_ok());
+
+        // a null local may hold any class, so no instance can be checked against it
+        assert!(parse_value("null", &ValueType::Null, &instances).is_ok());
+        assert!(parse_value(&node_id, &ValueType::Null, &instances).is_err());
+        assert!(parse_value(&other_id, &ValueType::Null, &instances).is_err());
+    }
+}
@@ -2,27 +2,39 @@ use std::boxed::Box as StdBox;
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use gtk::{Box, BoxExt, Button, ButtonBox, ButtonBoxExt, ButtonBoxStyle, ButtonExt, ButtonsType, DialogExt, DialogFlags, GtkWindowExt, MessageDialog, MessageType, Orientation, Paned, PanedExt, StyleContextExt, WidgetExt, Window};
-use gtk::prelude::Cast;
-use relm::{Channel, Component, connect, create_component, Relm, Sender, Update, Widget};
+use gdk::SELECTION_CLIPBOARD;
+use gtk::{Align, Box, BoxExt, Button, ButtonBox, ButtonBoxExt, ButtonBoxStyle, ButtonExt, ButtonsType, Clipboard, ComboBoxExt, ComboBoxText, ComboBoxTextExt, ContainerExt, DialogExt, DialogFlags, FileChooserAction, FileChooserDialog, FileChooserExt, GtkWindowExt, InfoBar, InfoBarExt, Label, LabelExt, MessageDialog, MessageDialogExt, MessageType, NONE_ADJUSTMENT, Notebook, Orientation, Paned, PanedExt, ResponseType, ScrolledWindow, ScrolledWindowExt, StyleContextExt, WidgetExt, Window};
+use gtk::prelude::{Cast, ComboBoxExtManual, NotebookExtManual};
+use relm::{Channel, Component, connect, create_component, interval, Relm, Sender, Update, Widget};
 use relm_derive::Msg;
 
+use crate::gui::changes::summary;
 use crate::gui::classes::{ClassesMsg, ClassesView};
+use crate::gui::editor::{EditorMsg, EditorView};
 use crate::gui::fields::{FieldsMsg, FieldsView, Viewed};
 use crate::gui::instances::{InstancesMsg, InstancesView};
 use crate::gui::instructions::{InstructionsMsg, InstructionsView};
+use crate::gui::java_source::{JavaSourceMsg, JavaSourceView};
 use crate::gui::locals::{LocalsMsg, LocalsView};
+use crate::gui::object_graph::{ObjectGraphMsg, ObjectGraphView};
 use crate::gui::operand_stack::{OperandStackMsg, OperandStackView};
+use crate::gui::value_edit::{parse_field_value, parse_value};
 use crate::vm::{
     class::{
         name::{ClassName},
     },
 };
 use crate::vm::class::class::Class;
-use crate::vm::class::instance::Instance;
+use crate::vm::class::instance::{Instance, InstanceId};
+use crate::vm::class::signature::FieldSig;
+use crate::vm::exec::class_source::CompositeSource;
 use crate::vm::exec::error::ExecError;
+use crate::vm::exec::stack_trace::StackTrace;
+use crate::vm::exec::heap_dump::HeapDump;
 use crate::vm::exec::vm::Vm;
 use crate::vm::memory::frame::Frame as VmFrame;
+use crate::vm::memory::locals::Slot;
+use crate::vm::types::value::Value;
 
 use super::{
     frame_stack::{FrameStackMsg, FrameStackView},
@@ -33,13 +45,23 @@ use super::{
 pub enum VmMsg {
     Update,
     Ended,
-    Error(ExecError),
+    Error(ExecError, StackTrace),
     NextStep,
+    Run,
+    Snapshot,
     Reload,
+    ExportHeap,
+    ChangeWatchMode(WatchMode),
+    CheckFiles,
 
     SelectFrame(usize, Arc<VmFrame>),
     SelectClass(Arc<Class>),
     SelectInstance(Instance),
+    SelectObject(InstanceId),
+
+    EditLocal(usize, String),
+    EditOperand(usize, String),
+    EditField(Viewed, FieldSig, String),
 
     Load(ClassName, Vec<PathBuf>),
 }
@@ -48,7 +70,23 @@ pub enum VmMsg {
 enum VmChannelMsg {
     Update,
     Ended,
-    Error(ExecError),
+    Error(ExecError, StackTrace),
+}
+
+
+/// What to do when the class files of the loaded classes change.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum WatchMode {
+    Ignore,
+    Prompt,
+    Reload,
+}
+
+
+impl WatchMode {
+    const ALL: [(WatchMode, &'static str); 3] = [(WatchMode::Ignore, "Ignore file changes"),
+                                                  (WatchMode::Prompt, "Prompt on file changes"),
+                                                  (WatchMode::Reload, "Reload on file changes")];
 }
 
 
@@ -57,6 +95,31 @@ pub struct VmState {
     main_class: ClassName,
     path: Vec<PathBuf>,
     joined: bool,
+    running: bool,
+    frame: Option<Arc<VmFrame>>,
+    previous: Vec<FrameValues>,
+    watch: WatchMode,
+}
+
+
+/// Values of a frame before the last step.
+struct FrameValues {
+    frame: Arc<VmFrame>,
+    pc: isize,
+    locals: Vec<Slot>,
+    operands: Vec<Value>,
+}
+
+
+impl FrameValues {
+    fn new(frame: &Arc<VmFrame>) -> Self {
+        FrameValues {
+            frame: frame.clone(),
+            pc: frame.pc(),
+            locals: frame.locals().values(),
+            operands: frame.stack().values(),
+        }
+    }
 }
 
 
@@ -69,11 +132,20 @@ pub struct VmView {
     locals: Component<LocalsView>,
     operand_stack: Component<OperandStackView>,
     instructions: Component<InstructionsView>,
+    java_source: Component<JavaSourceView>,
     instances: Component<InstancesView>,
     classes: Component<ClassesView>,
+    object_graph: Component<ObjectGraphView>,
     fields: Component<FieldsView>,
+    editor: Component<EditorView>,
+    summary: Label,
     next_button: Button,
+    run_button: Button,
     _reload_button: Button,
+    _export_button: Button,
+    _watch_chooser: ComboBoxText,
+    stale_bar: InfoBar,
+    stale_label: Label,
 }
 
 
@@ -84,86 +156,220 @@ impl Update for VmView {
 
     fn model(_: &Relm<Self>, args: (ClassName, Vec<PathBuf>)) -> VmState {
         VmState {
-            vm: Arc::new(Vm::new(args.1.clone())),
+            vm: Arc::new(Vm::new(CompositeSource::class_path(args.1.clone()))),
             main_class: args.0,
             path: args.1,
             joined: false,
+            running: false,
+            frame: None,
+            previous: Vec::new(),
+            watch: WatchMode::Ignore,
         }
     }
 
     fn update(&mut self, event: VmMsg) {
         match event {
             VmMsg::Update => {
-                let frames = self.model.vm.frames().unwrap();
-                let frame = frames.last().unwrap();
+                let frames = self.model.vm.frames().unwrap_or_default();
                 let classes = self.model.vm.classes();
+                let allocations = self.model.vm.allocations();
                 let instances = self.model.vm.instances();
-                let locals = frame.locals().values();
-                let operands = frame.stack().values();
-                let class = frame.class().clone();
-                let method = frame.method().clone();
-                let pc = frame.pc();
 
+                // the snapshot of a running program is empty until the first one is published
+                if let Some(frame) = frames.last() {
+                    let locals = frame.locals().values();
+                    let operands = frame.stack().values();
+
+                    let previous = self.previous_values(frame);
+
+                    self.locals.emit(LocalsMsg::Update(locals, previous.map(|values| values.locals.clone())));
+                    self.operand_stack.emit(OperandStackMsg::Update(operands, previous.map(|values| values.operands.clone())));
+                    self.show_code(frame);
+                }
+
+                // the snapshots of a running program are new frames, so they are not compared
+                if self.model.running {
+                    self.summary.set_label("");
+                    self.model.previous.clear();
+                } else {
+                    self.summary.set_label(&self.step_summary(&frames));
+                    self.model.previous = frames.iter().map(FrameValues::new).collect();
+                }
+
+                self.model.frame = frames.last().cloned();
+                self.locals.emit(LocalsMsg::Instances(instances.clone()));
+                self.operand_stack.emit(OperandStackMsg::Instances(instances.clone()));
+                self.fields.emit(FieldsMsg::Instances(instances.clone()));
                 self.frame_stack.emit(FrameStackMsg::Update(frames));
                 self.frame_stack.emit(FrameStackMsg::SelectTopFrame);
-                self.locals.emit(LocalsMsg::Update(locals));
-                self.operand_stack.emit(OperandStackMsg::Update(operands));
-                self.instructions.emit(InstructionsMsg::ChangeViewed(class, method));
-                self.instructions.emit(InstructionsMsg::SelectInstruction(pc));
                 self.instances.emit(InstancesMsg::Update(instances));
-                self.classes.emit(ClassesMsg::Update(classes));
+                self.classes.emit(ClassesMsg::Update(classes, allocations, self.model.vm.class_entries()));
+                self.object_graph.emit(ObjectGraphMsg::Update(HeapDump::new(&self.model.vm)));
                 self.fields.emit(FieldsMsg::Update);
             }
             VmMsg::Ended => {
                 self.next_button.set_sensitive(false);
+                self.run_button.set_sensitive(false);
+                self.set_editable(false);
                 self.model.joined = true;
                 self.model.vm.join();
+
+                if self.model.running {
+                    self.model.running = false;
+                    self.relm.stream().emit(VmMsg::Update);
+                }
             }
-            VmMsg::Error(error) => {
+            VmMsg::Error(error, trace) => {
                 self.next_button.set_sensitive(false);
+                self.run_button.set_sensitive(false);
+                self.set_editable(false);
+                self.show_failure(&error, &trace);
+            }
+            VmMsg::ExportHeap => {
                 let window = self.root.get_toplevel().unwrap().downcast::<Window>().unwrap();
-                let dialog = MessageDialog::new(
+                let dialog = FileChooserDialog::with_buttons(
+                    Some("Export heap"),
                     Some(&window),
-                    DialogFlags::MODAL,
-                    MessageType::Error,
-                    ButtonsType::Ok,
-                    &format!("Error: {}", error),
+                    FileChooserAction::Save,
+                    &[("Cancel", ResponseType::Cancel), ("Export", ResponseType::Accept)],
                 );
-                dialog.connect_response(|dialog, _| dialog.close());
-                dialog.show_all();
+                dialog.set_do_overwrite_confirmation(true);
+                dialog.set_current_name("heap.json");
+
+                let path = match dialog.run() {
+                    ResponseType::Accept => dialog.get_filename(),
+                    _ => None,
+                };
+                dialog.close();
+
+                if let Some(path) = path {
+                    if let Err(error) = HeapDump::new(&self.model.vm).write(&path) {
+                        self.show_error(&format!("can not write the heap dump: {}", error));
+                    }
+                }
+            }
+            VmMsg::ChangeWatchMode(watch) => {
+                self.model.watch = watch;
+                self.relm.stream().emit(VmMsg::CheckFiles);
+            }
+            VmMsg::CheckFiles => {
+                let stale = match self.model.watch {
+                    WatchMode::Ignore => Vec::new(),
+                    WatchMode::Prompt | WatchMode::Reload => self.model.vm.stale_classes(),
+                };
+
+                self.classes.emit(ClassesMsg::Stale(stale.clone()));
+
+                if stale.is_empty() {
+                    self.stale_bar.hide();
+                } else if self.model.watch == WatchMode::Reload {
+                    self.relm.stream().emit(VmMsg::Reload);
+                } else {
+                    let names = stale.iter().map(|name| name.as_ref()).collect::<Vec<_>>().join(", ");
+                    self.stale_label.set_label(&format!("Changed on disk: {}", names));
+                    self.stale_bar.show();
+                }
             }
             VmMsg::NextStep => {
                 self.model.vm.next();
             }
-            VmMsg::Reload => {
-                if !self.model.joined {
-                    self.model.vm.set_end_callback(None);
-                    self.model.vm.thread().unwrap().cancel();
-                    self.model.vm.join();
+            VmMsg::Run => {
+                self.restart();
+                self.model.running = true;
+                self.model.vm.clone().run(self.model.main_class.clone(), Vec::new());
+                self.next_button.set_sensitive(false);
+                self.run_button.set_sensitive(false);
+                self.set_editable(false);
+            }
+            VmMsg::Snapshot => {
+                if self.model.running {
+                    self.model.vm.request_snapshot();
                 }
-
-                self.model.joined = false;
-                self.model.vm = Arc::new(Vm::new(self.model.path.clone()));
-                self.connect();
+            }
+            VmMsg::Reload => {
+                self.restart();
+                self.model.vm.clone().start(self.model.main_class.clone(), Vec::new());
                 self.next_button.set_sensitive(true);
+                self.run_button.set_sensitive(true);
+                self.set_editable(true);
             }
             VmMsg::SelectFrame(_, frame) => {
-                self.locals.emit(LocalsMsg::Update(frame.locals().values()));
-                self.operand_stack.emit(OperandStackMsg::Update(frame.stack().values()));
-                self.instructions.emit(InstructionsMsg::ChangeViewed(frame.class().clone(), frame.method().clone()));
-                self.instructions.emit(InstructionsMsg::SelectInstruction(frame.pc()));
+                let previous = self.previous_values(&frame);
+
+                self.locals.emit(LocalsMsg::Update(frame.locals().values(), previous.map(|values| values.locals.clone())));
+                self.operand_stack.emit(OperandStackMsg::Update(frame.stack().values(), previous.map(|values| values.operands.clone())));
+                self.model.frame = Some(frame.clone());
+                self.show_code(&frame);
             }
             VmMsg::SelectClass(class) => {
                 self.instances.emit(InstancesMsg::Unselect);
+                self.editor.emit(EditorMsg::OpenClass(class.name().clone()));
                 self.fields.emit(FieldsMsg::ChangeViewed(Viewed::Class(class)));
             }
             VmMsg::SelectInstance(instance) => {
                 self.classes.emit(ClassesMsg::Unselect);
                 self.fields.emit(FieldsMsg::ChangeViewed(Viewed::Instance(instance)));
             }
+            VmMsg::SelectObject(id) => {
+                let instance = self.model.vm.instances().into_iter().find(|instance| instance.id() == id);
+
+                if let Some(instance) = instance {
+                    self.instances.emit(InstancesMsg::Unselect);
+                    self.relm.stream().emit(VmMsg::SelectInstance(instance));
+                }
+            }
+            VmMsg::EditLocal(index, text) => {
+                if let Some(frame) = self.model.frame.clone() {
+                    let result = match frame.locals().values().get(index) {
+                        Some(Slot::Value(value)) => {
+                            parse_value(&text, &value.value_type(), &self.model.vm.instances())
+                                .and_then(|value| frame.locals().store_value(index, value).map_err(|error| error.to_string()))
+                        }
+                        _ => Err(format!("the local variable {} is undefined", index)),
+                    };
+
+                    match result {
+                        Ok(()) => self.refresh_edited(&frame),
+                        Err(error) => self.show_error(&format!("can not set the local variable: {}", error)),
+                    }
+                }
+            }
+            VmMsg::EditOperand(index, text) => {
+                if let Some(frame) = self.model.frame.clone() {
+                    let result = match frame.stack().values().get(index) {
+                        Some(value) => {
+                            parse_value(&text, &value.value_type(), &self.model.vm.instances())
+                                .and_then(|value| frame.stack().replace_value(index, value).map_err(|error| error.to_string()))
+                        }
+                        None => Err(format!("there is no operand {}", index)),
+                    };
+
+                    match result {
+                        Ok(()) => self.refresh_edited(&frame),
+                        Err(error) => self.show_error(&format!("can not set the operand: {}", error)),
+                    }
+                }
+            }
+            VmMsg::EditField(viewed, sig, text) => {
+                let result = parse_field_value(&text, sig.type_desc(), &self.model.vm.instances())
+                    .and_then(|value| {
+                        match &viewed {
+                            Viewed::Class(class) => class.set_static_field_value(&sig, value),
+                            Viewed::Instance(instance) => instance.class().set_instance_field_value(instance, &sig, value),
+                            Viewed::None => Ok(()),
+                        }.map_err(|error| error.to_string())
+                    });
+
+                match (result, self.model.frame.clone()) {
+                    (Ok(()), Some(frame)) => self.refresh_edited(&frame),
+                    (Ok(()), None) => self.fields.emit(FieldsMsg::Update),
+                    (Err(error), _) => self.show_error(&format!("can not set the field: {}", error)),
+                }
+            }
             VmMsg::Load(clas_name, path) => {
                 self.model.main_class = clas_name;
                 self.model.path = path;
+                self.editor.emit(EditorMsg::ChangeClassPath(self.model.path.clone()));
                 self.relm.stream().emit(VmMsg::Reload);
             }
         }
@@ -183,9 +389,13 @@ impl Widget for VmView {
         let locals = create_component::<LocalsView>(());
         let operand_stack = create_component::<OperandStackView>(());
         let instructions = create_component::<InstructionsView>(());
+        let java_source = create_component::<JavaSourceView>(());
         let instances = create_component::<InstancesView>(());
         let classes = create_component::<ClassesView>(());
+        let object_graph = create_component::<ObjectGraphView>(());
         let fields = create_component::<FieldsView>(());
+        let editor = create_component::<EditorView>(());
+        editor.emit(EditorMsg::ChangeClassPath(model.path.clone()));
 
         let center_box = Box::new(Orientation::Vertical, 0);
         let paned_left_horizontal = Paned::new(Orientation::Horizontal);
@@ -193,19 +403,57 @@ impl Widget for VmView {
         let paned_center = Paned::new(Orientation::Horizontal);
         let paned_right_vertical = Paned::new(Orientation::Vertical);
         let paned_right_horizontal = Paned::new(Orientation::Horizontal);
+        let paned_heap = Paned::new(Orientation::Horizontal);
         let root = Paned::new(Orientation::Horizontal);
 
         let reload_button = Button::with_label("Reload");
         reload_button.get_style_context().add_class("destructive-action");
+        let export_button = Button::with_label("Export heap");
+        let run_button = Button::with_label("Run");
         let next_button = Button::with_label("Next");
         next_button.get_style_context().add_class("suggested-action");
+        let watch_chooser = ComboBoxText::new();
+        for (_, label) in &WatchMode::ALL {
+            watch_chooser.append_text(label);
+        }
+        watch_chooser.set_active(Some(0));
+
         let control = ButtonBox::new(Orientation::Horizontal);
         control.set_property_margin(10);
         control.set_layout(ButtonBoxStyle::Edge);
         control.pack_start(&reload_button, false, false, 0);
+        control.pack_start(&export_button, false, false, 0);
+        control.pack_start(&watch_chooser, false, false, 0);
+        control.pack_start(&run_button, false, false, 0);
         control.pack_start(&next_button, false, false, 0);
 
-        center_box.pack_start(instructions.widget(), true, true, 0);
+        let summary = Label::new(None);
+        summary.get_style_context().add_class("step-summary");
+        summary.set_halign(Align::Start);
+        summary.set_margin_start(10);
+        summary.set_margin_end(10);
+        summary.set_selectable(true);
+
+        let stale_label = Label::new(None);
+        stale_label.set_halign(Align::Start);
+        let stale_bar = InfoBar::new();
+        stale_bar.set_message_type(MessageType::Warning);
+        stale_bar.get_content_area().pack_start(&stale_label, true, true, 0);
+        stale_bar.add_button("Reload", ResponseType::Accept);
+        stale_bar.set_no_show_all(true);
+        stale_label.show();
+
+        let notebook = Notebook::new();
+        let paned_code = Paned::new(Orientation::Horizontal);
+        paned_code.pack1(instructions.widget(), true, false);
+        paned_code.pack2(java_source.widget(), true, false);
+
+        notebook.append_page(&paned_code, Some(&Label::new(Some("Instructions"))));
+        notebook.append_page(editor.widget(), Some(&Label::new(Some("Source"))));
+
+        center_box.pack_start(&stale_bar, false, true, 0);
+        center_box.pack_start(&notebook, true, true, 0);
+        center_box.pack_start(&summary, false, true, 0);
         center_box.pack_start(&control, false, true, 0);
         paned_left_vertical.pack1(locals.widget(), false, false);
         paned_left_vertical.pack2(operand_stack.widget(), false, false);
@@ -213,7 +461,9 @@ impl Widget for VmView {
         paned_left_horizontal.pack2(&paned_left_vertical, false, false);
         paned_right_horizontal.pack1(instances.widget(), false, false);
         paned_right_horizontal.pack2(classes.widget(), false, false);
-        paned_right_vertical.pack1(&paned_right_horizontal, false, false);
+        paned_heap.pack1(&paned_right_horizontal, false, false);
+        paned_heap.pack2(object_graph.widget(), true, false);
+        paned_right_vertical.pack1(&paned_heap, false, false);
         paned_right_vertical.pack2(fields.widget(), false, false);
         paned_center.pack1(&center_box, true, false);
         paned_center.pack2(&paned_right_vertical, false, false);
@@ -226,12 +476,39 @@ impl Widget for VmView {
             connect_clicked(_),
             VmMsg::NextStep
         );
+        connect!(
+            relm,
+            run_button,
+            connect_clicked(_),
+            VmMsg::Run
+        );
         connect!(
             relm,
             reload_button,
             connect_clicked(_),
             VmMsg::Reload
         );
+        connect!(
+            relm,
+            export_button,
+            connect_clicked(_),
+            VmMsg::ExportHeap
+        );
+
+        connect!(
+            relm,
+            watch_chooser,
+            connect_changed(chooser),
+            chooser.get_active().map(|index| VmMsg::ChangeWatchMode(WatchMode::ALL[index as usize].0))
+        );
+        connect!(
+            relm,
+            stale_bar,
+            connect_response(_, _),
+            VmMsg::Reload
+        );
+        interval(relm.stream(), Self::SNAPSHOT_INTERVAL, || VmMsg::Snapshot);
+        interval(relm.stream(), Self::WATCH_INTERVAL, || VmMsg::CheckFiles);
 
         connect!(
             frame_stack@FrameStackMsg::FrameActivated(ref index, ref frame),
@@ -248,13 +525,38 @@ impl Widget for VmView {
             relm,
             VmMsg::SelectClass(class.clone())
         );
+        connect!(
+            object_graph@ObjectGraphMsg::InstanceActivated(ref id),
+            relm,
+            VmMsg::SelectObject(*id)
+        );
+        connect!(
+            editor@EditorMsg::Saved,
+            relm,
+            VmMsg::Reload
+        );
+        connect!(
+            locals@LocalsMsg::Edited(index, ref text),
+            relm,
+            VmMsg::EditLocal(index, text.clone())
+        );
+        connect!(
+            operand_stack@OperandStackMsg::Edited(index, ref text),
+            relm,
+            VmMsg::EditOperand(index, text.clone())
+        );
+        connect!(
+            fields@FieldsMsg::FieldEdited(ref viewed, ref sig, ref text),
+            relm,
+            VmMsg::EditField(viewed.clone(), sig.clone(), text.clone())
+        );
 
         let stream = relm.stream().clone();
         let (channel, sender) = Channel::new(move |msg| {
             match msg {
                 VmChannelMsg::Update => stream.emit(VmMsg::Update),
                 VmChannelMsg::Ended => stream.emit(VmMsg::Ended),
-                VmChannelMsg::Error(error) => stream.emit(VmMsg::Error(error)),
+                VmChannelMsg::Error(error, trace) => stream.emit(VmMsg::Error(error, trace)),
             }
         });
 
@@ -268,21 +570,206 @@ impl Widget for VmView {
             locals,
             operand_stack,
             instructions,
+            java_source,
             instances,
             classes,
+            object_graph,
             fields,
+            editor,
+            summary,
             next_button,
+            run_button,
             _reload_button: reload_button,
+            _export_button: export_button,
+            _watch_chooser: watch_chooser,
+            stale_bar,
+            stale_label,
             vm_channel: (channel, sender),
         };
 
         view.connect();
+        view.model.vm.clone().start(view.model.main_class.clone(), Vec::new());
+        view.set_editable(true);
         view
     }
 }
 
 
 impl VmView {
+    /// Milliseconds between the snapshots of a running program.
+    const SNAPSHOT_INTERVAL: u32 = 250;
+
+    /// Milliseconds between the checks of the class files for changes.
+    const WATCH_INTERVAL: u32 = 1000;
+
+    /// Shows the code of the frame with the current instruction selected,
+    /// next to the Java source the code was compiled from.
+    fn show_code(&self, frame: &Arc<VmFrame>) {
+        let source = self.model.vm.java_source_file(frame.class());
+
+        self.instructions.emit(InstructionsMsg::ChangeViewed(frame.class().clone(), frame.method().clone()));
+        self.instructions.emit(InstructionsMsg::SelectInstruction(frame.pc()));
+        self.java_source.emit(JavaSourceMsg::ChangeViewed(source));
+        self.java_source.emit(JavaSourceMsg::SelectLine(frame.source_line()));
+    }
+
+    /// Stops the current virtual machine and replaces it with a new connected one.
+    fn restart(&mut self) {
+        if !self.model.joined {
+            self.model.vm.set_end_callback(None);
+            self.model.vm.stop();
+            self.model.vm.join();
+        }
+
+        self.model.joined = false;
+        self.model.running = false;
+        self.model.previous.clear();
+        self.summary.set_label("");
+        self.stale_bar.hide();
+        self.classes.emit(ClassesMsg::Stale(Vec::new()));
+        self.model.vm = Arc::new(Vm::new(CompositeSource::class_path(self.model.path.clone())));
+        self.connect();
+    }
+
+    /// Shows a modal dialog with the error message.
+    fn show_error(&self, message: &str) {
+        let window = self.root.get_toplevel().unwrap().downcast::<Window>().unwrap();
+        let dialog = MessageDialog::new(
+            Some(&window),
+            DialogFlags::MODAL,
+            MessageType::Error,
+            ButtonsType::Ok,
+            "Error",
+        );
+
+        let details = Label::new(Some(message));
+        details.get_style_context().add_class("error-details");
+        details.set_selectable(true);
+        details.set_halign(Align::Start);
+
+        if let Some(area) = dialog.get_message_area() {
+            area.downcast::<Box>().unwrap().pack_start(&details, false, false, 0);
+        }
+
+        dialog.connect_response(|dialog, _| dialog.close());
+        dialog.show_all();
+    }
+
+    /// Shows a modal dialog with the error the program failed with
+    /// and the stack trace, which can be copied to the clipboard.
+    fn show_failure(&self, error: &ExecError, trace: &StackTrace) {
+        const COPY: ResponseType = ResponseType::Other(1);
+
+        let window = self.root.get_toplevel().unwrap().downcast::<Window>().unwrap();
+        let dialog = MessageDialog::new(
+            Some(&window),
+            DialogFlags::MODAL,
+            MessageType::Error,
+            ButtonsType::Ok,
+            "Error",
+        );
+        dialog.add_button("Copy stack trace", COPY);
+
+        let details = Label::new(Some(&error.to_string()));
+        details.get_style_context().add_class("error-details");
+        details.set_selectable(true);
+        details.set_halign(Align::Start);
+
+        let trace_label = Label::new(Some(trace.to_string().trim_end()));
+        trace_label.get_style_context().add_class("error-details");
+        trace_label.set_selectable(true);
+        trace_label.set_halign(Align::Start);
+        trace_label.set_valign(Align::Start);
+
+        // a deep recursion has a long stack trace
+        let scrolled = ScrolledWindow::new(NONE_ADJUSTMENT, NONE_ADJUSTMENT);
+        scrolled.set_propagate_natural_width(true);
+        scrolled.set_propagate_natural_height(true);
+        scrolled.set_max_content_height(300);
+        scrolled.add(&trace_label);
+
+        if let Some(area) = dialog.get_message_area() {
+            let area = area.downcast::<Box>().unwrap();
+            area.pack_start(&details, false, false, 0);
+            area.pack_start(&scrolled, true, true, 0);
+        }
+
+        let text = format!("error: {}\n{}", error, trace);
+        dialog.connect_response(move |dialog, response| {
+            if response == COPY {
+                Clipboard::get(&SELECTION_CLIPBOARD).set_text(&text);
+            } else {
+                dialog.close();
+            }
+        });
+        dialog.show_all();
+    }
+
+    /// Allows editing of the values only when the program is paused.
+    fn set_editable(&self, editable: bool) {
+        self.locals.emit(LocalsMsg::Editable(editable));
+        self.operand_stack.emit(OperandStackMsg::Editable(editable));
+        self.fields.emit(FieldsMsg::Editable(editable));
+    }
+
+    /// Returns the values of the frame before the last step.
+    fn previous_values(&self, frame: &Arc<VmFrame>) -> Option<&FrameValues> {
+        self.model.previous.iter().find(|values| Arc::ptr_eq(&values.frame, frame))
+    }
+
+    /// Returns the summary of the effect of the last step on the frame which executed it.
+    fn step_summary(&self, frames: &[Arc<VmFrame>]) -> String {
+        let executed = match self.model.previous.last() {
+            Some(executed) => executed,
+            None => return String::new(),
+        };
+
+        let instruction = match executed.frame.method().code().instruction_str(executed.pc as usize) {
+            Ok(instruction) => instruction,
+            Err(_) => return String::new(),
+        };
+
+        let before = executed.operands.iter().map(Value::to_string).collect::<Vec<_>>();
+        let after = executed.frame.stack().values().iter().map(Value::to_string).collect::<Vec<_>>();
+        let kept = before.iter().zip(&after).take_while(|(before, after)| before == after).count();
+
+        let operands = before[kept..].to_vec();
+        let mut results = after[kept..].to_vec();
+
+        for (index, (before, after)) in executed.locals.iter().zip(executed.frame.locals().values()).enumerate() {
+            if let (before, Slot::Value(after)) = (slot_str(before), &after) {
+                if before != after.to_string() {
+                    results.push(format!("local {} = {}", index, after));
+                }
+            }
+        }
+
+        match frames.iter().position(|frame| Arc::ptr_eq(frame, &executed.frame)) {
+            None => results.push("return".to_owned()),
+            Some(index) if index + 1 < frames.len() => {
+                let called = frames.last().unwrap();
+                results.push(format!("call {}.{}", called.class().name(), called.method().signature().name()));
+            }
+            Some(_) => {}
+        }
+
+        summary(&instruction, &operands, &results)
+    }
+
+    /// Shows the values of the frame and the heap after an edit.
+    fn refresh_edited(&mut self, frame: &Arc<VmFrame>) {
+        let edited = FrameValues::new(frame);
+        match self.model.previous.iter_mut().find(|values| Arc::ptr_eq(&values.frame, frame)) {
+            Some(values) => *values = edited,
+            None => self.model.previous.push(edited),
+        }
+
+        self.locals.emit(LocalsMsg::Update(frame.locals().values(), None));
+        self.operand_stack.emit(OperandStackMsg::Update(frame.stack().values(), None));
+        self.object_graph.emit(ObjectGraphMsg::Update(HeapDump::new(&self.model.vm)));
+        self.fields.emit(FieldsMsg::Update);
+    }
+
     fn connect(&self) {
         let s = self.vm_channel.1.clone();
         self.model.vm.set_update_callback(Some(StdBox::new(move || {
@@ -293,13 +780,18 @@ impl VmView {
             s.send(VmChannelMsg::Ended).unwrap();
         })));
         let s = self.vm_channel.1.clone();
-        self.model.vm.set_error_callback(Some(StdBox::new(move |error| {
-            s.send(VmChannelMsg::Error(error)).unwrap();
+        self.model.vm.set_error_callback(Some(StdBox::new(move |error, trace| {
+            s.send(VmChannelMsg::Error(error, trace)).unwrap();
         })));
 
         self.fields.emit(FieldsMsg::ChangeViewed(Viewed::None));
-
-        self.model.vm.clone().start(self.model.main_class.clone());
     }
 }
 
+
+fn slot_str(slot: &Slot) -> String {
+    match slot {
+        Slot::Undefined => "UNDEFINED".to_owned(),
+        Slot::Value(value) => value.to_string(),
+    }
+}
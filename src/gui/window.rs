@@ -1,14 +1,19 @@
+use std::cell::RefCell;
 use std::path::PathBuf;
+use std::rc::Rc;
 
-use gtk::{BoxExt, Button, ContainerExt, Dialog, DialogExt, EditableSignals, Entry, EntryExt, FileChooserAction, FileChooserButton, FileChooserButtonExt, FileChooserExt, Grid, GridExt, GtkWindowExt, Inhibit, Label, ResponseType, StyleContextExt, WidgetExt, Window, WindowType};
-use gtk::prelude::Cast;
+use gtk::{Align, BinExt, Box, BoxExt, Button, ButtonBox, ButtonBoxExt, ButtonBoxStyle, ButtonExt, CellLayoutExt, CellRendererText, ComboBoxExt, ComboBoxText, ComboBoxTextExt, ContainerExt, Dialog, DialogExt, Entry, EntryExt, FileChooserAction, FileChooserDialog, FileChooserExt, FileFilter, Frame, FrameExt, Grid, GridExt, GtkListStoreExt, GtkWindowExt, Inhibit, Label, ListStore, Orientation, ResponseType, ShadowType, StyleContextExt, TreeModelExt, TreeSelectionExt, TreeView, TreeViewColumn, TreeViewExt, WidgetExt, Window, WindowType};
+use gtk::prelude::{Cast, ComboBoxExtManual, GtkListStoreExtManual, StaticType};
 use relm::{
     Component, connect, create_component, Relm, Update,
     Widget,
 };
 use relm_derive::Msg;
 
+use crate::gui::recent::{LoadConfig, RecentConfigs};
 use crate::gui::vm::{VmMsg, VmView};
+use crate::vm::exec::class_loader::ClassLoader;
+use crate::vm::exec::class_source::CompositeSource;
 use crate::vm::{
     class::{
         name::{ClassName},
@@ -32,7 +37,7 @@ pub enum AppEvent {
 pub struct AppWindow {
     window: Window,
     _header: Component<AppHeaderView>,
-    load_dialog: LoadDialog,
+    load_dialog: Rc<LoadDialog>,
     vm: Option<Component<VmView>>,
     landing_page: Component<LandingPage>,
 }
@@ -50,11 +55,11 @@ impl Update for AppWindow {
             AppEvent::LoadRequest => {
                 let result = self.load_dialog.run();
 
-                if let Some((class_name, path)) = result {
+                if let Some((class_name, class_path)) = result {
                     if let Some(vm) = &self.vm {
-                        vm.emit(VmMsg::Load(class_name, vec![path]));
+                        vm.emit(VmMsg::Load(class_name, class_path));
                     } else {
-                        let vm = create_component::<VmView>((class_name, vec![path]));
+                        let vm = create_component::<VmView>((class_name, class_path));
                         self.window.remove(self.landing_page.widget());
                         self.window.add(vm.widget());
                         self.vm = Some(vm);
@@ -117,94 +122,298 @@ impl Widget for AppWindow {
 }
 
 
+/// A dialog choosing the class path and the main class to load.
 struct LoadDialog {
     dialog: Dialog,
-    path_chooser: FileChooserButton,
-    class_entry: Entry,
-    _load_button: Button,
+    recent_chooser: ComboBoxText,
+    path_store: ListStore,
+    path_view: TreeView,
+    class_chooser: ComboBoxText,
+    load_button: Button,
+    class_path: RefCell<Vec<PathBuf>>,
+    recent: RefCell<RecentConfigs>,
 }
 
 
 impl LoadDialog {
-    fn new() -> LoadDialog {
+    fn new() -> Rc<LoadDialog> {
+        let recent_label = Label::new(Some("Recent:"));
         let path_label = Label::new(Some("Class path:"));
         let class_label = Label::new(Some("Main class:"));
-        let path_chooser = FileChooserButton::new("Class path", FileChooserAction::SelectFolder);
-        let class_entry = Entry::new();
+        for label in &[&recent_label, &path_label, &class_label] {
+            label.set_halign(Align::End);
+            label.set_valign(Align::Start);
+        }
+
+        let recent_chooser = ComboBoxText::new();
+
+        let path_store = ListStore::new(&[String::static_type()]);
+        let path_view = TreeView::with_model(&path_store);
+        let path_column = TreeViewColumn::new();
+        let path_cell = CellRendererText::new();
+        path_column.pack_start(&path_cell, true);
+        path_column.add_attribute(&path_cell, "text", 0);
+        path_view.append_column(&path_column);
+        path_view.set_headers_visible(false);
+        path_view.set_size_request(400, 120);
+
+        let path_frame = Frame::new(None);
+        path_frame.set_shadow_type(ShadowType::In);
+        path_frame.add(&path_view);
+
+        let add_button = Button::with_label("Add");
+        let add_archive_button = Button::with_label("Add archive");
+        let remove_button = Button::with_label("Remove");
+        let up_button = Button::with_label("Up");
+        let down_button = Button::with_label("Down");
+        let path_buttons = ButtonBox::new(Orientation::Vertical);
+        path_buttons.set_layout(ButtonBoxStyle::Start);
+        path_buttons.set_spacing(5);
+        for button in &[&add_button, &add_archive_button, &remove_button, &up_button, &down_button] {
+            path_buttons.pack_start(*button, false, false, 0);
+        }
+
+        let path_box = Box::new(Orientation::Horizontal, 5);
+        path_box.pack_start(&path_frame, true, true, 0);
+        path_box.pack_start(&path_buttons, false, false, 0);
+
+        let class_chooser = ComboBoxText::with_entry();
 
         let grid = Grid::new();
         grid.set_column_spacing(5);
         grid.set_row_spacing(5);
         grid.set_property_margin(10);
 
-        grid.attach(&path_label, 0, 0, 1, 1);
-        grid.attach(&class_label, 0, 1, 1, 1);
-        grid.attach(&path_chooser, 1, 0, 1, 1);
-        grid.attach(&class_entry, 1, 1, 1, 1);
+        grid.attach(&recent_label, 0, 0, 1, 1);
+        grid.attach(&path_label, 0, 1, 1, 1);
+        grid.attach(&class_label, 0, 2, 1, 1);
+        grid.attach(&recent_chooser, 1, 0, 1, 1);
+        grid.attach(&path_box, 1, 1, 1, 1);
+        grid.attach(&class_chooser, 1, 2, 1, 1);
 
         let dialog = Dialog::new();
+        dialog.set_title("Load");
         dialog.get_content_area().pack_start(&grid, true, true, 0);
         dialog.add_button("Cancel", ResponseType::Cancel);
         let load_button = dialog.add_button("Load", ResponseType::Accept).downcast::<Button>().unwrap();
         load_button.set_sensitive(false);
 
-        let ce = class_entry.clone();
-        let pc = path_chooser.clone();
-        let lb = load_button.clone();
-        class_entry.clone().connect_changed(move |entry| {
-            let mut ok = true;
+        let load_dialog = Rc::new(LoadDialog {
+            dialog,
+            recent_chooser,
+            path_store,
+            path_view,
+            class_chooser,
+            load_button,
+            class_path: RefCell::new(Vec::new()),
+            recent: RefCell::new(RecentConfigs::load()),
+        });
 
-            if ClassName::new(ce.get_text()).is_err() {
-                entry.get_style_context().add_class("entry-error");
-                ok = false;
-            } else {
-                entry.get_style_context().remove_class("entry-error");
+        let weak = Rc::downgrade(&load_dialog);
+        add_button.connect_clicked(move |_| {
+            if let Some(load_dialog) = weak.upgrade() {
+                load_dialog.add_entry(FileChooserAction::SelectFolder);
             }
+        });
 
-            if pc.get_filename().is_none() {
-                ok = false;
+        let weak = Rc::downgrade(&load_dialog);
+        add_archive_button.connect_clicked(move |_| {
+            if let Some(load_dialog) = weak.upgrade() {
+                load_dialog.add_entry(FileChooserAction::Open);
             }
-            lb.set_sensitive(ok);
         });
 
+        let weak = Rc::downgrade(&load_dialog);
+        remove_button.connect_clicked(move |_| {
+            if let Some(load_dialog) = weak.upgrade() {
+                load_dialog.remove_entry();
+            }
+        });
 
-        let ce = class_entry.clone();
-        let pc = path_chooser.clone();
-        let lb = load_button.clone();
-        path_chooser.clone().connect_file_set(move |_| {
-            let mut ok = true;
+        let weak = Rc::downgrade(&load_dialog);
+        up_button.connect_clicked(move |_| {
+            if let Some(load_dialog) = weak.upgrade() {
+                load_dialog.move_entry(true);
+            }
+        });
 
-            if ClassName::new(ce.get_text()).is_err() {
-                ok = false;
+        let weak = Rc::downgrade(&load_dialog);
+        down_button.connect_clicked(move |_| {
+            if let Some(load_dialog) = weak.upgrade() {
+                load_dialog.move_entry(false);
             }
-            if pc.get_filename().is_none() {
-                ok = false;
+        });
+
+        let weak = Rc::downgrade(&load_dialog);
+        load_dialog.class_chooser.connect_changed(move |_| {
+            if let Some(load_dialog) = weak.upgrade() {
+                load_dialog.validate();
             }
-            lb.set_sensitive(ok);
         });
 
-        LoadDialog {
-            dialog,
-            path_chooser,
-            class_entry,
-            _load_button: load_button,
-        }
+        let weak = Rc::downgrade(&load_dialog);
+        load_dialog.recent_chooser.connect_changed(move |_| {
+            if let Some(load_dialog) = weak.upgrade() {
+                load_dialog.apply_recent();
+            }
+        });
+
+        load_dialog
     }
 
-    fn run(&self) -> Option<(ClassName, PathBuf)> {
+    fn run(&self) -> Option<(ClassName, Vec<PathBuf>)> {
+        self.show_recent();
         self.dialog.show_all();
-        loop {
-            let result = self.dialog.run();
-            if result != ResponseType::Accept {
-                self.dialog.hide();
-                return None;
-            }
+        let result = self.dialog.run();
+        self.dialog.hide();
+
+        if result != ResponseType::Accept {
+            return None;
+        }
+
+        let class = ClassName::new(self.main_class().trim()).ok()?;
+        let class_path = self.class_path.borrow().clone();
+
+        // the recent configurations are only a convenience, failing to store them is not an error
+        let _ = self.recent.borrow_mut().add(LoadConfig::new(class.clone(), class_path.clone()));
+
+        Some((class, class_path))
+    }
+
+    /// Fills the recent configurations without applying any of them.
+    fn show_recent(&self) {
+        self.recent_chooser.remove_all();
+
+        for config in self.recent.borrow().configs() {
+            self.recent_chooser.append_text(&config.label());
+        }
+    }
+
+    /// Replaces the class path and the main class by the chosen recent configuration.
+    fn apply_recent(&self) {
+        let config = match self.recent_chooser.get_active() {
+            Some(index) => self.recent.borrow().configs()[index as usize].clone(),
+            None => return,
+        };
+
+        *self.class_path.borrow_mut() = config.class_path().to_vec();
+        self.class_path_changed(None);
+        self.set_main_class(config.main_class().as_ref());
+    }
 
-            let class = ClassName::new(self.class_entry.get_text().trim()).unwrap();
-            let path = self.path_chooser.get_filename().unwrap();
+    /// Appends a directory or, when opening files, a zip or jar archive chosen by the user
+    /// to the class path.
+    fn add_entry(&self, action: FileChooserAction) {
+        let dialog = FileChooserDialog::with_buttons(
+            Some("Add class path entry"),
+            Some(&self.dialog),
+            action,
+            &[("Cancel", ResponseType::Cancel), ("Add", ResponseType::Accept)],
+        );
+
+        if action == FileChooserAction::Open {
+            let filter = FileFilter::new();
+            filter.set_name(Some("Zip and jar archives"));
+            filter.add_pattern("*.zip");
+            filter.add_pattern("*.jar");
+            dialog.add_filter(&filter);
+        }
+
+        let path = match dialog.run() {
+            ResponseType::Accept => dialog.get_filename(),
+            _ => None,
+        };
+        dialog.close();
+
+        if let Some(path) = path {
+            let index = {
+                let mut class_path = self.class_path.borrow_mut();
+                class_path.push(path);
+                class_path.len() - 1
+            };
+            self.class_path_changed(Some(index));
+        }
+    }
+
+    fn remove_entry(&self) {
+        if let Some(index) = self.selected_entry() {
+            self.class_path.borrow_mut().remove(index);
+            self.class_path_changed(None);
+        }
+    }
+
+    /// Moves the selected entry one position up or down, the entries are searched for classes in order.
+    fn move_entry(&self, up: bool) {
+        let index = match self.selected_entry() {
+            Some(index) => index,
+            None => return,
+        };
+
+        let other = match up {
+            true if index > 0 => index - 1,
+            false if index + 1 < self.class_path.borrow().len() => index + 1,
+            _ => return,
+        };
+
+        self.class_path.borrow_mut().swap(index, other);
+        self.class_path_changed(Some(other));
+    }
+
+    fn selected_entry(&self) -> Option<usize> {
+        let (model, iter) = self.path_view.get_selection().get_selected()?;
+        model.get_path(&iter)?.get_indices().first().map(|index| *index as usize)
+    }
+
+    /// Shows the class path entries and offers the classes with the main method found on them.
+    fn class_path_changed(&self, selected: Option<usize>) {
+        let class_path = self.class_path.borrow().clone();
+
+        self.path_store.clear();
+        for entry in &class_path {
+            self.path_store.insert_with_values(None, &[0], &[&entry.to_string_lossy().to_string()]);
+        }
+
+        if let Some(iter) = selected.and_then(|index| self.path_store.iter_nth_child(None, index as i32)) {
+            self.path_view.get_selection().select_iter(&iter);
+        }
 
-            self.dialog.hide();
-            return Some((class, path));
+        let main_class = self.main_class();
+        let main_classes = ClassLoader::new(CompositeSource::class_path(class_path)).main_classes().unwrap_or_default();
+
+        self.class_chooser.remove_all();
+        for class_name in &main_classes {
+            self.class_chooser.append_text(class_name.as_ref());
+        }
+
+        match main_classes.first() {
+            Some(first) if main_class.trim().is_empty() => self.set_main_class(first.as_ref()),
+            _ => self.set_main_class(&main_class),
+        }
+    }
+
+    fn main_class(&self) -> String {
+        self.class_chooser.get_active_text().map(|text| text.to_string()).unwrap_or_default()
+    }
+
+    fn set_main_class(&self, main_class: &str) {
+        if let Some(entry) = self.class_chooser.get_child().and_then(|child| child.downcast::<Entry>().ok()) {
+            entry.set_text(main_class);
+        }
+        self.validate();
+    }
+
+    /// Enables loading only with a valid main class and a non empty class path.
+    fn validate(&self) {
+        let valid_class = ClassName::new(self.main_class().trim()).is_ok();
+
+        if let Some(entry) = self.class_chooser.get_child() {
+            if valid_class {
+                entry.get_style_context().remove_class("entry-error");
+            } else {
+                entry.get_style_context().add_class("entry-error");
+            }
         }
+
+        self.load_button.set_sensitive(valid_class && !self.class_path.borrow().is_empty());
     }
-}
\ No newline at end of file
+}
@@ -1,3 +1,6 @@
+use std::env;
+use std::process;
+
 use gdk::Screen;
 use gtk::{CssProviderExt, STYLE_PROVIDER_PRIORITY_APPLICATION, StyleContext};
 use relm::Widget;
@@ -5,11 +8,18 @@ use relm::Widget;
 use gui::AppWindow;
 
 
+pub mod cli;
 pub mod gui;
 pub mod vm;
 
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if !args.is_empty() {
+        process::exit(cli::main(args));
+    }
+
     gtk::init().expect("Can not initialize GTK application. Probably missing GTK dependencies.");
     load_css();
     AppWindow::run(()).unwrap();
@@ -7,7 +7,7 @@ use crate::vm::types::float::Float;
 use crate::vm::types::int::Int;
 use crate::vm::types::long::Long;
 use crate::vm::types::reference::Reference;
-use crate::vm::types::value::ValueType;
+use crate::vm::types::value::{Value, ValueType};
 
 
 impl Instruction {
@@ -23,11 +23,7 @@ impl Instruction {
         }
 
         let value = frame.stack().pop::<Int>()?;
-        thread.stack().pop().expect("frame stack should not be empty");
-
-        let frame = thread.stack().current().unwrap();
-        frame.stack().push(value)?;
-        Ok(())
+        Self::return_value(thread, value)
     }
 
     pub(super) fn lreturn(&self, thread: &Thread) -> Result<(), ExecError> {
@@ -42,11 +38,7 @@ impl Instruction {
         }
 
         let value = frame.stack().pop::<Long>()?;
-        thread.stack().pop().expect("frame stack should not be empty");
-
-        let frame = thread.stack().current().unwrap();
-        frame.stack().push(value)?;
-        Ok(())
+        Self::return_value(thread, value)
     }
 
     pub(super) fn freturn(&self, thread: &Thread) -> Result<(), ExecError> {
@@ -61,11 +53,7 @@ impl Instruction {
         }
 
         let value = frame.stack().pop::<Float>()?;
-        thread.stack().pop().expect("frame stack should not be empty");
-
-        let frame = thread.stack().current().unwrap();
-        frame.stack().push(value)?;
-        Ok(())
+        Self::return_value(thread, value)
     }
 
     pub(super) fn dreturn(&self, thread: &Thread) -> Result<(), ExecError> {
@@ -80,10 +68,7 @@ impl Instruction {
         }
 
         let value = frame.stack().pop::<Double>()?;
-        thread.stack().pop().expect("frame stack should not be empty");
-        let frame = thread.stack().current().unwrap();
-        frame.stack().push(value)?;
-        Ok(())
+        Self::return_value(thread, value)
     }
 
     pub(super) fn areturn(&self, thread: &Thread) -> Result<(), ExecError> {
@@ -104,10 +89,7 @@ impl Instruction {
                 }
             }
 
-            thread.stack().pop().expect("frame stack should not be empty");
-            let frame = thread.stack().current().unwrap();
-            frame.stack().push(value)?;
-            Ok(())
+            Self::return_value(thread, value)
         } else {
             return Err(ExecError::InvalidReturnType {
                 expected: expected_type.clone(),
@@ -130,4 +112,24 @@ impl Instruction {
         thread.stack().pop().expect("frame stack should not be empty");
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+
+impl Instruction {
+    /// Pops the frame of the returning method and pushes the returned value to the caller.
+    /// The value returned by the main method is the exit status of the program.
+    fn return_value<T: Into<Value>>(thread: &Thread, value: T) -> Result<(), ExecError> {
+        thread.stack().pop().expect("frame stack should not be empty");
+
+        match thread.stack().current() {
+            Some(frame) => frame.stack().push(value)?,
+            None => {
+                if let Value::Int(status) = value.into() {
+                    thread.runtime().exit(status.into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
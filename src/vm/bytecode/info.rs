@@ -0,0 +1,692 @@
+//! Metadata of the instructions - opcodes, categories, operands, stack effects and descriptions.
+
+use std::fmt;
+use std::fmt::Display;
+
+use crate::vm::bytecode::instruction::{Instruction, Ldc2Arg, LdcArg};
+use crate::vm::class::descriptor::ReturnDesc;
+use crate::vm::types::value::ValueType;
+
+
+/// Metadata of an instruction which do not depend on its operands.
+#[derive(Debug)]
+pub struct InstructionInfo {
+    opcode: u8,
+    mnemonic: &'static str,
+    category: Category,
+    operands: &'static [OperandType],
+    description: &'static str,
+}
+
+
+/// A category of instructions.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Category {
+    Constant,
+    Load,
+    Store,
+    Stack,
+    Arithmetic,
+    Conversion,
+    Comparison,
+    Branch,
+    Return,
+    Object,
+}
+
+
+/// A type of an instruction operand.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum OperandType {
+    Byte,
+    Short,
+    /// An int or float constant.
+    Constant,
+    /// A long or double constant.
+    WideConstant,
+    LocalIndex,
+    BranchOffset,
+    Field,
+    Method,
+    Class,
+}
+
+
+/// Types of the values an instruction pops from the operand stack
+/// and pushes on it, both with the top of the stack last.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct StackEffect {
+    consumed: Vec<ValueType>,
+    produced: Vec<ValueType>,
+}
+
+
+/// Metadata of all instructions in the order of the `Instruction` variants.
+/// The opcodes are the opcodes of the Java virtual machine.
+static INFOS: [InstructionInfo; 164] = [
+    InstructionInfo::new(0x00, "NOP", Category::Constant, &[], "Do nothing."),
+    InstructionInfo::new(0x02, "ICONST_M1", Category::Constant, &[], "Push the int constant -1."),
+    InstructionInfo::new(0x01, "ACONST_NULL", Category::Constant, &[], "Push the null reference."),
+    InstructionInfo::new(0x03, "ICONST_0", Category::Constant, &[], "Push the int constant 0."),
+    InstructionInfo::new(0x04, "ICONST_1", Category::Constant, &[], "Push the int constant 1."),
+    InstructionInfo::new(0x05, "ICONST_2", Category::Constant, &[], "Push the int constant 2."),
+    InstructionInfo::new(0x06, "ICONST_3", Category::Constant, &[], "Push the int constant 3."),
+    InstructionInfo::new(0x07, "ICONST_4", Category::Constant, &[], "Push the int constant 4."),
+    InstructionInfo::new(0x08, "ICONST_5", Category::Constant, &[], "Push the int constant 5."),
+    InstructionInfo::new(0x09, "LCONST_0", Category::Constant, &[], "Push the long constant 0."),
+    InstructionInfo::new(0x0a, "LCONST_1", Category::Constant, &[], "Push the long constant 1."),
+    InstructionInfo::new(0x0b, "FCONST_0", Category::Constant, &[], "Push the float constant 0.0."),
+    InstructionInfo::new(0x0c, "FCONST_1", Category::Constant, &[], "Push the float constant 1.0."),
+    InstructionInfo::new(0x0d, "FCONST_2", Category::Constant, &[], "Push the float constant 2.0."),
+    InstructionInfo::new(0x0e, "DCONST_0", Category::Constant, &[], "Push the double constant 0.0."),
+    InstructionInfo::new(0x0f, "DCONST_1", Category::Constant, &[], "Push the double constant 1.0."),
+    InstructionInfo::new(0x10, "BIPUSH", Category::Constant, &[OperandType::Byte], "Push the byte operand as an int."),
+    InstructionInfo::new(0x11, "SIPUSH", Category::Constant, &[OperandType::Short], "Push the short operand as an int."),
+    InstructionInfo::new(0x12, "LDC", Category::Constant, &[OperandType::Constant], "Push the int or float constant."),
+    InstructionInfo::new(0x13, "LDC_W", Category::Constant, &[OperandType::Constant], "Push the int or float constant."),
+    InstructionInfo::new(0x14, "LDC2_W", Category::Constant, &[OperandType::WideConstant], "Push the long or double constant."),
+    InstructionInfo::new(0x15, "ILOAD", Category::Load, &[OperandType::LocalIndex], "Push the int from the local variable."),
+    InstructionInfo::new(0x16, "LLOAD", Category::Load, &[OperandType::LocalIndex], "Push the long from the local variable."),
+    InstructionInfo::new(0x17, "FLOAD", Category::Load, &[OperandType::LocalIndex], "Push the float from the local variable."),
+    InstructionInfo::new(0x18, "DLOAD", Category::Load, &[OperandType::LocalIndex], "Push the double from the local variable."),
+    InstructionInfo::new(0x19, "ALOAD", Category::Load, &[OperandType::LocalIndex], "Push the reference from the local variable."),
+    InstructionInfo::new(0x1a, "ILOAD_0", Category::Load, &[], "Push the int from the local variable 0."),
+    InstructionInfo::new(0x1b, "ILOAD_1", Category::Load, &[], "Push the int from the local variable 1."),
+    InstructionInfo::new(0x1c, "ILOAD_2", Category::Load, &[], "Push the int from the local variable 2."),
+    InstructionInfo::new(0x1d, "ILOAD_3", Category::Load, &[], "Push the int from the local variable 3."),
+    InstructionInfo::new(0x1e, "LLOAD_0", Category::Load, &[], "Push the long from the local variable 0."),
+    InstructionInfo::new(0x1f, "LLOAD_1", Category::Load, &[], "Push the long from the local variable 1."),
+    InstructionInfo::new(0x20, "LLOAD_2", Category::Load, &[], "Push the long from the local variable 2."),
+    InstructionInfo::new(0x21, "LLOAD_3", Category::Load, &[], "Push the long from the local variable 3."),
+    InstructionInfo::new(0x22, "FLOAD_0", Category::Load, &[], "Push the float from the local variable 0."),
+    InstructionInfo::new(0x23, "FLOAD_1", Category::Load, &[], "Push the float from the local variable 1."),
+    InstructionInfo::new(0x24, "FLOAD_2", Category::Load, &[], "Push the float from the local variable 2."),
+    InstructionInfo::new(0x25, "FLOAD_3", Category::Load, &[], "Push the float from the local variable 3."),
+    InstructionInfo::new(0x26, "DLOAD_0", Category::Load, &[], "Push the double from the local variable 0."),
+    InstructionInfo::new(0x27, "DLOAD_1", Category::Load, &[], "Push the double from the local variable 1."),
+    InstructionInfo::new(0x28, "DLOAD_2", Category::Load, &[], "Push the double from the local variable 2."),
+    InstructionInfo::new(0x29, "DLOAD_3", Category::Load, &[], "Push the double from the local variable 3."),
+    InstructionInfo::new(0x2a, "ALOAD_0", Category::Load, &[], "Push the reference from the local variable 0."),
+    InstructionInfo::new(0x2b, "ALOAD_1", Category::Load, &[], "Push the reference from the local variable 1."),
+    InstructionInfo::new(0x2c, "ALOAD_2", Category::Load, &[], "Push the reference from the local variable 2."),
+    InstructionInfo::new(0x2d, "ALOAD_3", Category::Load, &[], "Push the reference from the local variable 3."),
+    InstructionInfo::new(0x36, "ISTORE", Category::Store, &[OperandType::LocalIndex], "Pop the int into the local variable."),
+    InstructionInfo::new(0x37, "LSTORE", Category::Store, &[OperandType::LocalIndex], "Pop the long into the local variable."),
+    InstructionInfo::new(0x38, "FSTORE", Category::Store, &[OperandType::LocalIndex], "Pop the float into the local variable."),
+    InstructionInfo::new(0x39, "DSTORE", Category::Store, &[OperandType::LocalIndex], "Pop the double into the local variable."),
+    InstructionInfo::new(0x3a, "ASTORE", Category::Store, &[OperandType::LocalIndex], "Pop the reference into the local variable."),
+    InstructionInfo::new(0x3b, "ISTORE_0", Category::Store, &[], "Pop the int into the local variable 0."),
+    InstructionInfo::new(0x3c, "ISTORE_1", Category::Store, &[], "Pop the int into the local variable 1."),
+    InstructionInfo::new(0x3d, "ISTORE_2", Category::Store, &[], "Pop the int into the local variable 2."),
+    InstructionInfo::new(0x3e, "ISTORE_3", Category::Store, &[], "Pop the int into the local variable 3."),
+    InstructionInfo::new(0x3f, "LSTORE_0", Category::Store, &[], "Pop the long into the local variable 0."),
+    InstructionInfo::new(0x40, "LSTORE_1", Category::Store, &[], "Pop the long into the local variable 1."),
+    InstructionInfo::new(0x41, "LSTORE_2", Category::Store, &[], "Pop the long into the local variable 2."),
+    InstructionInfo::new(0x42, "LSTORE_3", Category::Store, &[], "Pop the long into the local variable 3."),
+    InstructionInfo::new(0x43, "FSTORE_0", Category::Store, &[], "Pop the float into the local variable 0."),
+    InstructionInfo::new(0x44, "FSTORE_1", Category::Store, &[], "Pop the float into the local variable 1."),
+    InstructionInfo::new(0x45, "FSTORE_2", Category::Store, &[], "Pop the float into the local variable 2."),
+    InstructionInfo::new(0x46, "FSTORE_3", Category::Store, &[], "Pop the float into the local variable 3."),
+    InstructionInfo::new(0x47, "DSTORE_0", Category::Store, &[], "Pop the double into the local variable 0."),
+    InstructionInfo::new(0x48, "DSTORE_1", Category::Store, &[], "Pop the double into the local variable 1."),
+    InstructionInfo::new(0x49, "DSTORE_2", Category::Store, &[], "Pop the double into the local variable 2."),
+    InstructionInfo::new(0x4a, "DSTORE_3", Category::Store, &[], "Pop the double into the local variable 3."),
+    InstructionInfo::new(0x4b, "ASTORE_0", Category::Store, &[], "Pop the reference into the local variable 0."),
+    InstructionInfo::new(0x4c, "ASTORE_1", Category::Store, &[], "Pop the reference into the local variable 1."),
+    InstructionInfo::new(0x4d, "ASTORE_2", Category::Store, &[], "Pop the reference into the local variable 2."),
+    InstructionInfo::new(0x4e, "ASTORE_3", Category::Store, &[], "Pop the reference into the local variable 3."),
+    InstructionInfo::new(0x57, "POP", Category::Stack, &[], "Discard the top value, which must not be a long or double."),
+    InstructionInfo::new(0x58, "POP2", Category::Stack, &[], "Discard the top two slots - one long or double or two other values."),
+    InstructionInfo::new(0x59, "DUP", Category::Stack, &[], "Duplicate the top value."),
+    InstructionInfo::new(0x5a, "DUP_X1", Category::Stack, &[], "Duplicate the top value and insert it below the second value."),
+    InstructionInfo::new(0x5b, "DUP_X2", Category::Stack, &[], "Duplicate the top value and insert it below the next two slots."),
+    InstructionInfo::new(0x5c, "DUP2", Category::Stack, &[], "Duplicate the top two slots."),
+    InstructionInfo::new(0x5d, "DUP2_X1", Category::Stack, &[], "Duplicate the top two slots and insert them below the next slot."),
+    InstructionInfo::new(0x5e, "DUP2_X2", Category::Stack, &[], "Duplicate the top two slots and insert them below the next two slots."),
+    InstructionInfo::new(0x5f, "SWAP", Category::Stack, &[], "Swap the top two values, which must not be longs or doubles."),
+    InstructionInfo::new(0x60, "IADD", Category::Arithmetic, &[], "Add two ints."),
+    InstructionInfo::new(0x61, "LADD", Category::Arithmetic, &[], "Add two longs."),
+    InstructionInfo::new(0x62, "FADD", Category::Arithmetic, &[], "Add two floats."),
+    InstructionInfo::new(0x63, "DADD", Category::Arithmetic, &[], "Add two doubles."),
+    InstructionInfo::new(0x64, "ISUB", Category::Arithmetic, &[], "Subtract two ints."),
+    InstructionInfo::new(0x65, "LSUB", Category::Arithmetic, &[], "Subtract two longs."),
+    InstructionInfo::new(0x66, "FSUB", Category::Arithmetic, &[], "Subtract two floats."),
+    InstructionInfo::new(0x67, "DSUB", Category::Arithmetic, &[], "Subtract two doubles."),
+    InstructionInfo::new(0x68, "IMUL", Category::Arithmetic, &[], "Multiply two ints."),
+    InstructionInfo::new(0x69, "LMUL", Category::Arithmetic, &[], "Multiply two longs."),
+    InstructionInfo::new(0x6a, "FMUL", Category::Arithmetic, &[], "Multiply two floats."),
+    InstructionInfo::new(0x6b, "DMUL", Category::Arithmetic, &[], "Multiply two doubles."),
+    InstructionInfo::new(0x6c, "IDIV", Category::Arithmetic, &[], "Divide two ints."),
+    InstructionInfo::new(0x6d, "LDIV", Category::Arithmetic, &[], "Divide two longs."),
+    InstructionInfo::new(0x6e, "FDIV", Category::Arithmetic, &[], "Divide two floats."),
+    InstructionInfo::new(0x6f, "DDIV", Category::Arithmetic, &[], "Divide two doubles."),
+    InstructionInfo::new(0x70, "IREM", Category::Arithmetic, &[], "Compute the remainder of the division of two ints."),
+    InstructionInfo::new(0x71, "LREM", Category::Arithmetic, &[], "Compute the remainder of the division of two longs."),
+    InstructionInfo::new(0x72, "FREM", Category::Arithmetic, &[], "Compute the remainder of the division of two floats."),
+    InstructionInfo::new(0x73, "DREM", Category::Arithmetic, &[], "Compute the remainder of the division of two doubles."),
+    InstructionInfo::new(0x74, "INEG", Category::Arithmetic, &[], "Negate the int."),
+    InstructionInfo::new(0x75, "LNEG", Category::Arithmetic, &[], "Negate the long."),
+    InstructionInfo::new(0x76, "FNEG", Category::Arithmetic, &[], "Negate the float."),
+    InstructionInfo::new(0x77, "DNEG", Category::Arithmetic, &[], "Negate the double."),
+    InstructionInfo::new(0x78, "ISHL", Category::Arithmetic, &[], "Shift the int left by the int."),
+    InstructionInfo::new(0x79, "LSHL", Category::Arithmetic, &[], "Shift the long left by the int."),
+    InstructionInfo::new(0x7a, "ISHR", Category::Arithmetic, &[], "Shift the int right by the int, keeping the sign."),
+    InstructionInfo::new(0x7b, "LSHR", Category::Arithmetic, &[], "Shift the long right by the int, keeping the sign."),
+    InstructionInfo::new(0x7c, "IUSHR", Category::Arithmetic, &[], "Shift the int right by the int, filling with zeros."),
+    InstructionInfo::new(0x7d, "LUSHR", Category::Arithmetic, &[], "Shift the long right by the int, filling with zeros."),
+    InstructionInfo::new(0x7e, "IAND", Category::Arithmetic, &[], "Bitwise and of two ints."),
+    InstructionInfo::new(0x7f, "LAND", Category::Arithmetic, &[], "Bitwise and of two longs."),
+    InstructionInfo::new(0x80, "IOR", Category::Arithmetic, &[], "Bitwise or of two ints."),
+    InstructionInfo::new(0x81, "LOR", Category::Arithmetic, &[], "Bitwise or of two longs."),
+    InstructionInfo::new(0x82, "IXOR", Category::Arithmetic, &[], "Bitwise exclusive or of two ints."),
+    InstructionInfo::new(0x83, "LXOR", Category::Arithmetic, &[], "Bitwise exclusive or of two longs."),
+    InstructionInfo::new(0x84, "IINC", Category::Arithmetic, &[OperandType::LocalIndex, OperandType::Byte], "Increment the int local variable by the byte operand."),
+    InstructionInfo::new(0x85, "I2L", Category::Conversion, &[], "Convert the int to a long."),
+    InstructionInfo::new(0x86, "I2F", Category::Conversion, &[], "Convert the int to a float."),
+    InstructionInfo::new(0x87, "I2D", Category::Conversion, &[], "Convert the int to a double."),
+    InstructionInfo::new(0x88, "L2I", Category::Conversion, &[], "Convert the long to a int."),
+    InstructionInfo::new(0x89, "L2F", Category::Conversion, &[], "Convert the long to a float."),
+    InstructionInfo::new(0x8a, "L2D", Category::Conversion, &[], "Convert the long to a double."),
+    InstructionInfo::new(0x8b, "F2I", Category::Conversion, &[], "Convert the float to a int."),
+    InstructionInfo::new(0x8c, "F2L", Category::Conversion, &[], "Convert the float to a long."),
+    InstructionInfo::new(0x8d, "F2D", Category::Conversion, &[], "Convert the float to a double."),
+    InstructionInfo::new(0x8e, "D2I", Category::Conversion, &[], "Convert the double to a int."),
+    InstructionInfo::new(0x8f, "D2L", Category::Conversion, &[], "Convert the double to a long."),
+    InstructionInfo::new(0x90, "D2F", Category::Conversion, &[], "Convert the double to a float."),
+    InstructionInfo::new(0x94, "LCMP", Category::Comparison, &[], "Compare two longs, push -1, 0 or 1."),
+    InstructionInfo::new(0x95, "FCMPL", Category::Comparison, &[], "Compare two floats, push -1, 0 or 1, -1 if either is NaN."),
+    InstructionInfo::new(0x96, "FCMPG", Category::Comparison, &[], "Compare two floats, push -1, 0 or 1, 1 if either is NaN."),
+    InstructionInfo::new(0x97, "DCMPL", Category::Comparison, &[], "Compare two doubles, push -1, 0 or 1, -1 if either is NaN."),
+    InstructionInfo::new(0x98, "DCMPG", Category::Comparison, &[], "Compare two doubles, push -1, 0 or 1, 1 if either is NaN."),
+    InstructionInfo::new(0x99, "IFEQ", Category::Branch, &[OperandType::BranchOffset], "Branch if the int is equal to zero."),
+    InstructionInfo::new(0x9a, "IFNE", Category::Branch, &[OperandType::BranchOffset], "Branch if the int is not equal to zero."),
+    InstructionInfo::new(0x9b, "IFLT", Category::Branch, &[OperandType::BranchOffset], "Branch if the int is less than zero."),
+    InstructionInfo::new(0x9c, "IFGE", Category::Branch, &[OperandType::BranchOffset], "Branch if the int is greater than or equal to zero."),
+    InstructionInfo::new(0x9d, "IFGT", Category::Branch, &[OperandType::BranchOffset], "Branch if the int is greater than zero."),
+    InstructionInfo::new(0x9e, "IFLE", Category::Branch, &[OperandType::BranchOffset], "Branch if the int is less than or equal to zero."),
+    InstructionInfo::new(0x9f, "IF_ICMPEQ", Category::Branch, &[OperandType::BranchOffset], "Branch if the first int is equal to the second."),
+    InstructionInfo::new(0xa0, "IF_ICMPNE", Category::Branch, &[OperandType::BranchOffset], "Branch if the first int is not equal to the second."),
+    InstructionInfo::new(0xa1, "IF_ICMPLT", Category::Branch, &[OperandType::BranchOffset], "Branch if the first int is less than the second."),
+    InstructionInfo::new(0xa2, "IF_ICMPGE", Category::Branch, &[OperandType::BranchOffset], "Branch if the first int is greater than or equal to the second."),
+    InstructionInfo::new(0xa3, "IF_ICMPGT", Category::Branch, &[OperandType::BranchOffset], "Branch if the first int is greater than the second."),
+    InstructionInfo::new(0xa4, "IF_ICMPLE", Category::Branch, &[OperandType::BranchOffset], "Branch if the first int is less than or equal to the second."),
+    InstructionInfo::new(0xa5, "IF_ACMPEQ", Category::Branch, &[OperandType::BranchOffset], "Branch if the references are equal."),
+    InstructionInfo::new(0xa6, "IF_ACMPNE", Category::Branch, &[OperandType::BranchOffset], "Branch if the references are not equal."),
+    InstructionInfo::new(0xa7, "GOTO", Category::Branch, &[OperandType::BranchOffset], "Branch always."),
+    InstructionInfo::new(0xc6, "IFNULL", Category::Branch, &[OperandType::BranchOffset], "Branch if the reference is null."),
+    InstructionInfo::new(0xc7, "IFNONNULL", Category::Branch, &[OperandType::BranchOffset], "Branch if the reference is not null."),
+    InstructionInfo::new(0xac, "IRETURN", Category::Return, &[], "Return the int from the method."),
+    InstructionInfo::new(0xad, "LRETURN", Category::Return, &[], "Return the long from the method."),
+    InstructionInfo::new(0xae, "FRETURN", Category::Return, &[], "Return the float from the method."),
+    InstructionInfo::new(0xaf, "DRETURN", Category::Return, &[], "Return the double from the method."),
+    InstructionInfo::new(0xb0, "ARETURN", Category::Return, &[], "Return the reference from the method."),
+    InstructionInfo::new(0xb1, "RETURN", Category::Return, &[], "Return void from the method."),
+    InstructionInfo::new(0xb2, "GETSTATIC", Category::Object, &[OperandType::Field], "Push the value of the static field."),
+    InstructionInfo::new(0xb3, "PUTSTATIC", Category::Object, &[OperandType::Field], "Pop the value into the static field."),
+    InstructionInfo::new(0xb4, "GETFIELD", Category::Object, &[OperandType::Field], "Push the value of the field of the object."),
+    InstructionInfo::new(0xb5, "PUTFIELD", Category::Object, &[OperandType::Field], "Pop the value into the field of the object."),
+    InstructionInfo::new(0xb6, "INVOKEVIRTUAL", Category::Object, &[OperandType::Method], "Invoke the instance method on the object with the arguments."),
+    InstructionInfo::new(0xb8, "INVOKESTATIC", Category::Object, &[OperandType::Method], "Invoke the static method with the arguments."),
+    InstructionInfo::new(0xbb, "NEW", Category::Object, &[OperandType::Class], "Create a new object of the class."),
+];
+
+
+impl InstructionInfo {
+    const fn new(opcode: u8, mnemonic: &'static str, category: Category, operands: &'static [OperandType], description: &'static str) -> Self {
+        InstructionInfo { opcode, mnemonic, category, operands, description }
+    }
+
+    /// Returns the opcode.
+    pub fn opcode(&self) -> u8 {
+        self.opcode
+    }
+
+    /// Returns the mnemonic.
+    pub fn mnemonic(&self) -> &'static str {
+        self.mnemonic
+    }
+
+    /// Returns the category.
+    pub fn category(&self) -> Category {
+        self.category
+    }
+
+    /// Returns the types of the operands in the order they are written.
+    pub fn operands(&self) -> &'static [OperandType] {
+        self.operands
+    }
+
+    /// Returns a one sentence description of what the instruction does.
+    pub fn description(&self) -> &'static str {
+        self.description
+    }
+}
+
+
+impl Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Category::Constant => write!(f, "constant"),
+            Category::Load => write!(f, "load"),
+            Category::Store => write!(f, "store"),
+            Category::Stack => write!(f, "stack"),
+            Category::Arithmetic => write!(f, "arithmetic"),
+            Category::Conversion => write!(f, "conversion"),
+            Category::Comparison => write!(f, "comparison"),
+            Category::Branch => write!(f, "branch"),
+            Category::Return => write!(f, "return"),
+            Category::Object => write!(f, "object"),
+        }
+    }
+}
+
+
+impl Display for OperandType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OperandType::Byte => write!(f, "byte"),
+            OperandType::Short => write!(f, "short"),
+            OperandType::Constant => write!(f, "int or float"),
+            OperandType::WideConstant => write!(f, "long or double"),
+            OperandType::LocalIndex => write!(f, "local index"),
+            OperandType::BranchOffset => write!(f, "branch offset"),
+            OperandType::Field => write!(f, "field"),
+            OperandType::Method => write!(f, "method"),
+            OperandType::Class => write!(f, "class"),
+        }
+    }
+}
+
+
+impl StackEffect {
+    /// Creates a new stack effect from the consumed and the produced types,
+    /// both with the top of the stack last.
+    pub fn new(consumed: Vec<ValueType>, produced: Vec<ValueType>) -> Self {
+        StackEffect { consumed, produced }
+    }
+
+    /// Returns the types of the popped values, the top of the stack last.
+    pub fn consumed(&self) -> &[ValueType] {
+        &self.consumed
+    }
+
+    /// Returns the types of the pushed values, the top of the stack last.
+    pub fn produced(&self) -> &[ValueType] {
+        &self.produced
+    }
+}
+
+
+/// Formats the effect as `…, int, int → …, int`.
+impl Display for StackEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "…")?;
+        for value_type in &self.consumed {
+            write!(f, ", {}", value_type)?;
+        }
+
+        write!(f, " → …")?;
+        for value_type in &self.produced {
+            write!(f, ", {}", value_type)?;
+        }
+
+        Ok(())
+    }
+}
+
+
+impl Instruction {
+    /// Returns the metadata of all instructions in the order of the `Instruction` variants.
+    pub fn infos() -> &'static [InstructionInfo] {
+        &INFOS
+    }
+
+    /// Returns the metadata of the instruction with the given mnemonic, if there is one.
+    pub fn info_of(mnemonic: &str) -> Option<&'static InstructionInfo> {
+        INFOS.iter().find(|info| info.mnemonic == mnemonic)
+    }
+
+    /// Returns the metadata of this instruction.
+    pub fn info(&self) -> &'static InstructionInfo {
+        &INFOS[self.info_index()]
+    }
+
+    /// Returns the index of the metadata of this instruction in `INFOS`.
+    fn info_index(&self) -> usize {
+        match self {
+            Instruction::NOP => 0,
+            Instruction::ICONST_M1 => 1,
+            Instruction::ACONST_NULL => 2,
+            Instruction::ICONST_0 => 3,
+            Instruction::ICONST_1 => 4,
+            Instruction::ICONST_2 => 5,
+            Instruction::ICONST_3 => 6,
+            Instruction::ICONST_4 => 7,
+            Instruction::ICONST_5 => 8,
+            Instruction::LCONST_0 => 9,
+            Instruction::LCONST_1 => 10,
+            Instruction::FCONST_0 => 11,
+            Instruction::FCONST_1 => 12,
+            Instruction::FCONST_2 => 13,
+            Instruction::DCONST_0 => 14,
+            Instruction::DCONST_1 => 15,
+            Instruction::BIPUSH(_) => 16,
+            Instruction::SIPUSH(_) => 17,
+            Instruction::LDC(_) => 18,
+            Instruction::LDC_W(_) => 19,
+            Instruction::LDC2_W(_) => 20,
+            Instruction::ILOAD(_) => 21,
+            Instruction::LLOAD(_) => 22,
+            Instruction::FLOAD(_) => 23,
+            Instruction::DLOAD(_) => 24,
+            Instruction::ALOAD(_) => 25,
+            Instruction::ILOAD_0 => 26,
+            Instruction::ILOAD_1 => 27,
+            Instruction::ILOAD_2 => 28,
+            Instruction::ILOAD_3 => 29,
+            Instruction::LLOAD_0 => 30,
+            Instruction::LLOAD_1 => 31,
+            Instruction::LLOAD_2 => 32,
+            Instruction::LLOAD_3 => 33,
+            Instruction::FLOAD_0 => 34,
+            Instruction::FLOAD_1 => 35,
+            Instruction::FLOAD_2 => 36,
+            Instruction::FLOAD_3 => 37,
+            Instruction::DLOAD_0 => 38,
+            Instruction::DLOAD_1 => 39,
+            Instruction::DLOAD_2 => 40,
+            Instruction::DLOAD_3 => 41,
+            Instruction::ALOAD_0 => 42,
+            Instruction::ALOAD_1 => 43,
+            Instruction::ALOAD_2 => 44,
+            Instruction::ALOAD_3 => 45,
+            Instruction::ISTORE(_) => 46,
+            Instruction::LSTORE(_) => 47,
+            Instruction::FSTORE(_) => 48,
+            Instruction::DSTORE(_) => 49,
+            Instruction::ASTORE(_) => 50,
+            Instruction::ISTORE_0 => 51,
+            Instruction::ISTORE_1 => 52,
+            Instruction::ISTORE_2 => 53,
+            Instruction::ISTORE_3 => 54,
+            Instruction::LSTORE_0 => 55,
+            Instruction::LSTORE_1 => 56,
+            Instruction::LSTORE_2 => 57,
+            Instruction::LSTORE_3 => 58,
+            Instruction::FSTORE_0 => 59,
+            Instruction::FSTORE_1 => 60,
+            Instruction::FSTORE_2 => 61,
+            Instruction::FSTORE_3 => 62,
+            Instruction::DSTORE_0 => 63,
+            Instruction::DSTORE_1 => 64,
+            Instruction::DSTORE_2 => 65,
+            Instruction::DSTORE_3 => 66,
+            Instruction::ASTORE_0 => 67,
+            Instruction::ASTORE_1 => 68,
+            Instruction::ASTORE_2 => 69,
+            Instruction::ASTORE_3 => 70,
+            Instruction::POP => 71,
+            Instruction::POP2 => 72,
+            Instruction::DUP => 73,
+            Instruction::DUP_X1 => 74,
+            Instruction::DUP_X2 => 75,
+            Instruction::DUP2 => 76,
+            Instruction::DUP2_X1 => 77,
+            Instruction::DUP2_X2 => 78,
+            Instruction::SWAP => 79,
+            Instruction::IADD => 80,
+            Instruction::LADD => 81,
+            Instruction::FADD => 82,
+            Instruction::DADD => 83,
+            Instruction::ISUB => 84,
+            Instruction::LSUB => 85,
+            Instruction::FSUB => 86,
+            Instruction::DSUB => 87,
+            Instruction::IMUL => 88,
+            Instruction::LMUL => 89,
+            Instruction::FMUL => 90,
+            Instruction::DMUL => 91,
+            Instruction::IDIV => 92,
+            Instruction::LDIV => 93,
+            Instruction::FDIV => 94,
+            Instruction::DDIV => 95,
+            Instruction::IREM => 96,
+            Instruction::LREM => 97,
+            Instruction::FREM => 98,
+            Instruction::DREM => 99,
+            Instruction::INEG => 100,
+            Instruction::LNEG => 101,
+            Instruction::FNEG => 102,
+            Instruction::DNEG => 103,
+            Instruction::ISHL => 104,
+            Instruction::LSHL => 105,
+            Instruction::ISHR => 106,
+            Instruction::LSHR => 107,
+            Instruction::IUSHR => 108,
+            Instruction::LUSHR => 109,
+            Instruction::IAND => 110,
+            Instruction::LAND => 111,
+            Instruction::IOR => 112,
+            Instruction::LOR => 113,
+            Instruction::IXOR => 114,
+            Instruction::LXOR => 115,
+            Instruction::IINC(_, _) => 116,
+            Instruction::I2L => 117,
+            Instruction::I2F => 118,
+            Instruction::I2D => 119,
+            Instruction::L2I => 120,
+            Instruction::L2F => 121,
+            Instruction::L2D => 122,
+            Instruction::F2I => 123,
+            Instruction::F2L => 124,
+            Instruction::F2D => 125,
+            Instruction::D2I => 126,
+            Instruction::D2L => 127,
+            Instruction::D2F => 128,
+            Instruction::LCMP => 129,
+            Instruction::FCMPL => 130,
+            Instruction::FCMPG => 131,
+            Instruction::DCMPL => 132,
+            Instruction::DCMPG => 133,
+            Instruction::IFEQ(_) => 134,
+            Instruction::IFNE(_) => 135,
+            Instruction::IFLT(_) => 136,
+            Instruction::IFGE(_) => 137,
+            Instruction::IFGT(_) => 138,
+            Instruction::IFLE(_) => 139,
+            Instruction::IF_ICMPEQ(_) => 140,
+            Instruction::IF_ICMPNE(_) => 141,
+            Instruction::IF_ICMPLT(_) => 142,
+            Instruction::IF_ICMPGE(_) => 143,
+            Instruction::IF_ICMPGT(_) => 144,
+            Instruction::IF_ICMPLE(_) => 145,
+            Instruction::IF_ACMPEQ(_) => 146,
+            Instruction::IF_ACMPNE(_) => 147,
+            Instruction::GOTO(_) => 148,
+            Instruction::IFNULL(_) => 149,
+            Instruction::IFNONNULL(_) => 150,
+            Instruction::IRETURN => 151,
+            Instruction::LRETURN => 152,
+            Instruction::FRETURN => 153,
+            Instruction::DRETURN => 154,
+            Instruction::ARETURN => 155,
+            Instruction::RETURN => 156,
+            Instruction::GETSTATIC(_) => 157,
+            Instruction::PUTSTATIC(_) => 158,
+            Instruction::GETFIELD(_) => 159,
+            Instruction::PUTFIELD(_) => 160,
+            Instruction::INVOKEVIRTUAL(_) => 161,
+            Instruction::INVOKESTATIC(_) => 162,
+            Instruction::NEW(_) => 163,
+        }
+    }
+
+    /// Returns the types of the values this instruction pops and pushes, which may depend
+    /// on the operands. Returns `None` for the stack manipulation instructions like `DUP`,
+    /// which work with values of any type.
+    pub fn stack_effect(&self) -> Option<StackEffect> {
+        use ValueType::{AnyReference, Double, Float, Int, Long};
+
+        let (consumed, produced) = match self {
+            Instruction::NOP | Instruction::IINC(_, _) | Instruction::GOTO(_) => (vec![], vec![]),
+
+            Instruction::ACONST_NULL => (vec![], vec![ValueType::Null]),
+            Instruction::ICONST_M1 | Instruction::ICONST_0 | Instruction::ICONST_1 |
+            Instruction::ICONST_2 | Instruction::ICONST_3 | Instruction::ICONST_4 |
+            Instruction::ICONST_5 | Instruction::BIPUSH(_) | Instruction::SIPUSH(_) => (vec![], vec![Int]),
+            Instruction::LCONST_0 | Instruction::LCONST_1 => (vec![], vec![Long]),
+            Instruction::FCONST_0 | Instruction::FCONST_1 | Instruction::FCONST_2 => (vec![], vec![Float]),
+            Instruction::DCONST_0 | Instruction::DCONST_1 => (vec![], vec![Double]),
+            Instruction::LDC(LdcArg::Int(_)) | Instruction::LDC_W(LdcArg::Int(_)) => (vec![], vec![Int]),
+            Instruction::LDC(LdcArg::Float(_)) | Instruction::LDC_W(LdcArg::Float(_)) => (vec![], vec![Float]),
+            Instruction::LDC2_W(Ldc2Arg::Long(_)) => (vec![], vec![Long]),
+            Instruction::LDC2_W(Ldc2Arg::Double(_)) => (vec![], vec![Double]),
+
+            Instruction::ILOAD(_) | Instruction::ILOAD_0 | Instruction::ILOAD_1 |
+            Instruction::ILOAD_2 | Instruction::ILOAD_3 => (vec![], vec![Int]),
+            Instruction::LLOAD(_) | Instruction::LLOAD_0 | Instruction::LLOAD_1 |
+            Instruction::LLOAD_2 | Instruction::LLOAD_3 => (vec![], vec![Long]),
+            Instruction::FLOAD(_) | Instruction::FLOAD_0 | Instruction::FLOAD_1 |
+            Instruction::FLOAD_2 | Instruction::FLOAD_3 => (vec![], vec![Float]),
+            Instruction::DLOAD(_) | Instruction::DLOAD_0 | Instruction::DLOAD_1 |
+            Instruction::DLOAD_2 | Instruction::DLOAD_3 => (vec![], vec![Double]),
+            Instruction::ALOAD(_) | Instruction::ALOAD_0 | Instruction::ALOAD_1 |
+            Instruction::ALOAD_2 | Instruction::ALOAD_3 => (vec![], vec![AnyReference]),
+
+            Instruction::ISTORE(_) | Instruction::ISTORE_0 | Instruction::ISTORE_1 |
+            Instruction::ISTORE_2 | Instruction::ISTORE_3 => (vec![Int], vec![]),
+            Instruction::LSTORE(_) | Instruction::LSTORE_0 | Instruction::LSTORE_1 |
+            Instruction::LSTORE_2 | Instruction::LSTORE_3 => (vec![Long], vec![]),
+            Instruction::FSTORE(_) | Instruction::FSTORE_0 | Instruction::FSTORE_1 |
+            Instruction::FSTORE_2 | Instruction::FSTORE_3 => (vec![Float], vec![]),
+            Instruction::DSTORE(_) | Instruction::DSTORE_0 | Instruction::DSTORE_1 |
+            Instruction::DSTORE_2 | Instruction::DSTORE_3 => (vec![Double], vec![]),
+            Instruction::ASTORE(_) | Instruction::ASTORE_0 | Instruction::ASTORE_1 |
+            Instruction::ASTORE_2 | Instruction::ASTORE_3 => (vec![AnyReference], vec![]),
+
+            Instruction::POP | Instruction::POP2 | Instruction::DUP | Instruction::DUP_X1 |
+            Instruction::DUP_X2 | Instruction::DUP2 | Instruction::DUP2_X1 | Instruction::DUP2_X2 |
+            Instruction::SWAP => return None,
+
+            Instruction::IADD | Instruction::ISUB | Instruction::IMUL | Instruction::IDIV |
+            Instruction::IREM | Instruction::IAND | Instruction::IOR | Instruction::IXOR |
+            Instruction::ISHL | Instruction::ISHR | Instruction::IUSHR => (vec![Int, Int], vec![Int]),
+            Instruction::LADD | Instruction::LSUB | Instruction::LMUL | Instruction::LDIV |
+            Instruction::LREM | Instruction::LAND | Instruction::LOR | Instruction::LXOR => (vec![Long, Long], vec![Long]),
+            Instruction::LSHL | Instruction::LSHR | Instruction::LUSHR => (vec![Long, Int], vec![Long]),
+            Instruction::FADD | Instruction::FSUB | Instruction::FMUL | Instruction::FDIV |
+            Instruction::FREM => (vec![Float, Float], vec![Float]),
+            Instruction::DADD | Instruction::DSUB | Instruction::DMUL | Instruction::DDIV |
+            Instruction::DREM => (vec![Double, Double], vec![Double]),
+            Instruction::INEG => (vec![Int], vec![Int]),
+            Instruction::LNEG => (vec![Long], vec![Long]),
+            Instruction::FNEG => (vec![Float], vec![Float]),
+            Instruction::DNEG => (vec![Double], vec![Double]),
+
+            Instruction::I2L => (vec![Int], vec![Long]),
+            Instruction::I2F => (vec![Int], vec![Float]),
+            Instruction::I2D => (vec![Int], vec![Double]),
+            Instruction::L2I => (vec![Long], vec![Int]),
+            Instruction::L2F => (vec![Long], vec![Float]),
+            Instruction::L2D => (vec![Long], vec![Double]),
+            Instruction::F2I => (vec![Float], vec![Int]),
+            Instruction::F2L => (vec![Float], vec![Long]),
+            Instruction::F2D => (vec![Float], vec![Double]),
+            Instruction::D2I => (vec![Double], vec![Int]),
+            Instruction::D2L => (vec![Double], vec![Long]),
+            Instruction::D2F => (vec![Double], vec![Float]),
+
+            Instruction::LCMP => (vec![Long, Long], vec![Int]),
+            Instruction::FCMPL | Instruction::FCMPG => (vec![Float, Float], vec![Int]),
+            Instruction::DCMPL | Instruction::DCMPG => (vec![Double, Double], vec![Int]),
+
+            Instruction::IFEQ(_) | Instruction::IFNE(_) | Instruction::IFLT(_) |
+            Instruction::IFGE(_) | Instruction::IFGT(_) | Instruction::IFLE(_) => (vec![Int], vec![]),
+            Instruction::IF_ICMPEQ(_) | Instruction::IF_ICMPNE(_) | Instruction::IF_ICMPLT(_) |
+            Instruction::IF_ICMPGE(_) | Instruction::IF_ICMPGT(_) | Instruction::IF_ICMPLE(_) => (vec![Int, Int], vec![]),
+            Instruction::IF_ACMPEQ(_) | Instruction::IF_ACMPNE(_) => (vec![AnyReference, AnyReference], vec![]),
+            Instruction::IFNULL(_) | Instruction::IFNONNULL(_) => (vec![AnyReference], vec![]),
+
+            Instruction::IRETURN => (vec![Int], vec![]),
+            Instruction::LRETURN => (vec![Long], vec![]),
+            Instruction::FRETURN => (vec![Float], vec![]),
+            Instruction::DRETURN => (vec![Double], vec![]),
+            Instruction::ARETURN => (vec![AnyReference], vec![]),
+            Instruction::RETURN => (vec![], vec![]),
+
+            Instruction::GETSTATIC(field_ref) => (vec![], vec![field_ref.signature().type_desc().value_type()]),
+            Instruction::PUTSTATIC(field_ref) => (vec![field_ref.signature().type_desc().value_type()], vec![]),
+            Instruction::GETFIELD(field_ref) => {
+                (vec![ValueType::Reference(field_ref.class_name().clone())],
+                 vec![field_ref.signature().type_desc().value_type()])
+            }
+            Instruction::PUTFIELD(field_ref) => {
+                (vec![ValueType::Reference(field_ref.class_name().clone()), field_ref.signature().type_desc().value_type()],
+                 vec![])
+            }
+            Instruction::INVOKEVIRTUAL(method_ref) | Instruction::INVOKESTATIC(method_ref) => {
+                let signature = method_ref.signature();
+                let mut consumed = Vec::new();
+
+                if let Instruction::INVOKEVIRTUAL(_) = self {
+                    consumed.push(ValueType::Reference(method_ref.class_name().clone()));
+                }
+
+                consumed.extend(signature.params_desc().type_descs().iter().map(|type_desc| type_desc.value_type()));
+
+                let produced = match signature.return_desc() {
+                    ReturnDesc::NonVoid(type_desc) => vec![type_desc.value_type()],
+                    ReturnDesc::Void => vec![],
+                };
+
+                (consumed, produced)
+            }
+
+            Instruction::NEW(class_name) => (vec![], vec![ValueType::Reference(class_name.clone())]),
+        };
+
+        Some(StackEffect::new(consumed, produced))
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::vm::bytecode::instruction::Instruction;
+    use crate::vm::parse::parser::ClassFileParser;
+
+
+    fn parse(line: &str) -> Instruction {
+        ClassFileParser::new(line).parse_single_instruction().unwrap()
+    }
+
+
+    #[test]
+    fn infos() {
+        let mnemonics: Vec<_> = Instruction::infos().iter().map(|info| info.mnemonic()).collect();
+        let opcodes: HashSet<_> = Instruction::infos().iter().map(|info| info.opcode()).collect();
+
+        assert_eq!(mnemonics.len(), mnemonics.iter().collect::<HashSet<_>>().len());
+        for mnemonic in mnemonics {
+            assert_eq!(Instruction::info_of(mnemonic).map(|info| info.mnemonic()), Some(mnemonic));
+        }
+        assert_eq!(opcodes.len(), Instruction::infos().len());
+        assert_eq!(Instruction::IADD.info().opcode(), 0x60);
+        assert_eq!(parse("NEW A").info().mnemonic(), "NEW");
+        assert_eq!(parse("IINC 1 2").info().operands().len(), 2);
+        assert!(Instruction::info_of("IADDD").is_none());
+    }
+
+
+    #[test]
+    fn stack_effects() {
+        assert_eq!(Instruction::IADD.stack_effect().unwrap().to_string(), "…, int, int → …, int");
+        assert_eq!(parse("LDC 1.5").stack_effect().unwrap().to_string(), "… → …, float");
+        assert_eq!(parse("PUTFIELD int Point x").stack_effect().unwrap().to_string(), "…, Point, int → …");
+        assert_eq!(parse("INVOKEVIRTUAL long Point dist (int,double)").stack_effect().unwrap().to_string(),
+                   "…, Point, int, double → …, long");
+        assert!(Instruction::DUP.stack_effect().is_none());
+    }
+}
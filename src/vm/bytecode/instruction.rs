@@ -235,6 +235,203 @@ pub enum Instruction {
 }
 
 
+impl Instruction {
+    /// Returns the mnemonic of this instruction.
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::NOP => "NOP",
+            Instruction::ICONST_M1 => "ICONST_M1",
+            Instruction::ACONST_NULL => "ACONST_NULL",
+            Instruction::ICONST_0 => "ICONST_0",
+            Instruction::ICONST_1 => "ICONST_1",
+            Instruction::ICONST_2 => "ICONST_2",
+            Instruction::ICONST_3 => "ICONST_3",
+            Instruction::ICONST_4 => "ICONST_4",
+            Instruction::ICONST_5 => "ICONST_5",
+            Instruction::LCONST_0 => "LCONST_0",
+            Instruction::LCONST_1 => "LCONST_1",
+            Instruction::FCONST_0 => "FCONST_0",
+            Instruction::FCONST_1 => "FCONST_1",
+            Instruction::FCONST_2 => "FCONST_2",
+            Instruction::DCONST_0 => "DCONST_0",
+            Instruction::DCONST_1 => "DCONST_1",
+            Instruction::BIPUSH(_) => "BIPUSH",
+            Instruction::SIPUSH(_) => "SIPUSH",
+            Instruction::LDC(_) => "LDC",
+            Instruction::LDC_W(_) => "LDC_W",
+            Instruction::LDC2_W(_) => "LDC2_W",
+            Instruction::ILOAD(_) => "ILOAD",
+            Instruction::LLOAD(_) => "LLOAD",
+            Instruction::FLOAD(_) => "FLOAD",
+            Instruction::DLOAD(_) => "DLOAD",
+            Instruction::ALOAD(_) => "ALOAD",
+            Instruction::ILOAD_0 => "ILOAD_0",
+            Instruction::ILOAD_1 => "ILOAD_1",
+            Instruction::ILOAD_2 => "ILOAD_2",
+            Instruction::ILOAD_3 => "ILOAD_3",
+            Instruction::LLOAD_0 => "LLOAD_0",
+            Instruction::LLOAD_1 => "LLOAD_1",
+            Instruction::LLOAD_2 => "LLOAD_2",
+            Instruction::LLOAD_3 => "LLOAD_3",
+            Instruction::FLOAD_0 => "FLOAD_0",
+            Instruction::FLOAD_1 => "FLOAD_1",
+            Instruction::FLOAD_2 => "FLOAD_2",
+            Instruction::FLOAD_3 => "FLOAD_3",
+            Instruction::DLOAD_0 => "DLOAD_0",
+            Instruction::DLOAD_1 => "DLOAD_1",
+            Instruction::DLOAD_2 => "DLOAD_2",
+            Instruction::DLOAD_3 => "DLOAD_3",
+            Instruction::ALOAD_0 => "ALOAD_0",
+            Instruction::ALOAD_1 => "ALOAD_1",
+            Instruction::ALOAD_2 => "ALOAD_2",
+            Instruction::ALOAD_3 => "ALOAD_3",
+            Instruction::ISTORE(_) => "ISTORE",
+            Instruction::LSTORE(_) => "LSTORE",
+            Instruction::FSTORE(_) => "FSTORE",
+            Instruction::DSTORE(_) => "DSTORE",
+            Instruction::ASTORE(_) => "ASTORE",
+            Instruction::ISTORE_0 => "ISTORE_0",
+            Instruction::ISTORE_1 => "ISTORE_1",
+            Instruction::ISTORE_2 => "ISTORE_2",
+            Instruction::ISTORE_3 => "ISTORE_3",
+            Instruction::LSTORE_0 => "LSTORE_0",
+            Instruction::LSTORE_1 => "LSTORE_1",
+            Instruction::LSTORE_2 => "LSTORE_2",
+            Instruction::LSTORE_3 => "LSTORE_3",
+            Instruction::FSTORE_0 => "FSTORE_0",
+            Instruction::FSTORE_1 => "FSTORE_1",
+            Instruction::FSTORE_2 => "FSTORE_2",
+            Instruction::FSTORE_3 => "FSTORE_3",
+            Instruction::DSTORE_0 => "DSTORE_0",
+            Instruction::DSTORE_1 => "DSTORE_1",
+            Instruction::DSTORE_2 => "DSTORE_2",
+            Instruction::DSTORE_3 => "DSTORE_3",
+            Instruction::ASTORE_0 => "ASTORE_0",
+            Instruction::ASTORE_1 => "ASTORE_1",
+            Instruction::ASTORE_2 => "ASTORE_2",
+            Instruction::ASTORE_3 => "ASTORE_3",
+            Instruction::POP => "POP",
+            Instruction::POP2 => "POP2",
+            Instruction::DUP => "DUP",
+            Instruction::DUP_X1 => "DUP_X1",
+            Instruction::DUP_X2 => "DUP_X2",
+            Instruction::DUP2 => "DUP2",
+            Instruction::DUP2_X1 => "DUP2_X1",
+            Instruction::DUP2_X2 => "DUP2_X2",
+            Instruction::SWAP => "SWAP",
+            Instruction::IADD => "IADD",
+            Instruction::LADD => "LADD",
+            Instruction::FADD => "FADD",
+            Instruction::DADD => "DADD",
+            Instruction::ISUB => "ISUB",
+            Instruction::LSUB => "LSUB",
+            Instruction::FSUB => "FSUB",
+            Instruction::DSUB => "DSUB",
+            Instruction::IMUL => "IMUL",
+            Instruction::LMUL => "LMUL",
+            Instruction::FMUL => "FMUL",
+            Instruction::DMUL => "DMUL",
+            Instruction::IDIV => "IDIV",
+            Instruction::LDIV => "LDIV",
+            Instruction::FDIV => "FDIV",
+            Instruction::DDIV => "DDIV",
+            Instruction::IREM => "IREM",
+            Instruction::LREM => "LREM",
+            Instruction::FREM => "FREM",
+            Instruction::DREM => "DREM",
+            Instruction::INEG => "INEG",
+            Instruction::LNEG => "LNEG",
+            Instruction::FNEG => "FNEG",
+            Instruction::DNEG => "DNEG",
+            Instruction::ISHL => "ISHL",
+            Instruction::LSHL => "LSHL",
+            Instruction::ISHR => "ISHR",
+            Instruction::LSHR => "LSHR",
+            Instruction::IUSHR => "IUSHR",
+            Instruction::LUSHR => "LUSHR",
+            Instruction::IAND => "IAND",
+            Instruction::LAND => "LAND",
+            Instruction::IOR => "IOR",
+            Instruction::LOR => "LOR",
+            Instruction::IXOR => "IXOR",
+            Instruction::LXOR => "LXOR",
+            Instruction::IINC(_, _) => "IINC",
+            Instruction::I2L => "I2L",
+            Instruction::I2F => "I2F",
+            Instruction::I2D => "I2D",
+            Instruction::L2I => "L2I",
+            Instruction::L2F => "L2F",
+            Instruction::L2D => "L2D",
+            Instruction::F2I => "F2I",
+            Instruction::F2L => "F2L",
+            Instruction::F2D => "F2D",
+            Instruction::D2I => "D2I",
+            Instruction::D2L => "D2L",
+            Instruction::D2F => "D2F",
+            Instruction::LCMP => "LCMP",
+            Instruction::FCMPL => "FCMPL",
+            Instruction::FCMPG => "FCMPG",
+            Instruction::DCMPL => "DCMPL",
+            Instruction::DCMPG => "DCMPG",
+            Instruction::IFEQ(_) => "IFEQ",
+            Instruction::IFNE(_) => "IFNE",
+            Instruction::IFLT(_) => "IFLT",
+            Instruction::IFGE(_) => "IFGE",
+            Instruction::IFGT(_) => "IFGT",
+            Instruction::IFLE(_) => "IFLE",
+            Instruction::IF_ICMPEQ(_) => "IF_ICMPEQ",
+            Instruction::IF_ICMPNE(_) => "IF_ICMPNE",
+            Instruction::IF_ICMPLT(_) => "IF_ICMPLT",
+            Instruction::IF_ICMPGE(_) => "IF_ICMPGE",
+            Instruction::IF_ICMPGT(_) => "IF_ICMPGT",
+            Instruction::IF_ICMPLE(_) => "IF_ICMPLE",
+            Instruction::IF_ACMPEQ(_) => "IF_ACMPEQ",
+            Instruction::IF_ACMPNE(_) => "IF_ACMPNE",
+            Instruction::GOTO(_) => "GOTO",
+            Instruction::IFNULL(_) => "IFNULL",
+            Instruction::IFNONNULL(_) => "IFNONNULL",
+            Instruction::IRETURN => "IRETURN",
+            Instruction::LRETURN => "LRETURN",
+            Instruction::FRETURN => "FRETURN",
+            Instruction::DRETURN => "DRETURN",
+            Instruction::ARETURN => "ARETURN",
+            Instruction::RETURN => "RETURN",
+            Instruction::GETSTATIC(_) => "GETSTATIC",
+            Instruction::PUTSTATIC(_) => "PUTSTATIC",
+            Instruction::GETFIELD(_) => "GETFIELD",
+            Instruction::PUTFIELD(_) => "PUTFIELD",
+            Instruction::INVOKEVIRTUAL(_) => "INVOKEVIRTUAL",
+            Instruction::INVOKESTATIC(_) => "INVOKESTATIC",
+            Instruction::NEW(_) => "NEW",
+        }
+    }
+
+    /// Returns the branch offset if this is a branch instruction.
+    pub fn branch_offset(&self) -> Option<i16> {
+        match self {
+            Instruction::IFEQ(offset) => Some(*offset),
+            Instruction::IFNE(offset) => Some(*offset),
+            Instruction::IFLT(offset) => Some(*offset),
+            Instruction::IFGE(offset) => Some(*offset),
+            Instruction::IFGT(offset) => Some(*offset),
+            Instruction::IFLE(offset) => Some(*offset),
+            Instruction::IF_ICMPEQ(offset) => Some(*offset),
+            Instruction::IF_ICMPNE(offset) => Some(*offset),
+            Instruction::IF_ICMPLT(offset) => Some(*offset),
+            Instruction::IF_ICMPGE(offset) => Some(*offset),
+            Instruction::IF_ICMPGT(offset) => Some(*offset),
+            Instruction::IF_ICMPLE(offset) => Some(*offset),
+            Instruction::IF_ACMPEQ(offset) => Some(*offset),
+            Instruction::IF_ACMPNE(offset) => Some(*offset),
+            Instruction::GOTO(offset) => Some(*offset),
+            Instruction::IFNULL(offset) => Some(*offset),
+            Instruction::IFNONNULL(offset) => Some(*offset),
+            _ => None,
+        }
+    }
+}
+
+
 impl Display for Instruction {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
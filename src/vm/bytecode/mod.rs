@@ -1,4 +1,5 @@
 pub mod instruction;
+pub mod info;
 pub mod exec;
 pub mod constant;
 pub mod load;
@@ -1,17 +1,25 @@
+use std::sync::Arc;
+
 use crate::vm::bytecode::instruction::Instruction;
+use crate::vm::class::class::Class;
+use crate::vm::class::method::Method;
 use crate::vm::class::name::ClassName;
+use crate::vm::class::resolved::ResolvedRef;
 use crate::vm::class::symbolic::{FieldRef, MethodRef};
 use crate::vm::exec::error::ExecError;
+use crate::vm::exec::native;
+use crate::vm::exec::native::Native;
 use crate::vm::exec::thread::Thread;
 use crate::vm::memory::frame::Frame;
+use crate::vm::types::int::Int;
 use crate::vm::types::reference::Reference;
 
 
 impl Instruction {
     pub(super) fn getstatic(&self, thread: &Thread, field_ref: &FieldRef) -> Result<(), ExecError> {
         let frame = thread.stack().current().unwrap();
-        let class = thread.runtime().resolve_class(field_ref.class_name())?;
-        let value = class.static_field_value(field_ref.signature())?;
+        let (class, offset) = self.resolve_field(thread, &frame, field_ref, true)?;
+        let value = class.static_field_value_at(offset);
         frame.stack().push_value(value)?;
         frame.inc_pc();
         Ok(())
@@ -19,18 +27,18 @@ impl Instruction {
 
     pub(super) fn putstatic(&self, thread: &Thread, field_ref: &FieldRef) -> Result<(), ExecError> {
         let frame = thread.stack().current().unwrap();
-        let class = thread.runtime().resolve_class(field_ref.class_name())?;
+        let (class, offset) = self.resolve_field(thread, &frame, field_ref, true)?;
         let value = frame.stack().pop_value()?;
-        class.set_static_field_value(field_ref.signature(), value)?;
+        class.set_static_field_value_at(offset, field_ref.signature(), value)?;
         frame.inc_pc();
         Ok(())
     }
 
     pub(super) fn getfield(&self, thread: &Thread, field_ref: &FieldRef) -> Result<(), ExecError> {
         let frame = thread.stack().current().unwrap();
-        let class = thread.runtime().resolve_class(field_ref.class_name())?;
+        let (class, offset) = self.resolve_field(thread, &frame, field_ref, false)?;
         let instance = frame.stack().pop::<Reference>()?.into_instance()?;
-        let value = class.instance_field_value(&instance, field_ref.signature())?;
+        let value = class.instance_field_value_at(&instance, offset)?;
         frame.stack().push_value(value)?;
         frame.inc_pc();
         Ok(())
@@ -38,19 +46,18 @@ impl Instruction {
 
     pub(super) fn putfield(&self, thread: &Thread, field_ref: &FieldRef) -> Result<(), ExecError> {
         let frame = thread.stack().current().unwrap();
-        let class = thread.runtime().resolve_class(field_ref.class_name())?;
+        let (class, offset) = self.resolve_field(thread, &frame, field_ref, false)?;
         let value = frame.stack().pop_value()?;
         let instance = frame.stack().pop::<Reference>()?.into_instance()?;
-        class.set_instance_field_value(&instance, field_ref.signature(), value)?;
+        class.set_instance_field_value_at(&instance, offset, field_ref.signature(), value)?;
         frame.inc_pc();
         Ok(())
     }
 
     pub(super) fn invokevirtual(&self, thread: &Thread, method_ref: &MethodRef) -> Result<(), ExecError> {
         let frame = thread.stack().current().unwrap();
-        let class = thread.runtime().resolve_class(method_ref.class_name())?;
-        let method = class.instance_method(method_ref.signature())?;
-        let next_frame = Frame::new_from_call(class.clone(), method.clone(), frame.stack())?;
+        let (class, method) = self.resolve_method(thread, &frame, method_ref, false)?;
+        let next_frame = Self::new_frame(thread, class, method, &frame)?;
         thread.stack().push(next_frame);
         frame.inc_pc();
         Ok(())
@@ -58,9 +65,13 @@ impl Instruction {
 
     pub(super) fn invokestatic(&self, thread: &Thread, method_ref: &MethodRef) -> Result<(), ExecError> {
         let frame = thread.stack().current().unwrap();
-        let class = thread.runtime().resolve_class(method_ref.class_name())?;
-        let method = class.static_method(method_ref.signature())?;
-        let next_frame = Frame::new_from_call(class.clone(), method.clone(), frame.stack())?;
+
+        if let Some(native) = Native::find(method_ref) {
+            return Self::invoke_native(thread, &frame, native);
+        }
+
+        let (class, method) = self.resolve_method(thread, &frame, method_ref, true)?;
+        let next_frame = Self::new_frame(thread, class, method, &frame)?;
         thread.stack().push(next_frame);
         frame.inc_pc();
         Ok(())
@@ -68,11 +79,109 @@ impl Instruction {
 
     pub(super) fn new(&self, thread: &Thread, class_name: &ClassName) -> Result<(), ExecError> {
         let frame = thread.stack().current().unwrap();
-        let class = thread.runtime().resolve_class(class_name)?;
-        let instance = thread.runtime().create_instance(class);
+        let class = self.resolve_class(thread, &frame, class_name)?;
+        let instance = thread.runtime().create_instance(class)?;
         let reference = Reference::new(instance);
         frame.stack().push(reference)?;
         frame.inc_pc();
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+
+impl Instruction {
+    fn invoke_native(thread: &Thread, frame: &Frame, native: Native) -> Result<(), ExecError> {
+        match native {
+            Native::Exit => {
+                let status = frame.stack().pop::<Int>()?;
+                thread.runtime().exit(status.into());
+
+                // the program ends, so all the frames are discarded
+                while thread.stack().pop().is_some() {}
+            }
+            Native::IdentityHashCode => {
+                let reference = frame.stack().pop::<Reference>()?;
+                frame.stack().push(native::identity_hash_code(&reference))?;
+                frame.inc_pc();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates the frame of the called method within the limits of the runtime.
+    fn new_frame(thread: &Thread, class: Arc<Class>, method: Arc<Method>, caller: &Frame) -> Result<Frame, ExecError> {
+        let config = thread.runtime().config();
+
+        if thread.stack().len() >= config.max_frame_depth() {
+            return Err(ExecError::FrameStackOverflow { max: config.max_frame_depth() });
+        }
+
+        Ok(Frame::new_from_call(class, method, caller.stack(), config.max_operand_stack())?)
+    }
+}
+
+
+/// Resolution of the symbolic references.
+/// The first execution of the instruction resolves the reference and stores
+/// it in the code of the frame method, the next executions use the stored one.
+impl Instruction {
+    fn resolve_class(&self, thread: &Thread, frame: &Frame, class_name: &ClassName) -> Result<Arc<Class>, ExecError> {
+        let resolved = frame.method().code().resolved();
+        let pc = frame.pc() as usize;
+
+        if let Some(ResolvedRef::Class(class)) = resolved.get(pc) {
+            if let Some(class) = class.upgrade() {
+                return Ok(class);
+            }
+        }
+
+        let class = thread.runtime().resolve_class(class_name)?;
+        resolved.set(pc, ResolvedRef::Class(Arc::downgrade(&class)));
+        Ok(class)
+    }
+
+    fn resolve_field(&self, thread: &Thread, frame: &Frame, field_ref: &FieldRef, is_static: bool) -> Result<(Arc<Class>, usize), ExecError> {
+        let resolved = frame.method().code().resolved();
+        let pc = frame.pc() as usize;
+
+        if let Some(ResolvedRef::Field { class, offset }) = resolved.get(pc) {
+            if let Some(class) = class.upgrade() {
+                return Ok((class, *offset));
+            }
+        }
+
+        let class = thread.runtime().resolve_class(field_ref.class_name())?;
+
+        let offset = if is_static {
+            class.static_field_offset(field_ref.signature())?
+        } else {
+            class.instance_field_offset(field_ref.signature())?
+        };
+
+        resolved.set(pc, ResolvedRef::Field { class: Arc::downgrade(&class), offset });
+        Ok((class, offset))
+    }
+
+    fn resolve_method(&self, thread: &Thread, frame: &Frame, method_ref: &MethodRef, is_static: bool) -> Result<(Arc<Class>, Arc<Method>), ExecError> {
+        let resolved = frame.method().code().resolved();
+        let pc = frame.pc() as usize;
+
+        if let Some(ResolvedRef::Method { class, method }) = resolved.get(pc) {
+            if let (Some(class), Some(method)) = (class.upgrade(), method.upgrade()) {
+                return Ok((class, method));
+            }
+        }
+
+        let class = thread.runtime().resolve_class(method_ref.class_name())?;
+
+        let method = if is_static {
+            class.static_method(method_ref.signature())?.clone()
+        } else {
+            class.instance_method(method_ref.signature())?.clone()
+        };
+
+        resolved.set(pc, ResolvedRef::Method { class: Arc::downgrade(&class), method: Arc::downgrade(&method) });
+        Ok((class, method))
+    }
+}
@@ -18,6 +18,7 @@ pub struct Class {
     methods: Vec<Arc<Method>>,
     static_fields_values: RwLock<Vec<Value>>,
     nonstatic_fields_len: usize,
+    source_file: Option<String>,
 }
 
 
@@ -92,13 +93,25 @@ impl Class {
             methods,
             static_fields_values: RwLock::new(static_fields_values),
             nonstatic_fields_len,
+            source_file: None,
         })
     }
 
+    /// Sets the name of the Java source file the class was compiled from.
+    pub fn with_source_file(mut self, source_file: Option<String>) -> Self {
+        self.source_file = source_file;
+        self
+    }
+
     /// Returns the name of this class.
     pub fn name(&self) -> &ClassName {
         &self.name
     }
+
+    /// Returns the name of the Java source file the class was compiled from, if known.
+    pub fn source_file(&self) -> Option<&str> {
+        self.source_file.as_deref()
+    }
 }
 
 
@@ -133,7 +146,7 @@ impl Class {
     ///
     /// Returns a `ClassError::NoSuchField` if there is not an instance field of the given signature.
     pub fn instance_field(&self, signature: &FieldSig) -> Result<&Arc<Field>, ClassError> {
-        Ok(&self.static_field_entry(signature)?.field)
+        Ok(&self.instance_field_entry(signature)?.field)
     }
 }
 
@@ -193,8 +206,7 @@ impl Class {
     ///
     /// Returns a `ClassError::NoSuchField` if there is not a static field of the given signature.
     pub fn static_field_value(&self, signature: &FieldSig) -> Result<Value, ClassError> {
-        let i = self.static_field_entry(signature)?.offset;
-        Ok(self.static_fields_values.read().unwrap()[i].clone())
+        Ok(self.static_field_value_at(self.static_field_offset(signature)?))
     }
 
     /// Sets a static field value.
@@ -203,14 +215,7 @@ impl Class {
     ///
     /// Returns a `ClassError::NoSuchField` if there is not a static field of the given signature.
     pub fn set_static_field_value(&self, signature: &FieldSig, value: Value) -> Result<(), ClassError> {
-        let i = self.static_field_entry(signature)?.offset;
-
-        if !signature.type_desc().is_assignable_with(&value) {
-            return Err(ClassError::FieldValueTypeMismatch(signature.clone(), value));
-        }
-
-        self.static_fields_values.write().unwrap()[i] = value;
-        Ok(())
+        self.set_static_field_value_at(self.static_field_offset(signature)?, signature, value)
     }
 
     /// Returns an instance field value.
@@ -220,12 +225,7 @@ impl Class {
     /// Returns a `ClassError::NoSuchField` if there is not a static field of the given signature.
     /// Returns a `ClassError::NotInstanceOf` if the given instance is not an instance of this class.
     pub fn instance_field_value(&self, instance: &Instance, signature: &FieldSig) -> Result<Value, ClassError> {
-        if self.name != instance.class().name {
-            return Err(ClassError::NotInstanceOf(instance.class().name.clone(), self.name.clone()));
-        }
-
-        let i = self.instance_field_entry(signature)?.offset;
-        Ok(instance.field(i))
+        self.instance_field_value_at(instance, self.instance_field_offset(signature)?)
     }
 
     /// Sets an instance field value.
@@ -235,17 +235,105 @@ impl Class {
     /// Returns a `ClassError::NoSuchField` if there is not a instance field of the given signature.
     /// Returns a `ClassError::NotInstanceOf` if the given instance is not an instance of this class.
     pub fn set_instance_field_value(&self, instance: &Instance, signature: &FieldSig, value: Value) -> Result<(), ClassError> {
-        if self.name != instance.class().name {
-            return Err(ClassError::NotInstanceOf(instance.class().name.clone(), self.name.clone()));
+        self.set_instance_field_value_at(instance, self.instance_field_offset(signature)?, signature, value)
+    }
+}
+
+
+/// Field values by offsets.
+/// The offsets are found once by the field signatures, so the repeated accesses
+/// do not have to search the fields.
+impl Class {
+    /// Returns the offset of a static field of the given signature.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ClassError::NoSuchField` if there is not a static field of the given signature.
+    pub fn static_field_offset(&self, signature: &FieldSig) -> Result<usize, ClassError> {
+        Ok(self.static_field_entry(signature)?.offset)
+    }
+
+    /// Returns the offset of an instance field of the given signature.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ClassError::NoSuchField` if there is not an instance field of the given signature.
+    pub fn instance_field_offset(&self, signature: &FieldSig) -> Result<usize, ClassError> {
+        Ok(self.instance_field_entry(signature)?.offset)
+    }
+
+    /// Returns a value of the static field on the given offset.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the offset is out of bounds.
+    pub fn static_field_value_at(&self, offset: usize) -> Value {
+        self.static_fields_values.read().unwrap()[offset].clone()
+    }
+
+    /// Sets a value of the static field of the given signature on the given offset.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ClassError::FieldValueTypeMismatch` if the value does not match the field type.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the offset is out of bounds.
+    pub fn set_static_field_value_at(&self, offset: usize, signature: &FieldSig, value: Value) -> Result<(), ClassError> {
+        if !signature.type_desc().is_assignable_with(&value) {
+            return Err(ClassError::FieldValueTypeMismatch(signature.clone(), value));
         }
 
-        let i = self.instance_field_entry(signature)?.offset;
+        self.static_fields_values.write().unwrap()[offset] = value;
+        Ok(())
+    }
+
+    /// Returns a value of the instance field on the given offset.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ClassError::NotInstanceOf` if the given instance is not an instance of this class.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the offset is out of bounds.
+    pub fn instance_field_value_at(&self, instance: &Instance, offset: usize) -> Result<Value, ClassError> {
+        self.check_instance(instance)?;
+        Ok(instance.field(offset))
+    }
+
+    /// Sets a value of the instance field of the given signature on the given offset.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ClassError::NotInstanceOf` if the given instance is not an instance of this class
+    /// or `ClassError::FieldValueTypeMismatch` if the value does not match the field type.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the offset is out of bounds.
+    pub fn set_instance_field_value_at(&self, instance: &Instance, offset: usize, signature: &FieldSig, value: Value) -> Result<(), ClassError> {
+        self.check_instance(instance)?;
 
         if !signature.type_desc().is_assignable_with(&value) {
             return Err(ClassError::FieldValueTypeMismatch(signature.clone(), value));
         }
 
-        instance.set_field(i, value);
+        instance.set_field(offset, value);
+        Ok(())
+    }
+
+    /// Checks whether the given instance is an instance of this class.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ClassError::NotInstanceOf` if the given instance is not an instance of this class.
+    fn check_instance(&self, instance: &Instance) -> Result<(), ClassError> {
+        if !std::ptr::eq(self, &**instance.class()) && self.name != instance.class().name {
+            return Err(ClassError::NotInstanceOf(instance.class().name.clone(), self.name.clone()));
+        }
+
         Ok(())
     }
 }
@@ -286,6 +374,19 @@ impl Class {
         Ok(method)
     }
 
+    /// Finds the main method of the class, a static method with a main signature,
+    /// see `MethodSig::is_main`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ClassError::NoSuchMethod` with the `static void main ()` signature
+    /// if there is no main method.
+    pub fn main_method(&self) -> Result<&Arc<Method>, ClassError> {
+        self.methods.iter()
+            .find(|method| method.is_static() && method.signature().is_main())
+            .ok_or_else(|| ClassError::NoSuchMethod(MethodSig::main()))
+    }
+
     /// Finds an instance method of the given signature.
     ///
     /// # Errors
@@ -1,26 +1,39 @@
+use std::fmt;
+
 use crate::vm::bytecode::instruction::Instruction;
 use crate::vm::class::error::CodeError;
+use crate::vm::class::resolved::ResolvedRefs;
 use crate::vm::memory::locals::Locals;
 
 
 /// A method code.
 /// It is an indexable array of bytecode instructions together with
 /// predefined size of locals array needed for execution.
+/// Instructions may be labeled by symbolic names used as branch targets.
+/// Symbolic references of the instructions are cached once resolved.
+/// The code parsed from a class file knows the line of each instruction
+/// and optionally the lines of the original Java source.
 #[derive(Debug, Clone)]
 pub struct Code {
     locals_size: usize,
     instructions: Vec<Instruction>,
+    labels: Vec<(usize, String)>,
+    lines: Vec<usize>,
+    source_lines: Vec<(usize, usize)>,
+    resolved: ResolvedRefs,
 }
 
 
 impl Code {
-    /// Create a new `Code` with the given locals array size and instruction.
+    /// Create a new `Code` with the given locals array size, instructions
+    /// and labels - pairs of the labeled instruction index and the label name.
     ///
     /// # Errors
     ///
-    /// Returns `CodeError::NoInstructions` if no instructions are given
-    /// or `CodeError::TooBigLocalsSize` if `locals_size` is bigger than allowed.
-    pub fn new(locals_size: usize, instructions: Vec<Instruction>) -> Result<Self, CodeError> {
+    /// Returns `CodeError::NoInstructions` if no instructions are given,
+    /// `CodeError::TooBigLocalsSize` if `locals_size` is bigger than allowed
+    /// or `CodeError::LabelOutOfBounds` if a label does not point to any instruction.
+    pub fn new(locals_size: usize, instructions: Vec<Instruction>, mut labels: Vec<(usize, String)>) -> Result<Self, CodeError> {
         if instructions.is_empty() {
             return Err(CodeError::NoInstructions);
         }
@@ -32,7 +45,19 @@ impl Code {
             });
         }
 
-        Ok(Code { locals_size, instructions })
+        if let Some((index, label)) = labels.iter().find(|(index, _)| *index >= instructions.len()) {
+            return Err(CodeError::LabelOutOfBounds {
+                label: label.clone(),
+                max: instructions.len(),
+                index: *index,
+            });
+        }
+
+        labels.sort_by_key(|(index, _)| *index);
+
+        let resolved = ResolvedRefs::new(instructions.len());
+
+        Ok(Code { locals_size, instructions, labels, lines: Vec::new(), source_lines: Vec::new(), resolved })
     }
 
     /// Get the locals size.
@@ -50,15 +75,116 @@ impl Code {
     /// # Errors
     ///
     /// Returns `CodeError::InstructionOutOfBounds` if the index is greater than the last instruction index.
-    pub fn instruction(&self, index: isize) -> Result<Instruction, CodeError> {
+    pub fn instruction(&self, index: isize) -> Result<&Instruction, CodeError> {
         if index < 0 {
             return Err(CodeError::IndexOutOfBounds { max: self.instructions.len(), index });
         }
 
         match self.instructions.get(index as usize) {
             None => Err(CodeError::IndexOutOfBounds { max: self.instructions.len(), index }),
-            Some(instruction) => Ok(instruction.clone()),
+            Some(instruction) => Ok(instruction),
         }
     }
+
+    /// Returns the cache of the symbolic references resolved by the instructions.
+    pub fn resolved(&self) -> &ResolvedRefs {
+        &self.resolved
+    }
 }
 
+
+/// Lines.
+impl Code {
+    /// Sets the lines of the class file the instructions were parsed from,
+    /// one for every instruction, starting from 1.
+    pub fn with_lines(mut self, lines: Vec<usize>) -> Self {
+        self.lines = lines;
+        self
+    }
+
+    /// Returns the line of the class file of the instruction on the given index,
+    /// if the code was parsed from a class file.
+    pub fn line(&self, index: isize) -> Option<usize> {
+        if index < 0 {
+            return None;
+        }
+
+        self.lines.get(index as usize).cloned()
+    }
+
+    /// Returns the index of the instruction on the given line of the class file, if any.
+    pub fn index_at_line(&self, line: usize) -> Option<usize> {
+        self.lines.iter().position(|l| *l == line)
+    }
+
+    /// Sets the line-number table of the Java source - pairs of the index
+    /// of the first instruction compiled from a source line and the line.
+    pub fn with_source_lines(mut self, mut source_lines: Vec<(usize, usize)>) -> Self {
+        source_lines.sort_by_key(|(index, _)| *index);
+        self.source_lines = source_lines;
+        self
+    }
+
+    /// Returns the line of the Java source the instruction on the given index
+    /// was compiled from, if the code has a line-number table covering it.
+    pub fn source_line(&self, index: isize) -> Option<usize> {
+        if index < 0 || index as usize >= self.instructions.len() {
+            return None;
+        }
+
+        self.source_lines.iter()
+            .take_while(|(start, _)| *start <= index as usize)
+            .last()
+            .map(|(_, line)| *line)
+    }
+}
+
+
+/// Labels.
+impl Code {
+    /// Returns an iterator over all labels as pairs of the labeled
+    /// instruction index and the label name, ordered by the index.
+    pub fn labels(&self) -> impl ExactSizeIterator<Item=(usize, &str)> {
+        self.labels.iter().map(|(index, label)| (*index, label.as_str()))
+    }
+
+    /// Returns an iterator over names of the labels of the instruction on the given index.
+    pub fn labels_at(&self, index: usize) -> impl Iterator<Item=&str> {
+        self.labels.iter()
+            .filter(move |(i, _)| *i == index)
+            .map(|(_, label)| label.as_str())
+    }
+
+    /// Returns the textual representation of the instruction on the given index.
+    /// Branch offsets are replaced by the target labels where possible.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CodeError::InstructionOutOfBounds` if the index is greater than the last instruction index.
+    pub fn instruction_str(&self, index: usize) -> Result<String, CodeError> {
+        let instruction = self.instruction(index as isize)?;
+
+        let target = instruction.branch_offset()
+                                .map(|offset| index as isize + offset as isize)
+                                .filter(|target| *target >= 0)
+                                .and_then(|target| self.labels_at(target as usize).next());
+
+        Ok(match target {
+            None => instruction.to_string(),
+            Some(label) => format!("{:15} {}", instruction.mnemonic(), label),
+        })
+    }
+}
+
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for index in 0..self.instructions.len() {
+            for label in self.labels_at(index) {
+                writeln!(f, "{}:", label)?;
+            }
+            writeln!(f, "    {}", self.instruction_str(index).map_err(|_| fmt::Error)?)?;
+        }
+        Ok(())
+    }
+}
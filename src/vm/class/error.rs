@@ -54,6 +54,12 @@ pub enum CodeError {
     },
     #[error("there are no instructions in the code")]
     NoInstructions,
+    #[error("label {label} is out of bounds: the length is {max} but the label index is {index}")]
+    LabelOutOfBounds {
+        label: String,
+        max: usize,
+        index: usize,
+    },
 }
 
 
@@ -1,4 +1,5 @@
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
 use std::sync::{Arc, Mutex};
 
 use crate::vm::class::class::Class;
@@ -15,15 +16,23 @@ pub struct Instance {
 /// A class instance data.
 #[derive(Debug)]
 struct InstanceData {
+    id: InstanceId,
     class: Arc<Class>,
     fields: Mutex<Vec<Value>>,
 }
 
 
 impl Instance {
-    /// Creates a new instance by collecting and initializing the instance
-    /// fields of the given class.
-    pub fn new(class: Arc<Class>) -> Self {
+    /// Estimated size of the instance header in bytes.
+    const HEADER_SIZE: usize = 16;
+
+    /// Estimated size of a field slot in bytes.
+    const SLOT_SIZE: usize = 4;
+
+    /// Creates a new instance with the given id by collecting and initializing the instance
+    /// fields of the given class. The ids should be assigned by the virtual machine,
+    /// see `Vm::create_instance`.
+    pub fn new(id: InstanceId, class: Arc<Class>) -> Self {
         let fields = class.fields()
                           .filter(|field| !field.is_static())
                           .map(|field|
@@ -35,6 +44,7 @@ impl Instance {
 
         Instance {
             data: Arc::new(InstanceData {
+                id,
                 class,
                 fields: Mutex::new(fields),
             })
@@ -64,19 +74,68 @@ impl Instance {
         self.data.fields.lock().unwrap()[index] = value
     }
 
+    /// Get the estimated size of the instance in bytes - the header and the slots
+    /// of the fields, where long and double fields take two slots.
+    pub fn size(&self) -> usize {
+        let slots: usize = self.data.fields.lock().unwrap()
+                               .iter()
+                               .map(|value| value.value_type().category().size())
+                               .sum();
+
+        Self::HEADER_SIZE + slots * Self::SLOT_SIZE
+    }
+
     /// Get the instance id.
     pub fn id(&self) -> InstanceId {
-        InstanceId(Arc::as_ptr(&self.data) as usize)
+        self.data.id
+    }
+
+    /// Get the identity hash code of the instance, which is derived from its id,
+    /// so it is the same in every run of a deterministic program.
+    pub fn identity_hash(&self) -> i32 {
+        self.data.id.0 as i32
+    }
+}
+
+
+impl PartialEq for Instance {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
     }
 }
 
 
+impl Eq for Instance {}
+
+
+impl Hash for Instance {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state)
+    }
+}
+
+
+/// An instance id - the number of the allocation which created the instance,
+/// starting from 1 in every virtual machine.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Ord, PartialOrd, Hash)]
-pub struct InstanceId(usize);
+pub struct InstanceId(u64);
+
+
+impl InstanceId {
+    /// Returns the id of the first allocation.
+    pub fn first() -> Self {
+        InstanceId(1)
+    }
+
+    /// Returns the id of the allocation following this one.
+    pub fn next(self) -> Self {
+        InstanceId(self.0 + 1)
+    }
+}
 
 
 impl Display for InstanceId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:X}", self.0)
+        write!(f, "{}", self.0)
     }
 }
\ No newline at end of file
@@ -6,5 +6,6 @@ pub mod signature;
 pub mod field;
 pub mod method;
 pub mod code;
+pub mod resolved;
 pub mod class;
 pub mod instance;
@@ -0,0 +1,76 @@
+//! Cache of resolved symbolic references.
+
+use std::fmt;
+use std::sync::{OnceLock, Weak};
+
+use crate::vm::class::class::Class;
+use crate::vm::class::method::Method;
+
+
+/// A symbolic reference of an instruction resolved by its first execution.
+///
+/// Resolved classes and methods are held weakly, so the cache
+/// of a method does not keep its own class alive.
+#[derive(Debug, Clone)]
+pub enum ResolvedRef {
+    Class(Weak<Class>),
+    Field {
+        class: Weak<Class>,
+        offset: usize,
+    },
+    Method {
+        class: Weak<Class>,
+        method: Weak<Method>,
+    },
+}
+
+
+/// A per instruction cache of resolved symbolic references.
+///
+/// Each entry is written once, classes are never unloaded from a virtual machine,
+/// so a reference once resolved stays valid and reading it takes no lock.
+pub struct ResolvedRefs {
+    refs: Vec<OnceLock<ResolvedRef>>,
+}
+
+
+impl ResolvedRefs {
+    /// Creates a new empty cache for the given number of instructions.
+    pub fn new(len: usize) -> Self {
+        ResolvedRefs {
+            refs: (0..len).map(|_| OnceLock::new()).collect()
+        }
+    }
+
+    /// Returns the reference resolved by the instruction on the given index if any.
+    pub fn get(&self, index: usize) -> Option<&ResolvedRef> {
+        self.refs.get(index)?.get()
+    }
+
+    /// Stores the reference resolved by the instruction on the given index.
+    /// A reference stored before, e.g. by another thread, is kept.
+    pub fn set(&self, index: usize, resolved: ResolvedRef) {
+        if let Some(entry) = self.refs.get(index) {
+            let _ = entry.set(resolved);
+        }
+    }
+}
+
+
+impl Clone for ResolvedRefs {
+    /// Creates a new empty cache - the resolved references are bound
+    /// to the classes of the virtual machine which resolved them.
+    fn clone(&self) -> Self {
+        ResolvedRefs::new(self.refs.len())
+    }
+}
+
+
+impl fmt::Debug for ResolvedRefs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let resolved = self.refs.iter()
+                           .filter(|entry| entry.get().is_some())
+                           .count();
+        write!(f, "ResolvedRefs {{ {}/{} resolved }}", resolved, self.refs.len())
+    }
+}
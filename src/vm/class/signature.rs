@@ -44,6 +44,11 @@ impl MethodSig {
         })
     }
 
+    /// Creates the signature of the main method `void main ()`.
+    pub fn main() -> Self {
+        MethodSig::new(ReturnDesc::Void, MethodName::new("main").unwrap(), ParamsDesc::empty()).unwrap()
+    }
+
     /// Returns the the method return type descriptor.
     pub fn return_desc(&self) -> &ReturnDesc {
         &self.return_desc
@@ -71,6 +76,15 @@ impl MethodSig {
     pub fn is_clinit(&self) -> bool {
         self.name.is_clinit() && self.return_desc.is_void()
     }
+
+    /// Returns true if this signature can be a main method, false otherwise.
+    /// A main method is named `main`, returns `void` or the `int` exit status
+    /// and its parameters, the program arguments, are not references.
+    pub fn is_main(&self) -> bool {
+        self.name.as_ref() == "main"
+            && (self.return_desc.is_void() || self.return_desc == ReturnDesc::NonVoid(TypeDesc::Int))
+            && !self.params_desc.type_descs().iter().any(TypeDesc::is_reference)
+    }
 }
 
 
@@ -0,0 +1,254 @@
+//! Reading of zip archives, including jar files, on the class path.
+//!
+//! Only the features needed for class files are supported: entries
+//! which are stored or compressed by deflate, without encryption and zip64.
+
+use std::convert::TryInto;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use miniz_oxide::inflate::TINFLStatus;
+
+
+/// A zip archive read into memory.
+pub struct Archive {
+    data: Vec<u8>,
+    entries: Vec<ArchiveEntry>,
+}
+
+
+/// An entry of the central directory of a zip archive.
+struct ArchiveEntry {
+    name: String,
+    method: u16,
+    compressed_size: usize,
+    size: usize,
+    header_offset: usize,
+}
+
+
+impl Archive {
+    const END_SIGNATURE: u32 = 0x0605_4b50;
+    const CENTRAL_SIGNATURE: u32 = 0x0201_4b50;
+    const LOCAL_SIGNATURE: u32 = 0x0403_4b50;
+
+    const STORED: u16 = 0;
+    const DEFLATED: u16 = 8;
+
+    /// Returns whether the class path entry is an archive, judged by its extension.
+    pub fn is_archive(path: &Path) -> bool {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) => extension.eq_ignore_ascii_case("zip") || extension.eq_ignore_ascii_case("jar"),
+            None => false,
+        }
+    }
+
+    /// Reads the archive from the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the file can not be read or is not a valid zip archive.
+    pub fn open(path: &Path) -> Result<Self, io::Error> {
+        Self::new(fs::read(path)?)
+    }
+
+    /// Reads the directory of the archive given by its contents.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` of kind `InvalidData` if the data are not a valid zip archive.
+    pub fn new(data: Vec<u8>) -> Result<Self, io::Error> {
+        // the end of central directory record is followed by a comment of at most 65535 bytes
+        let search_start = data.len().saturating_sub(22 + 0xffff);
+        let end = (search_start..data.len().saturating_sub(21))
+            .rev()
+            .find(|offset| read_u32(&data, *offset) == Some(Self::END_SIGNATURE))
+            .ok_or_else(|| invalid("missing end of central directory"))?;
+
+        let count = read_u16(&data, end + 10).ok_or_else(|| invalid("truncated end of central directory"))?;
+        let mut offset = read_u32(&data, end + 16).ok_or_else(|| invalid("truncated end of central directory"))? as usize;
+        let mut entries = Vec::with_capacity(count as usize);
+
+        for _ in 0..count {
+            if read_u32(&data, offset) != Some(Self::CENTRAL_SIGNATURE) {
+                return Err(invalid("invalid central directory entry"));
+            }
+
+            let field = |at: usize, size: usize| match size {
+                2 => read_u16(&data, offset + at).map(usize::from),
+                _ => read_u32(&data, offset + at).map(|value| value as usize),
+            }.ok_or_else(|| invalid("truncated central directory entry"));
+
+            let name_length = field(28, 2)?;
+            let name = data.get(offset + 46..offset + 46 + name_length)
+                           .ok_or_else(|| invalid("truncated central directory entry"))?;
+
+            entries.push(ArchiveEntry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                method: field(10, 2)? as u16,
+                compressed_size: field(20, 4)?,
+                size: field(24, 4)?,
+                header_offset: field(42, 4)?,
+            });
+
+            offset += 46 + name_length + field(30, 2)? + field(32, 2)?;
+        }
+
+        Ok(Archive {
+            data,
+            entries,
+        })
+    }
+
+    /// Returns the names of the files in the archive, the directories are separated by `/`.
+    pub fn names(&self) -> impl Iterator<Item=&str> {
+        self.entries.iter()
+            .map(|entry| entry.name.as_str())
+            .filter(|name| !name.ends_with('/'))
+    }
+
+    /// Returns the contents of the file of the given name, or `None` if there is no such file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the file is compressed by an unsupported method
+    /// or its data are invalid.
+    pub fn read(&self, name: &str) -> Result<Option<Vec<u8>>, io::Error> {
+        let entry = match self.entries.iter().find(|entry| entry.name == name) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        let header = entry.header_offset;
+        if read_u32(&self.data, header) != Some(Self::LOCAL_SIGNATURE) {
+            return Err(invalid("invalid local file header"));
+        }
+
+        let name_length = read_u16(&self.data, header + 26).ok_or_else(|| invalid("truncated local file header"))?;
+        let extra_length = read_u16(&self.data, header + 28).ok_or_else(|| invalid("truncated local file header"))?;
+        let start = header + 30 + name_length as usize + extra_length as usize;
+        let compressed = self.data.get(start..start + entry.compressed_size)
+                             .ok_or_else(|| invalid("truncated file data"))?;
+
+        let contents = match entry.method {
+            Self::STORED => compressed.to_vec(),
+            Self::DEFLATED => inflate(compressed, entry.size)?,
+            method => {
+                return Err(io::Error::new(io::ErrorKind::Unsupported,
+                                          format!("unsupported compression method {} of {}", method, name)));
+            }
+        };
+
+        if contents.len() != entry.size {
+            return Err(invalid("file size does not match the central directory"));
+        }
+
+        Ok(Some(contents))
+    }
+}
+
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+
+/// Decompresses data compressed by deflate (RFC 1951). The output is limited
+/// to the size from the central directory, so a damaged or crafted archive
+/// can not make it grow without bounds.
+fn inflate(data: &[u8], size: usize) -> Result<Vec<u8>, io::Error> {
+    miniz_oxide::inflate::decompress_to_vec_with_limit(data, size)
+        .map_err(|error| match error.status {
+            TINFLStatus::HasMoreOutput => invalid("file size does not match the central directory"),
+            _ => invalid("invalid compressed data"),
+        })
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::io;
+
+    use crate::vm::exec::archive::Archive;
+
+
+    /// A jar with a deflated `Main.mvm`, a stored `pkg/Util.mvm` and the directory `pkg/`.
+    const JAR: [u8; 473] = [
+        0x50, 0x4b, 0x03, 0x04, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x70, 0x6b,
+        0x67, 0x2f, 0x50, 0x4b, 0x03, 0x04, 0x14, 0x00, 0x00, 0x00, 0x08, 0x00, 0xcb, 0xb9, 0x52, 0x5d,
+        0x29, 0xe6, 0xf3, 0xc9, 0x7f, 0x00, 0x00, 0x00, 0x91, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00,
+        0x4d, 0x61, 0x69, 0x6e, 0x2e, 0x6d, 0x76, 0x6d, 0x25, 0x8d, 0xc1, 0x0a, 0xc2, 0x30, 0x10, 0x05,
+        0xef, 0xfb, 0x15, 0x7b, 0xd4, 0x8b, 0xb4, 0x57, 0x6f, 0x35, 0x89, 0x74, 0xb1, 0x49, 0x4a, 0xb2,
+        0xf1, 0x5a, 0x8a, 0x82, 0x04, 0xab, 0x29, 0x1a, 0xfd, 0x7e, 0x13, 0xbc, 0x3d, 0x86, 0x61, 0x9e,
+        0x9e, 0xe3, 0x13, 0xb4, 0xe2, 0xde, 0x4a, 0x78, 0xe7, 0x39, 0xc7, 0x0b, 0x7e, 0x53, 0xbc, 0xe2,
+        0xa3, 0x70, 0xdc, 0x6c, 0xb1, 0x05, 0x12, 0xd6, 0x78, 0x9e, 0x1a, 0x20, 0xcf, 0xd6, 0xa9, 0x32,
+        0x96, 0x94, 0xd6, 0x3d, 0x12, 0x19, 0x81, 0x4d, 0x15, 0x06, 0xdb, 0xc9, 0x82, 0x0f, 0x34, 0x06,
+        0xdf, 0x63, 0x5b, 0xcc, 0xe3, 0x44, 0x42, 0x8f, 0x03, 0x63, 0x55, 0x81, 0xcc, 0xd9, 0x9e, 0x94,
+        0xe7, 0x8e, 0x49, 0xfc, 0xeb, 0xeb, 0xfd, 0xb6, 0x0b, 0x39, 0x2e, 0xf8, 0xfa, 0xd4, 0x17, 0x70,
+        0x8a, 0x83, 0x33, 0xa0, 0x8c, 0x84, 0x1f, 0x50, 0x4b, 0x03, 0x04, 0x14, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0xcb, 0xb9, 0x52, 0x5d, 0xdd, 0xa1, 0x58, 0xb4, 0x30, 0x00, 0x00, 0x00, 0x30, 0x00, 0x00,
+        0x00, 0x0c, 0x00, 0x00, 0x00, 0x70, 0x6b, 0x67, 0x2f, 0x55, 0x74, 0x69, 0x6c, 0x2e, 0x6d, 0x76,
+        0x6d, 0x70, 0x6b, 0x67, 0x2e, 0x55, 0x74, 0x69, 0x6c, 0x0a, 0x4d, 0x45, 0x54, 0x48, 0x4f, 0x44,
+        0x0a, 0x73, 0x74, 0x61, 0x74, 0x69, 0x63, 0x20, 0x76, 0x6f, 0x69, 0x64, 0x20, 0x72, 0x75, 0x6e,
+        0x20, 0x28, 0x29, 0x20, 0x30, 0x0a, 0x52, 0x45, 0x54, 0x55, 0x52, 0x4e, 0x0a, 0x45, 0x4e, 0x44,
+        0x0a, 0x50, 0x4b, 0x01, 0x02, 0x14, 0x03, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x01, 0x00, 0x00, 0x00, 0x00, 0x70,
+        0x6b, 0x67, 0x2f, 0x50, 0x4b, 0x01, 0x02, 0x14, 0x03, 0x14, 0x00, 0x00, 0x00, 0x08, 0x00, 0xcb,
+        0xb9, 0x52, 0x5d, 0x29, 0xe6, 0xf3, 0xc9, 0x7f, 0x00, 0x00, 0x00, 0x91, 0x00, 0x00, 0x00, 0x08,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x01, 0x22, 0x00, 0x00,
+        0x00, 0x4d, 0x61, 0x69, 0x6e, 0x2e, 0x6d, 0x76, 0x6d, 0x50, 0x4b, 0x01, 0x02, 0x14, 0x03, 0x14,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0xcb, 0xb9, 0x52, 0x5d, 0xdd, 0xa1, 0x58, 0xb4, 0x30, 0x00, 0x00,
+        0x00, 0x30, 0x00, 0x00, 0x00, 0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x80, 0x01, 0xc7, 0x00, 0x00, 0x00, 0x70, 0x6b, 0x67, 0x2f, 0x55, 0x74, 0x69, 0x6c, 0x2e,
+        0x6d, 0x76, 0x6d, 0x50, 0x4b, 0x05, 0x06, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x03, 0x00, 0xa2,
+        0x00, 0x00, 0x00, 0x21, 0x01, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+
+    #[test]
+    fn read() {
+        let archive = Archive::new(JAR.to_vec()).unwrap();
+
+        assert_eq!(archive.names().collect::<Vec<_>>(), vec!["Main.mvm", "pkg/Util.mvm"]);
+
+        let main = String::from_utf8(archive.read("Main.mvm").unwrap().unwrap()).unwrap();
+        assert!(main.starts_with("Main\nMETHOD\nstatic void main () 1\n"));
+        assert!(main.ends_with("INVOKESTATIC void pkg.Util run ()\nRETURN\nEND\n"));
+
+        let util = archive.read("pkg/Util.mvm").unwrap().unwrap();
+        assert_eq!(util, b"pkg.Util\nMETHOD\nstatic void run () 0\nRETURN\nEND\n");
+
+        assert!(archive.read("Missing.mvm").unwrap().is_none());
+        assert!(Archive::new(JAR[..100].to_vec()).is_err());
+    }
+
+
+    #[test]
+    fn size_limit() {
+        // the central directory entry of Main.mvm declares less than its 0x91 inflated bytes
+        let mut jar = JAR.to_vec();
+        let size = jar.windows(5)
+                      .enumerate()
+                      .filter(|(_, bytes)| bytes == &[0x7f, 0x00, 0x00, 0x00, 0x91])
+                      .map(|(offset, _)| offset + 4)
+                      .last()
+                      .unwrap();
+        jar[size] = 0x10;
+
+        let archive = Archive::new(jar).unwrap();
+        assert_eq!(archive.read("Main.mvm").unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
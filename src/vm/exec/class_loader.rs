@@ -1,32 +1,35 @@
 use std::convert::TryInto;
-use std::fs::File;
-use std::io::Read;
+use std::io;
 use std::path::PathBuf;
 
 use crate::vm::class::class::Class;
 use crate::vm::class::name::ClassName;
+use crate::vm::exec::archive::Archive;
+use crate::vm::exec::class_source::ClassSource;
 use crate::vm::exec::error::{ClassLoadError, ClassLoadErrorKind};
 use crate::vm::parse::parser::ClassFileParser;
+use crate::vm::verify::verifier::Verifier;
 
 
 /// A class loader which can search for
-/// class definitions in the filesystem and
-/// load them.
+/// class definitions in a class source,
+/// e.g. the directories and archives of a class path,
+/// and load them.
 pub struct ClassLoader {
-    paths: Vec<PathBuf>
+    source: Box<dyn ClassSource>,
 }
 
 
 impl ClassLoader {
-    /// Creates a new class loader with given class paths
-    pub fn new(paths: Vec<PathBuf>) -> Self {
+    /// Creates a new class loader searching the given source
+    pub fn new(source: impl ClassSource + 'static) -> Self {
         ClassLoader {
-            paths
+            source: Box::new(source)
         }
     }
 
     /// Loads class of the given name.
-    /// Searches in the defined class paths until it find the
+    /// Searches in the class source until it find the
     /// class file.
     pub fn load(&self, name: &ClassName) -> Result<Class, ClassLoadError> {
         match self.load_class(name) {
@@ -37,31 +40,70 @@ impl ClassLoader {
         }
     }
 
-    fn load_class(&self, name: &ClassName) -> Result<Class, ClassLoadErrorKind> {
-        let mut class_path: PathBuf = name.as_ref().split('.').collect();
-        class_path.set_extension("mvm");
+    /// Finds names of all the classes defined in the class source.
+    /// Files which names are not valid class names are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if a class path directory or archive can not be read.
+    pub fn class_names(&self) -> Result<Vec<ClassName>, io::Error> {
+        let mut names = self.source.class_names()?;
 
-        for path in self.paths.iter().cloned() {
-            let path: PathBuf = path;
-            let path = path.join(&class_path);
+        names.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+        names.dedup();
+        Ok(names)
+    }
 
-            if let Ok(mut file) = File::open(path) {
-                let mut contents = String::new();
-                file.read_to_string(&mut contents)?;
+    /// Finds names of all the classes defined in the class source which can be loaded
+    /// and have a main method, see `Class::main_method`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if a class path directory or archive can not be read.
+    pub fn main_classes(&self) -> Result<Vec<ClassName>, io::Error> {
+        Ok(self.class_names()?
+               .into_iter()
+               .filter(|name| match self.load(name) {
+                   Ok(class) => class.main_method().is_ok(),
+                   Err(_) => false,
+               })
+               .collect())
+    }
 
-                let class_info = ClassFileParser::new(&contents).parse()?;
-                let class: Class = class_info.try_into()?;
+    /// Returns the path of the class file of the given class in a class path directory.
+    /// Classes in archives or in memory have no class file.
+    pub fn class_file(&self, name: &ClassName) -> Option<PathBuf> {
+        self.source_file(name).filter(|file| !Archive::is_archive(file))
+    }
 
-                if class.name() != name {
-                    return Err(ClassLoadErrorKind::WrongName {
-                        name: class.name().clone()
-                    });
-                }
+    /// Returns the class path entry, a directory or an archive, the class is loaded from.
+    pub fn class_entry(&self, name: &ClassName) -> Option<PathBuf> {
+        self.source.entry(name)
+    }
 
-                return Ok(class);
-            }
+    /// Returns the file the class is read from, which is the class file in a directory
+    /// or the archive containing the class.
+    pub fn source_file(&self, name: &ClassName) -> Option<PathBuf> {
+        self.source.source_file(name)
+    }
+
+    fn load_class(&self, name: &ClassName) -> Result<Class, ClassLoadErrorKind> {
+        let contents = match self.source.read(name)? {
+            Some(contents) => contents,
+            None => return Err(ClassLoadErrorKind::ClassNotFound),
+        };
+
+        let class_info = ClassFileParser::new(&contents).parse()?;
+        let class: Class = class_info.try_into()?;
+
+        if class.name() != name {
+            return Err(ClassLoadErrorKind::WrongName {
+                name: class.name().clone()
+            });
         }
 
-        Err(ClassLoadErrorKind::ClassNotFound)
+        Verifier::new(&class).verify().map_err(Box::new)?;
+
+        Ok(class)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,359 @@
+//! Sources of class definitions searched by the class loader.
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::vm::class::name::ClassName;
+use crate::vm::exec::archive::Archive;
+
+
+/// A source of class files, e.g. a directory or an archive.
+pub trait ClassSource: Send + Sync {
+    /// Reads the source text of the class file of the class.
+    /// Returns `None` if the source does not contain the class.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the class file exists, but can not be read.
+    fn read(&self, name: &ClassName) -> Result<Option<String>, io::Error>;
+
+    /// Finds names of all the classes in the source.
+    /// Files which names are not valid class names are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the source can not be read.
+    fn class_names(&self) -> Result<Vec<ClassName>, io::Error>;
+
+    /// Returns true if the source contains the class.
+    fn contains(&self, name: &ClassName) -> bool {
+        matches!(self.read(name), Ok(Some(_)))
+    }
+
+    /// Returns the class path entry, a directory or an archive, containing the class.
+    /// Sources which are not on the filesystem have no entries.
+    fn entry(&self, _name: &ClassName) -> Option<PathBuf> {
+        None
+    }
+
+    /// Returns the file the class is read from, which is the class file in a directory
+    /// or the archive containing the class. Changes of the file make the loaded class stale.
+    fn source_file(&self, _name: &ClassName) -> Option<PathBuf> {
+        None
+    }
+}
+
+
+/// A directory containing class files in subdirectories given by their packages,
+/// e.g. the class `a.b.C` in the file `a/b/C.mvm`.
+pub struct DirectorySource {
+    dir: PathBuf,
+}
+
+
+impl DirectorySource {
+    pub fn new(dir: PathBuf) -> Self {
+        DirectorySource {
+            dir
+        }
+    }
+}
+
+
+impl ClassSource for DirectorySource {
+    fn read(&self, name: &ClassName) -> Result<Option<String>, io::Error> {
+        let mut file = match File::open(self.dir.join(class_file_path(name))) {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error),
+        };
+
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        Ok(Some(contents))
+    }
+
+    fn class_names(&self) -> Result<Vec<ClassName>, io::Error> {
+        let mut names = Vec::new();
+        find_class_names(&self.dir, &mut Vec::new(), &mut names)?;
+        Ok(names)
+    }
+
+    fn contains(&self, name: &ClassName) -> bool {
+        self.dir.join(class_file_path(name)).is_file()
+    }
+
+    fn entry(&self, name: &ClassName) -> Option<PathBuf> {
+        if self.contains(name) {
+            Some(self.dir.clone())
+        } else {
+            None
+        }
+    }
+
+    fn source_file(&self, name: &ClassName) -> Option<PathBuf> {
+        Some(self.dir.join(class_file_path(name))).filter(|path| path.is_file())
+    }
+}
+
+
+/// A zip or jar archive containing class files, laid out as in a directory.
+/// The archive is read once when the source is created, a changed archive
+/// is seen by a new source.
+pub struct ArchiveSource {
+    file: PathBuf,
+    archive: Result<Archive, io::Error>,
+}
+
+
+impl ArchiveSource {
+    pub fn new(file: PathBuf) -> Self {
+        let archive = Archive::open(&file);
+
+        ArchiveSource {
+            file,
+            archive,
+        }
+    }
+
+    /// Returns the archive, `None` if the file does not exist.
+    fn archive(&self) -> Result<Option<&Archive>, io::Error> {
+        match &self.archive {
+            Ok(archive) => Ok(Some(archive)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(io::Error::new(error.kind(), error.to_string())),
+        }
+    }
+
+    /// Returns the name of the archive entry of the class, archive entries are always separated by slashes.
+    fn entry_name(name: &ClassName) -> String {
+        format!("{}.mvm", name.as_ref().replace('.', "/"))
+    }
+}
+
+
+impl ClassSource for ArchiveSource {
+    fn read(&self, name: &ClassName) -> Result<Option<String>, io::Error> {
+        let archive = match self.archive()? {
+            Some(archive) => archive,
+            None => return Ok(None),
+        };
+
+        match archive.read(&Self::entry_name(name))? {
+            Some(contents) => {
+                let contents = String::from_utf8(contents).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+                Ok(Some(contents))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn class_names(&self) -> Result<Vec<ClassName>, io::Error> {
+        let archive = match &self.archive {
+            Ok(archive) => archive,
+            Err(error) => return Err(io::Error::new(error.kind(), error.to_string())),
+        };
+
+        Ok(archive.names()
+                  .filter_map(|entry_name| entry_name.strip_suffix(".mvm"))
+                  .filter_map(|class_path| ClassName::new(class_path.replace('/', ".")).ok())
+                  .collect())
+    }
+
+    fn contains(&self, name: &ClassName) -> bool {
+        let entry_name = Self::entry_name(name);
+        matches!(&self.archive, Ok(archive) if archive.names().any(|name| name == entry_name))
+    }
+
+    fn entry(&self, name: &ClassName) -> Option<PathBuf> {
+        self.source_file(name)
+    }
+
+    fn source_file(&self, name: &ClassName) -> Option<PathBuf> {
+        if self.contains(name) {
+            Some(self.file.clone())
+        } else {
+            None
+        }
+    }
+}
+
+
+/// Class files kept in memory, given by their source texts.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySource {
+    classes: HashMap<ClassName, String>,
+}
+
+
+impl MemorySource {
+    pub fn new(classes: HashMap<ClassName, String>) -> Self {
+        MemorySource {
+            classes
+        }
+    }
+
+    /// Adds the class file source of the class, replacing the previous one.
+    pub fn with_class(mut self, name: ClassName, source: impl Into<String>) -> Self {
+        self.classes.insert(name, source.into());
+        self
+    }
+}
+
+
+impl ClassSource for MemorySource {
+    fn read(&self, name: &ClassName) -> Result<Option<String>, io::Error> {
+        Ok(self.classes.get(name).cloned())
+    }
+
+    fn class_names(&self) -> Result<Vec<ClassName>, io::Error> {
+        Ok(self.classes.keys().cloned().collect())
+    }
+
+    fn contains(&self, name: &ClassName) -> bool {
+        self.classes.contains_key(name)
+    }
+}
+
+
+/// A chain of class sources searched in order, the first source containing
+/// a class defines it.
+#[derive(Default)]
+pub struct CompositeSource {
+    sources: Vec<Box<dyn ClassSource>>,
+}
+
+
+impl CompositeSource {
+    pub fn new(sources: Vec<Box<dyn ClassSource>>) -> Self {
+        CompositeSource {
+            sources
+        }
+    }
+
+    /// Creates the sources of the class path entries, archives for the files
+    /// with the zip or jar extension and directories otherwise.
+    pub fn class_path(paths: Vec<PathBuf>) -> Self {
+        let sources = paths.into_iter()
+                           .map(|path| -> Box<dyn ClassSource> {
+                               if Archive::is_archive(&path) {
+                                   Box::new(ArchiveSource::new(path))
+                               } else {
+                                   Box::new(DirectorySource::new(path))
+                               }
+                           })
+                           .collect();
+
+        CompositeSource::new(sources)
+    }
+
+    /// Appends the source searched after the current ones.
+    pub fn with_source(mut self, source: impl ClassSource + 'static) -> Self {
+        self.sources.push(Box::new(source));
+        self
+    }
+
+    fn find(&self, name: &ClassName) -> Option<&dyn ClassSource> {
+        self.sources.iter().map(|source| source.as_ref()).find(|source| source.contains(name))
+    }
+}
+
+
+impl ClassSource for CompositeSource {
+    fn read(&self, name: &ClassName) -> Result<Option<String>, io::Error> {
+        for source in &self.sources {
+            if let Some(contents) = source.read(name)? {
+                return Ok(Some(contents));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn class_names(&self) -> Result<Vec<ClassName>, io::Error> {
+        let mut names = Vec::new();
+
+        for source in &self.sources {
+            names.extend(source.class_names()?);
+        }
+
+        Ok(names)
+    }
+
+    fn contains(&self, name: &ClassName) -> bool {
+        self.find(name).is_some()
+    }
+
+    fn entry(&self, name: &ClassName) -> Option<PathBuf> {
+        self.find(name).and_then(|source| source.entry(name))
+    }
+
+    fn source_file(&self, name: &ClassName) -> Option<PathBuf> {
+        self.find(name).and_then(|source| source.source_file(name))
+    }
+}
+
+
+/// Returns the path of the class file relative to the class path entry.
+fn class_file_path(name: &ClassName) -> PathBuf {
+    let mut class_path: PathBuf = name.as_ref().split('.').collect();
+    class_path.set_extension("mvm");
+    class_path
+}
+
+
+/// Recursively searches the directory for class files and adds their names,
+/// given by the path relative to the class path, to `names`.
+fn find_class_names(dir: &Path, packages: &mut Vec<String>, names: &mut Vec<ClassName>) -> Result<(), io::Error> {
+    for entry in dir.read_dir()? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            if let Some(package) = path.file_name().and_then(|name| name.to_str()) {
+                packages.push(package.to_owned());
+                find_class_names(&path, packages, names)?;
+                packages.pop();
+            }
+        } else if path.extension() == Some(OsStr::new("mvm")) {
+            if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                let mut name = packages.clone();
+                name.push(stem.to_owned());
+
+                if let Ok(name) = ClassName::new(name.join(".")) {
+                    names.push(name);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::vm::class::name::ClassName;
+    use crate::vm::exec::class_source::{ClassSource, CompositeSource, MemorySource};
+
+
+    #[test]
+    fn composite() {
+        let a = ClassName::new("A").unwrap();
+        let b = ClassName::new("B").unwrap();
+
+        let source = CompositeSource::default()
+            .with_source(MemorySource::default().with_class(a.clone(), "A first"))
+            .with_source(MemorySource::default().with_class(a.clone(), "A second").with_class(b.clone(), "B"));
+
+        assert_eq!(source.read(&a).unwrap().as_deref(), Some("A first"));
+        assert_eq!(source.read(&b).unwrap().as_deref(), Some("B"));
+        assert_eq!(source.read(&ClassName::new("C").unwrap()).unwrap(), None);
+        assert_eq!(source.class_names().unwrap().len(), 3);
+        assert!(source.contains(&b));
+        assert_eq!(source.entry(&b), None);
+    }
+}
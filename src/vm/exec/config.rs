@@ -0,0 +1,122 @@
+use std::fmt;
+
+use crate::vm::memory::frame::Frame;
+
+
+/// A configuration of a virtual machine - the limits
+/// of the memory available to the executed program.
+#[derive(Debug, Clone)]
+pub struct VmConfig {
+    max_heap_objects: Option<usize>,
+    max_heap_bytes: Option<usize>,
+    max_frame_depth: usize,
+    max_operand_stack: usize,
+}
+
+
+impl VmConfig {
+    pub const DEFAULT_MAX_FRAME_DEPTH: usize = 1024;
+
+    /// Creates a new configuration with an unlimited heap, the default max frame depth
+    /// and the max operand stack size allowed by the verifier.
+    pub fn new() -> Self {
+        VmConfig {
+            max_heap_objects: None,
+            max_heap_bytes: None,
+            max_frame_depth: Self::DEFAULT_MAX_FRAME_DEPTH,
+            max_operand_stack: Frame::MAX_STACK,
+        }
+    }
+
+    /// Limits the number of objects allocated on the heap.
+    pub fn with_max_heap_objects(mut self, max: Option<usize>) -> Self {
+        self.max_heap_objects = max;
+        self
+    }
+
+    /// Limits the estimated size of the objects allocated on the heap, see `Instance::size`.
+    pub fn with_max_heap_bytes(mut self, max: Option<usize>) -> Self {
+        self.max_heap_bytes = max;
+        self
+    }
+
+    /// Limits the number of frames on the frame stack.
+    pub fn with_max_frame_depth(mut self, max: usize) -> Self {
+        self.max_frame_depth = max;
+        self
+    }
+
+    /// Limits the number of slots of the operand stack of every frame, long and double values take two.
+    /// The limit can not be raised above `Frame::MAX_STACK` checked by the verifier.
+    pub fn with_max_operand_stack(mut self, max: usize) -> Self {
+        self.max_operand_stack = max.min(Frame::MAX_STACK);
+        self
+    }
+
+    pub fn max_heap_objects(&self) -> Option<usize> {
+        self.max_heap_objects
+    }
+
+    pub fn max_heap_bytes(&self) -> Option<usize> {
+        self.max_heap_bytes
+    }
+
+    pub fn max_frame_depth(&self) -> usize {
+        self.max_frame_depth
+    }
+
+    pub fn max_operand_stack(&self) -> usize {
+        self.max_operand_stack
+    }
+}
+
+
+impl Default for VmConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/// A limit of the heap which was reached by an allocation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HeapLimit {
+    Objects(usize),
+    Bytes(usize),
+}
+
+
+impl fmt::Display for HeapLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeapLimit::Objects(max) => write!(f, "{} objects", max),
+            HeapLimit::Bytes(max) => write!(f, "{} bytes", max),
+        }
+    }
+}
+
+
+/// Allocation statistics of a single class.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Allocations {
+    count: usize,
+    bytes: usize,
+}
+
+
+impl Allocations {
+    /// Returns the number of allocated objects.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the estimated size of the allocated objects.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub(super) fn add(&mut self, bytes: usize) {
+        self.count += 1;
+        self.bytes += bytes;
+    }
+}
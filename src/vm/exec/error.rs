@@ -5,10 +5,12 @@ use thiserror::Error;
 use crate::vm::class::descriptor::ReturnDesc;
 use crate::vm::class::error::{ClassError, CodeError};
 use crate::vm::class::name::ClassName;
+use crate::vm::exec::config::HeapLimit;
 use crate::vm::memory::error::{FrameError, LocalsError, OperandStackError};
-use crate::vm::parse::error::{CreateClassError, ParseClassError};
+use crate::vm::parse::error::{CreateClassError, ParseClassErrors};
 use crate::vm::types::error::ValueError;
 use crate::vm::types::value::ValueType;
+use crate::vm::verify::error::VerifyError;
 
 
 #[derive(Error, Debug)]
@@ -57,6 +59,21 @@ pub enum ExecError {
         expected: ReturnDesc,
         called: ValueType,
     },
+    #[error("out of memory while creating an instance of {class_name}, the heap is limited to {limit}")]
+    OutOfMemory {
+        class_name: ClassName,
+        limit: HeapLimit,
+    },
+    #[error("frame stack overflow, the max depth is {max}")]
+    FrameStackOverflow {
+        max: usize
+    },
+    #[error("the execution was stopped")]
+    Stopped,
+    #[error("the program exited with status {status}")]
+    Exited {
+        status: i32
+    },
 }
 
 
@@ -83,10 +100,10 @@ pub enum ClassLoadErrorKind {
     WrongName {
         name: ClassName
     },
-    #[error(transparent)]
+    #[error("invalid class file\n{source}")]
     Parse {
         #[from]
-        source: ParseClassError
+        source: ParseClassErrors
     },
     #[error(transparent)]
     Create {
@@ -94,6 +111,11 @@ pub enum ClassLoadErrorKind {
         source: CreateClassError
     },
     #[error(transparent)]
+    Verify {
+        #[from]
+        source: Box<VerifyError>
+    },
+    #[error(transparent)]
     Io {
         #[from]
         source: io::Error
@@ -110,6 +132,6 @@ pub enum InterpreterError {
     #[error(transparent)]
     Parse {
         #[from]
-        source: ParseClassError
+        source: ParseClassErrors
     },
 }
\ No newline at end of file
@@ -0,0 +1,428 @@
+//! Export of the object graph of a virtual machine.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::vm::class::instance::{Instance, InstanceId};
+use crate::vm::class::name::ClassName;
+use crate::vm::class::signature::{FieldSig, MethodSig};
+use crate::vm::exec::vm::Vm;
+use crate::vm::memory::frame::Frame;
+use crate::vm::memory::locals::Slot;
+use crate::vm::types::reference::Reference;
+use crate::vm::types::value::Value;
+
+
+/// A snapshot of all the instances on the heap, their field values
+/// and the roots referencing them.
+#[derive(Debug, Clone)]
+pub struct HeapDump {
+    objects: Vec<HeapObject>,
+    roots: Vec<HeapRoot>,
+}
+
+
+/// An instance in the heap dump.
+#[derive(Debug, Clone)]
+pub struct HeapObject {
+    id: InstanceId,
+    class_name: ClassName,
+    fields: Vec<(FieldSig, Value)>,
+    is_root: bool,
+}
+
+
+/// A location outside of the heap which references an instance.
+#[derive(Debug, Clone)]
+pub struct HeapRoot {
+    kind: RootKind,
+    target: InstanceId,
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RootKind {
+    Static {
+        class_name: ClassName,
+        field: FieldSig,
+    },
+    Local {
+        frame: usize,
+        method: MethodSig,
+        index: usize,
+    },
+    Operand {
+        frame: usize,
+        method: MethodSig,
+        index: usize,
+    },
+}
+
+
+impl HeapDump {
+    /// Creates a dump of the current state of the virtual machine.
+    /// The state should not change while the dump is created,
+    /// so the virtual machine should be paused.
+    pub fn new(vm: &Vm) -> Self {
+        Self::with_frames(vm, &vm.frames().unwrap_or_default())
+    }
+
+    /// Creates a dump of the heap of the virtual machine with the given frames as roots,
+    /// for example a snapshot of the interpreter.
+    pub fn with_frames(vm: &Vm, frames: &[Arc<Frame>]) -> Self {
+        let mut roots = Vec::new();
+
+        let mut classes = vm.classes();
+        classes.sort_by_key(|class| class.name().to_string());
+
+        for class in &classes {
+            for field in class.fields().filter(|field| field.is_static()) {
+                let value = class.static_field_value(field.signature()).unwrap();
+
+                if let Some(target) = instance_id(&value) {
+                    let kind = RootKind::Static {
+                        class_name: class.name().clone(),
+                        field: field.signature().clone(),
+                    };
+                    roots.push(HeapRoot { kind, target });
+                }
+            }
+        }
+
+        for (frame_index, frame) in frames.iter().enumerate() {
+            let method = frame.method().signature();
+
+            for (index, slot) in frame.locals().values().iter().enumerate() {
+                if let Slot::Value(value) = slot {
+                    if let Some(target) = instance_id(value) {
+                        let kind = RootKind::Local { frame: frame_index, method: method.clone(), index };
+                        roots.push(HeapRoot { kind, target });
+                    }
+                }
+            }
+
+            for (index, value) in frame.stack().values().iter().enumerate() {
+                if let Some(target) = instance_id(value) {
+                    let kind = RootKind::Operand { frame: frame_index, method: method.clone(), index };
+                    roots.push(HeapRoot { kind, target });
+                }
+            }
+        }
+
+        let targets: HashSet<_> = roots.iter().map(|root| root.target).collect();
+
+        let mut instances = vm.instances();
+        instances.sort_by_key(Instance::id);
+
+        let objects = instances.iter()
+                               .map(|instance| HeapObject::new(instance, targets.contains(&instance.id())))
+                               .collect();
+
+        HeapDump { objects, roots }
+    }
+
+    /// Returns the instances ordered by their ids.
+    pub fn objects(&self) -> &[HeapObject] {
+        &self.objects
+    }
+
+    /// Returns the static fields, then the locals and operand stack values
+    /// of the frames (the first called first) which reference an instance.
+    pub fn roots(&self) -> &[HeapRoot] {
+        &self.roots
+    }
+
+    /// Writes the dump to the file, as DOT if the file has the `dot` or `gv` extension,
+    /// otherwise as JSON.
+    pub fn write(&self, path: &Path) -> Result<(), io::Error> {
+        let contents = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("dot") | Some("gv") => self.to_dot(),
+            _ => self.to_json(),
+        };
+
+        fs::write(path, contents)
+    }
+
+    /// Writes the dump as a JSON document.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+
+        json.push_str("{\n  \"objects\": [");
+
+        for (index, object) in self.objects.iter().enumerate() {
+            json.push_str(if index == 0 { "\n" } else { ",\n" });
+            write!(
+                json,
+                "    {{\"id\": {}, \"class\": {}, \"root\": {}, \"fields\": [",
+                json_string(&object.id.to_string()),
+                json_string(object.class_name.as_ref()),
+                object.is_root,
+            ).unwrap();
+
+            for (index, (field, value)) in object.fields.iter().enumerate() {
+                if index != 0 {
+                    json.push_str(", ");
+                }
+
+                let target = match instance_id(value) {
+                    None => "null".to_owned(),
+                    Some(target) => json_string(&target.to_string()),
+                };
+
+                write!(
+                    json,
+                    "{{\"name\": {}, \"type\": {}, \"value\": {}, \"ref\": {}}}",
+                    json_string(field.name().as_ref()),
+                    json_string(&field.type_desc().to_string()),
+                    json_string(&value.to_string()),
+                    target,
+                ).unwrap();
+            }
+
+            json.push_str("]}");
+        }
+
+        json.push_str("\n  ],\n  \"roots\": [");
+
+        for (index, root) in self.roots.iter().enumerate() {
+            json.push_str(if index == 0 { "\n    " } else { ",\n    " });
+
+            match &root.kind {
+                RootKind::Static { class_name, field } => write!(
+                    json,
+                    "{{\"kind\": \"static\", \"class\": {}, \"field\": {}",
+                    json_string(class_name.as_ref()),
+                    json_string(field.name().as_ref()),
+                ),
+                RootKind::Local { frame, method, index } => write!(
+                    json,
+                    "{{\"kind\": \"local\", \"frame\": {}, \"method\": {}, \"index\": {}",
+                    frame,
+                    json_string(&method.to_string()),
+                    index,
+                ),
+                RootKind::Operand { frame, method, index } => write!(
+                    json,
+                    "{{\"kind\": \"operand\", \"frame\": {}, \"method\": {}, \"index\": {}",
+                    frame,
+                    json_string(&method.to_string()),
+                    index,
+                ),
+            }.unwrap();
+
+            write!(json, ", \"target\": {}}}", json_string(&root.target.to_string())).unwrap();
+        }
+
+        json.push_str("\n  ]\n}\n");
+        json
+    }
+
+    /// Writes the dump as a Graphviz DOT digraph. Instances are records with
+    /// a port per field, roots are plain text nodes and the instances they reference are bold.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+
+        dot.push_str("digraph heap {\n");
+        dot.push_str("    node [shape=record, fontname=monospace];\n");
+
+        for object in &self.objects {
+            let mut label = format!("{{{}@{}", object.class_name, object.id);
+
+            for (index, (field, value)) in object.fields.iter().enumerate() {
+                write!(label, "|<f{}> {} {} = {}\\l", index, field.type_desc(), field.name(), dot_escape(&value.to_string())).unwrap();
+            }
+
+            label.push('}');
+
+            let style = if object.is_root { ", style=bold" } else { "" };
+            writeln!(dot, "    {} [label=\"{}\"{}];", object_node(object.id), label, style).unwrap();
+        }
+
+        for (index, root) in self.roots.iter().enumerate() {
+            let label = match &root.kind {
+                RootKind::Static { class_name, field } => format!("{}.{}", class_name, field.name()),
+                RootKind::Local { frame, method, index } => format!("#{} {} local {}", frame, method.name(), index),
+                RootKind::Operand { frame, method, index } => format!("#{} {} stack {}", frame, method.name(), index),
+            };
+
+            writeln!(dot, "    root{} [shape=plaintext, label=\"{}\"];", index, dot_escape(&label)).unwrap();
+            writeln!(dot, "    root{} -> {};", index, object_node(root.target)).unwrap();
+        }
+
+        for object in &self.objects {
+            for (index, (_, value)) in object.fields.iter().enumerate() {
+                if let Some(target) = instance_id(value) {
+                    writeln!(dot, "    {}:f{} -> {};", object_node(object.id), index, object_node(target)).unwrap();
+                }
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+
+impl HeapObject {
+    fn new(instance: &Instance, is_root: bool) -> Self {
+        let class = instance.class();
+
+        let fields = class.fields()
+                          .filter(|field| !field.is_static())
+                          .map(|field| {
+                              let signature = field.signature();
+                              (signature.clone(), class.instance_field_value(instance, signature).unwrap())
+                          })
+                          .collect();
+
+        HeapObject {
+            id: instance.id(),
+            class_name: class.name().clone(),
+            fields,
+            is_root,
+        }
+    }
+
+    pub fn id(&self) -> InstanceId {
+        self.id
+    }
+
+    pub fn class_name(&self) -> &ClassName {
+        &self.class_name
+    }
+
+    /// Returns the instance fields with their values in the order of declaration.
+    pub fn fields(&self) -> &[(FieldSig, Value)] {
+        &self.fields
+    }
+
+    /// Returns whether the instance is referenced by a root.
+    pub fn is_root(&self) -> bool {
+        self.is_root
+    }
+}
+
+
+impl HeapRoot {
+    pub fn kind(&self) -> &RootKind {
+        &self.kind
+    }
+
+    /// Returns the id of the referenced instance.
+    pub fn target(&self) -> InstanceId {
+        self.target
+    }
+}
+
+
+/// Returns the id of the instance referenced by the value if any.
+fn instance_id(value: &Value) -> Option<InstanceId> {
+    match value {
+        Value::Reference(Reference::Instance(instance)) => Some(instance.id()),
+        _ => None,
+    }
+}
+
+
+fn object_node(id: InstanceId) -> String {
+    format!("object{}", id)
+}
+
+
+/// Quotes and escapes the string for JSON.
+fn json_string(string: &str) -> String {
+    let mut json = String::with_capacity(string.len() + 2);
+    json.push('"');
+
+    for c in string.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+    json
+}
+
+
+/// Escapes the characters with a special meaning in DOT record labels.
+fn dot_escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+
+    for c in string.chars() {
+        if "{}|<>\"\\".contains(c) {
+            escaped.push('\\');
+        }
+
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::vm::class::name::ClassName;
+    use crate::vm::class::signature::MethodSig;
+    use crate::vm::exec::class_source::MemorySource;
+    use crate::vm::exec::heap_dump::HeapDump;
+    use crate::vm::exec::interpreter::Interpreter;
+    use crate::vm::exec::vm::Vm;
+
+
+    #[test]
+    fn dump() {
+        let source = MemorySource::default().with_class(ClassName::new("Node").unwrap(), "\
+Node
+FIELD
+static Node head
+FIELD
+Node next
+FIELD
+int value
+METHOD
+static void main () 0
+NEW Node
+DUP
+NEW Node
+PUTFIELD Node Node next
+PUTSTATIC Node Node head
+RETURN
+END
+");
+
+        let vm = Arc::new(Vm::new(source));
+        let result = Interpreter::new(vm.clone()).run(&ClassName::new("Node").unwrap(), &MethodSig::main());
+        result.unwrap();
+
+        let dump = HeapDump::new(&vm);
+        let (head, next) = (&dump.objects()[0], &dump.objects()[1]);
+
+        assert_eq!(dump.objects().len(), 2);
+        assert_eq!(head.id().to_string(), "1");
+        assert_eq!(next.id().to_string(), "2");
+        assert!(head.is_root());
+        assert!(!next.is_root());
+        assert_eq!(dump.roots().len(), 1);
+        assert_eq!(dump.roots()[0].target(), head.id());
+
+        let json = dump.to_json();
+        assert!(json.contains(&format!("{{\"name\": \"next\", \"type\": \"Node\", \"value\": \"Node@{}\", \"ref\": \"{}\"}}", next.id(), next.id())));
+        assert!(json.contains(&format!("{{\"kind\": \"static\", \"class\": \"Node\", \"field\": \"head\", \"target\": \"{}\"}}", head.id())));
+
+        let dot = dump.to_dot();
+        assert!(dot.contains(&format!("object{}:f0 -> object{};", head.id(), next.id())));
+        assert!(dot.contains(&format!("root0 -> object{};", head.id())));
+    }
+}
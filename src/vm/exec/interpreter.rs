@@ -0,0 +1,1161 @@
+//! A fast interpreter for running programs without stepping.
+//!
+//! Unlike `Thread`, which executes shared frames one instruction at a time so they
+//! can be inspected between the steps, the interpreter owns its frames and runs
+//! code decoded in advance, so the hot path does not take any lock.
+//! The state of the frames is exposed on demand as a snapshot.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use crate::vm::bytecode::instruction::{Instruction, Ldc2Arg, LdcArg};
+use crate::vm::class::class::Class;
+use crate::vm::class::descriptor::ReturnDesc;
+use crate::vm::class::method::Method;
+use crate::vm::class::name::ClassName;
+use crate::vm::class::signature::{FieldSig, MethodSig};
+use crate::vm::class::symbolic::{FieldRef, MethodRef};
+use crate::vm::exec::error::ExecError;
+use crate::vm::exec::native;
+use crate::vm::exec::native::Native;
+use crate::vm::exec::vm::Vm;
+use crate::vm::memory::error::{FrameError, LocalsError, OperandStackError};
+use crate::vm::memory::frame::Frame;
+use crate::vm::memory::locals::Slot;
+use crate::vm::types::double::Double;
+use crate::vm::types::error::ValueError;
+use crate::vm::types::float::Float;
+use crate::vm::types::int::Int;
+use crate::vm::types::long::Long;
+use crate::vm::types::reference::Reference;
+use crate::vm::types::value::{Value, ValueType};
+
+
+/// An interpreter running a method to its end on the current thread.
+pub struct Interpreter {
+    runtime: Arc<Vm>,
+    methods: Vec<DecodedMethod>,
+    decoded: HashMap<*const Method, usize>,
+    frames: Vec<OwnedFrame>,
+    executed: u64,
+}
+
+
+impl Interpreter {
+    /// Mask of the number of executed instructions, requests from other threads
+    /// are checked every 1024 instructions.
+    const POLL_MASK: u64 = 1024 - 1;
+
+    /// Creates a new interpreter using the given virtual machine as a runtime.
+    pub fn new(runtime: Arc<Vm>) -> Self {
+        Interpreter {
+            runtime,
+            methods: Vec::new(),
+            decoded: HashMap::new(),
+            frames: Vec::new(),
+            executed: 0,
+        }
+    }
+
+    /// Runs the static method without parameters of the given class
+    /// until it returns, the program exits or the virtual machine is requested to stop.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ExecError` if the execution fails, the frames are left in the state
+    /// of the failure and can be inspected by `snapshot`.
+    pub fn run(&mut self, class_name: &ClassName, method_sig: &MethodSig) -> Result<(), ExecError> {
+        match self.invoke(class_name, method_sig, Vec::new()) {
+            Ok(_) | Err(ExecError::Stopped) | Err(ExecError::Exited { .. }) => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Runs the main method of the given class, see `Class::main_method`, with the arguments
+    /// until it returns, the program exits or the virtual machine is requested to stop.
+    /// The returned `int` is recorded as the exit status of the program.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ExecError` if there is no main method, the arguments do not match
+    /// its parameters or the execution fails.
+    pub fn run_main(&mut self, class_name: &ClassName, args: Vec<Value>) -> Result<(), ExecError> {
+        let class = self.runtime.resolve_class(class_name)?;
+        let method_sig = class.main_method()?.signature().clone();
+
+        match self.invoke(class_name, &method_sig, args) {
+            Ok(Some(Value::Int(status))) => self.runtime.exit(status.into()),
+            Ok(_) | Err(ExecError::Stopped) | Err(ExecError::Exited { .. }) => {}
+            Err(error) => return Err(error),
+        }
+
+        Ok(())
+    }
+
+    /// Runs the static method of the given class with the arguments until it returns.
+    /// Returns the returned value, `None` for void methods.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ExecError::Stopped` if the virtual machine was requested to stop,
+    /// `ExecError::Exited` if the program called `System.exit`. Returns other `ExecError`s
+    /// if the arguments do not match the parameters of the method or the execution fails,
+    /// the frames are then left in the state of the failure and can be inspected by `snapshot`.
+    pub fn invoke(&mut self, class_name: &ClassName, method_sig: &MethodSig, args: Vec<Value>) -> Result<Option<Value>, ExecError> {
+        let class = self.runtime.resolve_class(class_name)?;
+        let method = class.static_method(method_sig)?.clone();
+
+        let frame = Frame::new_from_args(class.clone(), method.clone(), args, self.runtime.config().max_operand_stack())?;
+
+        let index = self.decode(class, method);
+        let mut owned_frame = OwnedFrame::new(index, &self.methods[index], self.runtime.config().max_operand_stack());
+        owned_frame.locals = frame.locals().values();
+        self.frames.push(owned_frame);
+        self.execute()
+    }
+
+    /// Returns the number of instructions executed so far.
+    pub fn executed(&self) -> u64 {
+        self.executed
+    }
+
+    /// Creates copies of the frames in their current state, the first called first.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ExecError` if a frame does not fit in the limits of the virtual machine,
+    /// e.g. its configuration changed during the execution.
+    pub fn snapshot(&self) -> Result<Vec<Arc<Frame>>, ExecError> {
+        self.frames.iter()
+            .map(|frame| {
+                let method = &self.methods[frame.method];
+                Frame::with_state(
+                    method.class.clone(),
+                    method.method.clone(),
+                    frame.pc,
+                    frame.locals.clone(),
+                    frame.stack.values().to_vec(),
+                    self.runtime.config().max_operand_stack(),
+                ).map(Arc::new).map_err(ExecError::from)
+            })
+            .collect()
+    }
+
+    /// Executes the frames until the first one returns, returns its returned value.
+    fn execute(&mut self) -> Result<Option<Value>, ExecError> {
+        loop {
+            if self.executed & Self::POLL_MASK == 0 && self.poll() {
+                return Err(ExecError::Stopped);
+            }
+
+            let Interpreter { runtime, methods, frames, .. } = self;
+
+            let frame = match frames.last_mut() {
+                None => return Ok(None),
+                Some(frame) => frame,
+            };
+
+            let step = step(runtime, &methods[frame.method], frame)?;
+
+            match step {
+                Step::Next => {}
+                Step::Resolve => {
+                    self.resolve()?;
+                    continue;
+                }
+                Step::Call(index) => self.call(index)?,
+                Step::Exit(status) => {
+                    self.frames.clear();
+                    self.executed += 1;
+                    return Err(ExecError::Exited { status });
+                }
+                Step::Return(value) => {
+                    self.frames.pop();
+
+                    match (value, self.frames.last_mut()) {
+                        (Some(value), Some(caller)) => caller.stack.push(value)?,
+                        (value, None) => {
+                            self.executed += 1;
+                            return Ok(value);
+                        }
+                        (None, Some(_)) => {}
+                    }
+                }
+            }
+
+            self.executed += 1;
+        }
+    }
+
+    /// Handles the requests from other threads, returns true if the execution should stop.
+    /// The stop request is consumed, so a later execution runs normally.
+    fn poll(&self) -> bool {
+        if self.runtime.take_snapshot_request() {
+            if let Ok(frames) = self.snapshot() {
+                self.runtime.publish_snapshot(frames);
+                self.runtime.notify_update();
+            }
+        }
+
+        self.runtime.take_stop_request()
+    }
+
+    /// Pushes a frame of the decoded method, popping the arguments from the current frame.
+    fn call(&mut self, index: usize) -> Result<(), ExecError> {
+        let max_depth = self.runtime.config().max_frame_depth();
+
+        if self.frames.len() >= max_depth {
+            return Err(ExecError::FrameStackOverflow { max: max_depth });
+        }
+
+        let method = &self.methods[index];
+        let caller = self.frames.last_mut().unwrap();
+        let params = method.method.signature().params_desc().type_descs();
+        let count = params.len() + if method.method.is_static() { 0 } else { 1 };
+
+        if caller.stack.len() < count {
+            return Err(OperandStackError::Underflow.into());
+        }
+
+        // the arguments are checked before the caller is changed, so it stays consistent on a failure
+        let args = &caller.stack.values()[caller.stack.len() - count..];
+        let (this, args) = if method.method.is_static() {
+            (None, args)
+        } else {
+            (Some(Reference::try_from(args[0].clone())?), &args[1..])
+        };
+
+        for (type_desc, value) in params.iter().zip(args) {
+            if !type_desc.is_assignable_with(value) {
+                return Err(FrameError::IncompatibleArgumentType {
+                    expected: type_desc.clone(),
+                    got: value.value_type(),
+                }.into());
+            }
+        }
+
+        let mut frame = OwnedFrame::new(index, method, self.runtime.config().max_operand_stack());
+        let mut local = 0;
+
+        if let Some(this) = this {
+            frame.locals[local] = Slot::Value(this.into());
+            local += 1;
+        }
+
+        for value in caller.stack.split_off(caller.stack.len() - count).into_iter().skip(local) {
+            let size = category_size(&value);
+            frame.locals[local] = Slot::Value(value);
+            local += size;
+        }
+
+        caller.pc += 1;
+        self.frames.push(frame);
+        Ok(())
+    }
+
+    /// Resolves the symbolic reference of the current instruction and replaces
+    /// the instruction with its resolved form.
+    fn resolve(&mut self) -> Result<(), ExecError> {
+        let frame = self.frames.last().unwrap();
+        let (method, pc) = (frame.method, frame.pc as usize);
+
+        let resolved = match &self.methods[method].ops[pc] {
+            Op::GetStatic(field_ref) => {
+                let class = self.runtime.resolve_class(field_ref.class_name())?;
+                let offset = class.static_field_offset(field_ref.signature())?;
+                Op::GetStaticAt(class, offset)
+            }
+            Op::PutStatic(field_ref) => {
+                let class = self.runtime.resolve_class(field_ref.class_name())?;
+                let offset = class.static_field_offset(field_ref.signature())?;
+                Op::PutStaticAt(class, offset, field_ref.signature().clone())
+            }
+            Op::GetField(field_ref) => {
+                let class = self.runtime.resolve_class(field_ref.class_name())?;
+                let offset = class.instance_field_offset(field_ref.signature())?;
+                Op::GetFieldAt(class, offset)
+            }
+            Op::PutField(field_ref) => {
+                let class = self.runtime.resolve_class(field_ref.class_name())?;
+                let offset = class.instance_field_offset(field_ref.signature())?;
+                Op::PutFieldAt(class, offset, field_ref.signature().clone())
+            }
+            Op::Invoke(method_ref, true) if Native::find(method_ref).is_some() => {
+                Op::Native(Native::find(method_ref).unwrap())
+            }
+            Op::Invoke(method_ref, is_static) => {
+                let (method_ref, is_static) = (method_ref.clone(), *is_static);
+                let class = self.runtime.resolve_class(method_ref.class_name())?;
+
+                let callee = if is_static {
+                    class.static_method(method_ref.signature())?.clone()
+                } else {
+                    class.instance_method(method_ref.signature())?.clone()
+                };
+
+                Op::Call(self.decode(class, callee))
+            }
+            Op::New(class_name) => Op::NewOf(self.runtime.resolve_class(class_name)?),
+            _ => unreachable!("only symbolic references are resolved"),
+        };
+
+        self.methods[method].ops[pc] = resolved;
+        Ok(())
+    }
+
+    /// Decodes the method if it was not decoded yet and returns its index.
+    fn decode(&mut self, class: Arc<Class>, method: Arc<Method>) -> usize {
+        if let Some(index) = self.decoded.get(&Arc::as_ptr(&method)) {
+            return *index;
+        }
+
+        let ops = method.code()
+                        .instructions()
+                        .enumerate()
+                        .map(|(pc, instruction)| Op::decode(instruction, pc as isize))
+                        .collect();
+
+        let index = self.methods.len();
+        self.decoded.insert(Arc::as_ptr(&method), index);
+        self.methods.push(DecodedMethod { class, method, ops });
+        index
+    }
+}
+
+
+/// A method with its instructions decoded for the interpreter.
+struct DecodedMethod {
+    class: Arc<Class>,
+    method: Arc<Method>,
+    ops: Vec<Op>,
+}
+
+
+/// A frame owned by the interpreter.
+struct OwnedFrame {
+    method: usize,
+    pc: isize,
+    locals: Vec<Slot>,
+    stack: ValueStack,
+}
+
+
+impl OwnedFrame {
+    fn new(index: usize, method: &DecodedMethod, max_stack: usize) -> Self {
+        OwnedFrame {
+            method: index,
+            pc: 0,
+            locals: vec![Slot::Undefined; method.method.code().locals_size()],
+            stack: ValueStack::new(max_stack),
+        }
+    }
+}
+
+
+/// An operand stack of a frame owned by the interpreter.
+/// Like `OperandStack`, it limits the number of slots, long and double values
+/// take two, and a push over the limit leaves the stack unchanged.
+struct ValueStack {
+    values: Vec<Value>,
+    slots: usize,
+    max_slots: usize,
+}
+
+
+impl ValueStack {
+    fn new(max_slots: usize) -> Self {
+        ValueStack {
+            values: Vec::with_capacity(16),
+            slots: 0,
+            max_slots,
+        }
+    }
+
+    /// Returns the values, the top of the stack last.
+    fn values(&self) -> &[Value] {
+        &self.values
+    }
+
+    /// Returns the number of values.
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn push(&mut self, value: Value) -> Result<(), OperandStackError> {
+        let size = category_size(&value);
+
+        if self.slots + size > self.max_slots {
+            return Err(OperandStackError::Overflow);
+        }
+
+        self.slots += size;
+        self.values.push(value);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Value, OperandStackError> {
+        let value = self.values.pop().ok_or(OperandStackError::Underflow)?;
+        self.slots -= category_size(&value);
+        Ok(value)
+    }
+
+    /// Removes the values from the given index to the top and returns them.
+    fn split_off(&mut self, at: usize) -> Vec<Value> {
+        let values = self.values.split_off(at);
+        self.slots -= values.iter().map(category_size).sum::<usize>();
+        values
+    }
+
+    /// Removes the values above the given number of values.
+    fn truncate(&mut self, len: usize) {
+        self.slots -= self.values.drain(len..).map(|value| category_size(&value)).sum::<usize>();
+    }
+
+    /// Inserts the values at the given index, below the values above it.
+    fn insert(&mut self, at: usize, values: Vec<Value>) -> Result<(), OperandStackError> {
+        let size = values.iter().map(category_size).sum::<usize>();
+
+        if self.slots + size > self.max_slots {
+            return Err(OperandStackError::Overflow);
+        }
+
+        self.slots += size;
+        self.values.splice(at..at, values);
+        Ok(())
+    }
+
+    /// Swaps the two values on the top.
+    fn swap(&mut self) {
+        let len = self.values.len();
+        self.values.swap(len - 1, len - 2);
+    }
+}
+
+
+/// A result of a single step.
+enum Step {
+    Next,
+    Resolve,
+    Call(usize),
+    Return(Option<Value>),
+    Exit(i32),
+}
+
+
+/// A condition of a conditional branch.
+#[derive(Debug, Copy, Clone)]
+enum Cond {
+    Eq,
+    Ne,
+    Lt,
+    Ge,
+    Gt,
+    Le,
+}
+
+
+impl Cond {
+    fn holds(self, value1: &Int, value2: &Int) -> bool {
+        match self {
+            Cond::Eq => value1.eq(value2),
+            Cond::Ne => !value1.eq(value2),
+            Cond::Lt => value1.lt(value2),
+            Cond::Ge => value1.ge(value2),
+            Cond::Gt => value1.gt(value2),
+            Cond::Le => value1.le(value2),
+        }
+    }
+}
+
+
+/// A decoded instruction.
+/// Constants are converted to values, local indexes are explicit, branch targets
+/// are absolute and symbolic references are replaced by their resolved form
+/// on their first execution.
+#[derive(Debug, Clone)]
+enum Op {
+    Nop,
+    Push(Value),
+    Load(usize),
+    Store(usize),
+    Inc(usize, i8),
+    Pop(usize),
+    Dup(usize, usize),
+    Swap,
+    Compute(Instruction),
+    If(Cond, isize),
+    IfCmp(Cond, isize),
+    IfRefEq(bool, isize),
+    IfNull(bool, isize),
+    Goto(isize),
+    Return,
+    ReturnValue,
+    GetStatic(FieldRef),
+    PutStatic(FieldRef),
+    GetField(FieldRef),
+    PutField(FieldRef),
+    Invoke(MethodRef, bool),
+    Native(Native),
+    New(ClassName),
+    GetStaticAt(Arc<Class>, usize),
+    PutStaticAt(Arc<Class>, usize, FieldSig),
+    GetFieldAt(Arc<Class>, usize),
+    PutFieldAt(Arc<Class>, usize, FieldSig),
+    Call(usize),
+    NewOf(Arc<Class>),
+}
+
+
+impl Op {
+    fn decode(instruction: &Instruction, pc: isize) -> Op {
+        let target = |offset: &i16| pc + *offset as isize;
+
+        match instruction {
+            Instruction::NOP => Op::Nop,
+
+            Instruction::ACONST_NULL => Op::Push(Reference::null().into()),
+            Instruction::ICONST_M1 => Op::Push(Int::new(-1).into()),
+            Instruction::ICONST_0 => Op::Push(Int::new(0).into()),
+            Instruction::ICONST_1 => Op::Push(Int::new(1).into()),
+            Instruction::ICONST_2 => Op::Push(Int::new(2).into()),
+            Instruction::ICONST_3 => Op::Push(Int::new(3).into()),
+            Instruction::ICONST_4 => Op::Push(Int::new(4).into()),
+            Instruction::ICONST_5 => Op::Push(Int::new(5).into()),
+            Instruction::LCONST_0 => Op::Push(Long::new(0).into()),
+            Instruction::LCONST_1 => Op::Push(Long::new(1).into()),
+            Instruction::FCONST_0 => Op::Push(Float::new(0.0).into()),
+            Instruction::FCONST_1 => Op::Push(Float::new(1.0).into()),
+            Instruction::FCONST_2 => Op::Push(Float::new(2.0).into()),
+            Instruction::DCONST_0 => Op::Push(Double::new(0.0).into()),
+            Instruction::DCONST_1 => Op::Push(Double::new(1.0).into()),
+            Instruction::BIPUSH(byte) => Op::Push(Int::new(*byte as i32).into()),
+            Instruction::SIPUSH(short) => Op::Push(Int::new(*short as i32).into()),
+            Instruction::LDC(value) | Instruction::LDC_W(value) => match value {
+                LdcArg::Int(int) => Op::Push(Int::new(*int).into()),
+                LdcArg::Float(float) => Op::Push(Float::new(*float).into()),
+            },
+            Instruction::LDC2_W(value) => match value {
+                Ldc2Arg::Long(long) => Op::Push(Long::new(*long).into()),
+                Ldc2Arg::Double(double) => Op::Push(Double::new(*double).into()),
+            },
+
+            Instruction::ILOAD(index) | Instruction::LLOAD(index) | Instruction::FLOAD(index) |
+            Instruction::DLOAD(index) | Instruction::ALOAD(index) => Op::Load(*index as usize),
+            Instruction::ILOAD_0 | Instruction::LLOAD_0 | Instruction::FLOAD_0 |
+            Instruction::DLOAD_0 | Instruction::ALOAD_0 => Op::Load(0),
+            Instruction::ILOAD_1 | Instruction::LLOAD_1 | Instruction::FLOAD_1 |
+            Instruction::DLOAD_1 | Instruction::ALOAD_1 => Op::Load(1),
+            Instruction::ILOAD_2 | Instruction::LLOAD_2 | Instruction::FLOAD_2 |
+            Instruction::DLOAD_2 | Instruction::ALOAD_2 => Op::Load(2),
+            Instruction::ILOAD_3 | Instruction::LLOAD_3 | Instruction::FLOAD_3 |
+            Instruction::DLOAD_3 | Instruction::ALOAD_3 => Op::Load(3),
+
+            Instruction::ISTORE(index) | Instruction::LSTORE(index) | Instruction::FSTORE(index) |
+            Instruction::DSTORE(index) | Instruction::ASTORE(index) => Op::Store(*index as usize),
+            Instruction::ISTORE_0 | Instruction::LSTORE_0 | Instruction::FSTORE_0 |
+            Instruction::DSTORE_0 | Instruction::ASTORE_0 => Op::Store(0),
+            Instruction::ISTORE_1 | Instruction::LSTORE_1 | Instruction::FSTORE_1 |
+            Instruction::DSTORE_1 | Instruction::ASTORE_1 => Op::Store(1),
+            Instruction::ISTORE_2 | Instruction::LSTORE_2 | Instruction::FSTORE_2 |
+            Instruction::DSTORE_2 | Instruction::ASTORE_2 => Op::Store(2),
+            Instruction::ISTORE_3 | Instruction::LSTORE_3 | Instruction::FSTORE_3 |
+            Instruction::DSTORE_3 | Instruction::ASTORE_3 => Op::Store(3),
+            Instruction::IINC(index, value) => Op::Inc(*index as usize, *value),
+
+            Instruction::POP => Op::Pop(1),
+            Instruction::POP2 => Op::Pop(2),
+            Instruction::DUP => Op::Dup(1, 0),
+            Instruction::DUP_X1 => Op::Dup(1, 1),
+            Instruction::DUP_X2 => Op::Dup(1, 2),
+            Instruction::DUP2 => Op::Dup(2, 0),
+            Instruction::DUP2_X1 => Op::Dup(2, 1),
+            Instruction::DUP2_X2 => Op::Dup(2, 2),
+            Instruction::SWAP => Op::Swap,
+
+            Instruction::IFEQ(offset) => Op::If(Cond::Eq, target(offset)),
+            Instruction::IFNE(offset) => Op::If(Cond::Ne, target(offset)),
+            Instruction::IFLT(offset) => Op::If(Cond::Lt, target(offset)),
+            Instruction::IFGE(offset) => Op::If(Cond::Ge, target(offset)),
+            Instruction::IFGT(offset) => Op::If(Cond::Gt, target(offset)),
+            Instruction::IFLE(offset) => Op::If(Cond::Le, target(offset)),
+            Instruction::IF_ICMPEQ(offset) => Op::IfCmp(Cond::Eq, target(offset)),
+            Instruction::IF_ICMPNE(offset) => Op::IfCmp(Cond::Ne, target(offset)),
+            Instruction::IF_ICMPLT(offset) => Op::IfCmp(Cond::Lt, target(offset)),
+            Instruction::IF_ICMPGE(offset) => Op::IfCmp(Cond::Ge, target(offset)),
+            Instruction::IF_ICMPGT(offset) => Op::IfCmp(Cond::Gt, target(offset)),
+            Instruction::IF_ICMPLE(offset) => Op::IfCmp(Cond::Le, target(offset)),
+            Instruction::IF_ACMPEQ(offset) => Op::IfRefEq(true, target(offset)),
+            Instruction::IF_ACMPNE(offset) => Op::IfRefEq(false, target(offset)),
+            Instruction::IFNULL(offset) => Op::IfNull(true, target(offset)),
+            Instruction::IFNONNULL(offset) => Op::IfNull(false, target(offset)),
+            Instruction::GOTO(offset) => Op::Goto(target(offset)),
+
+            Instruction::IRETURN | Instruction::LRETURN | Instruction::FRETURN |
+            Instruction::DRETURN | Instruction::ARETURN => Op::ReturnValue,
+            Instruction::RETURN => Op::Return,
+
+            Instruction::GETSTATIC(field_ref) => Op::GetStatic(field_ref.clone()),
+            Instruction::PUTSTATIC(field_ref) => Op::PutStatic(field_ref.clone()),
+            Instruction::GETFIELD(field_ref) => Op::GetField(field_ref.clone()),
+            Instruction::PUTFIELD(field_ref) => Op::PutField(field_ref.clone()),
+            Instruction::INVOKEVIRTUAL(method_ref) => Op::Invoke(method_ref.clone(), false),
+            Instruction::INVOKESTATIC(method_ref) => Op::Invoke(method_ref.clone(), true),
+            Instruction::NEW(class_name) => Op::New(class_name.clone()),
+
+            instruction => Op::Compute(instruction.clone()),
+        }
+    }
+}
+
+
+/// Executes the current instruction of the frame.
+fn step(runtime: &Vm, method: &DecodedMethod, frame: &mut OwnedFrame) -> Result<Step, ExecError> {
+    let op = match usize::try_from(frame.pc).ok().and_then(|pc| method.ops.get(pc)) {
+        Some(op) => op,
+        None => return Err(method.method.code().instruction(frame.pc).unwrap_err().into()),
+    };
+
+    let stack = &mut frame.stack;
+
+    match op {
+        Op::Nop => {}
+        Op::Push(value) => stack.push(value.clone())?,
+        Op::Load(index) => {
+            match frame.locals.get(*index) {
+                None => return Err(LocalsError::IndexOutOfBounds { index: *index, size: frame.locals.len() }.into()),
+                Some(Slot::Undefined) => return Err(LocalsError::InvalidIndex.into()),
+                Some(Slot::Value(value)) => stack.push(value.clone())?,
+            }
+        }
+        Op::Store(index) => {
+            let value = pop_value(stack)?;
+            store(&mut frame.locals, *index, value)?;
+        }
+        Op::Inc(index, value) => {
+            let int = match frame.locals.get(*index) {
+                Some(Slot::Value(local)) => Int::try_from(local.clone())?,
+                Some(Slot::Undefined) => return Err(LocalsError::InvalidIndex.into()),
+                None => return Err(LocalsError::IndexOutOfBounds { index: *index, size: frame.locals.len() }.into()),
+            };
+            frame.locals[*index] = Slot::Value(int.inc(*value).into());
+        }
+        Op::Pop(size) => {
+            let count = count_values(stack.values(), 0, *size)?;
+            stack.truncate(stack.len() - count);
+        }
+        Op::Dup(dup, skip) => {
+            let dup_count = count_values(stack.values(), 0, *dup)?;
+            let skip_count = count_values(stack.values(), dup_count, *skip)?;
+            let len = stack.len();
+            let copies: Vec<_> = stack.values()[(len - dup_count)..].to_vec();
+            stack.insert(len - dup_count - skip_count, copies)?;
+        }
+        Op::Swap => {
+            let count = count_values(stack.values(), 0, 2)?;
+
+            if count != 2 {
+                return Err(OperandStackError::InvalidType.into());
+            }
+
+            stack.swap();
+        }
+        Op::Compute(instruction) => compute(instruction, stack)?,
+        Op::If(cond, target) => {
+            let value = pop::<Int>(stack)?;
+            return Ok(branch(frame, cond.holds(&value, &Int::new(0)), *target));
+        }
+        Op::IfCmp(cond, target) => {
+            let value2 = pop::<Int>(stack)?;
+            let value1 = pop::<Int>(stack)?;
+            return Ok(branch(frame, cond.holds(&value1, &value2), *target));
+        }
+        Op::IfRefEq(equal, target) => {
+            let value2 = pop::<Reference>(stack)?;
+            let value1 = pop::<Reference>(stack)?;
+            return Ok(branch(frame, value1.eq(&value2) == *equal, *target));
+        }
+        Op::IfNull(null, target) => {
+            let value = pop::<Reference>(stack)?;
+            return Ok(branch(frame, value.is_null() == *null, *target));
+        }
+        Op::Goto(target) => {
+            frame.pc = *target;
+            return Ok(Step::Next);
+        }
+        Op::Return => {
+            let expected = method.method.signature().return_desc();
+
+            if !expected.is_void() {
+                return Err(ExecError::InvalidReturnType {
+                    expected: expected.clone(),
+                    called: ValueType::Void,
+                });
+            }
+
+            return Ok(Step::Return(None));
+        }
+        Op::ReturnValue => {
+            let value = pop_value(stack)?;
+            let expected = method.method.signature().return_desc();
+
+            match expected {
+                ReturnDesc::NonVoid(type_desc) if type_desc.is_assignable_with(&value) => {}
+                _ => return Err(ExecError::InvalidReturnType {
+                    expected: expected.clone(),
+                    called: value.value_type(),
+                }),
+            }
+
+            return Ok(Step::Return(Some(value)));
+        }
+        Op::GetStatic(_) | Op::PutStatic(_) | Op::GetField(_) | Op::PutField(_) |
+        Op::Invoke(_, _) | Op::New(_) => return Ok(Step::Resolve),
+        Op::GetStaticAt(class, offset) => stack.push(class.static_field_value_at(*offset))?,
+        Op::PutStaticAt(class, offset, signature) => {
+            let value = pop_value(stack)?;
+            class.set_static_field_value_at(*offset, signature, value)?;
+        }
+        Op::GetFieldAt(class, offset) => {
+            let instance = pop::<Reference>(stack)?.into_instance()?;
+            stack.push(class.instance_field_value_at(&instance, *offset)?)?;
+        }
+        Op::PutFieldAt(class, offset, signature) => {
+            let value = pop_value(stack)?;
+            let instance = pop::<Reference>(stack)?.into_instance()?;
+            class.set_instance_field_value_at(&instance, *offset, signature, value)?;
+        }
+        Op::Call(index) => return Ok(Step::Call(*index)),
+        Op::Native(Native::Exit) => {
+            let status = pop::<Int>(stack)?.into();
+            runtime.exit(status);
+            return Ok(Step::Exit(status));
+        }
+        Op::Native(Native::IdentityHashCode) => {
+            let reference = pop::<Reference>(stack)?;
+            stack.push(native::identity_hash_code(&reference).into())?;
+        }
+        Op::NewOf(class) => {
+            let instance = runtime.create_instance(class.clone())?;
+            stack.push(Reference::new(instance).into())?;
+        }
+    }
+
+    frame.pc += 1;
+    Ok(Step::Next)
+}
+
+
+/// Executes arithmetic, conversion and comparison instructions, which only work with the operand stack.
+fn compute(instruction: &Instruction, stack: &mut ValueStack) -> Result<(), ExecError> {
+    match instruction {
+        Instruction::IADD => binary(stack, |a: Int, b: Int| Ok(a.add(&b))),
+        Instruction::LADD => binary(stack, |a: Long, b: Long| Ok(a.add(&b))),
+        Instruction::FADD => binary(stack, |a: Float, b: Float| Ok(a.add(&b))),
+        Instruction::DADD => binary(stack, |a: Double, b: Double| Ok(a.add(&b))),
+        Instruction::ISUB => binary(stack, |a: Int, b: Int| Ok(a.sub(&b))),
+        Instruction::LSUB => binary(stack, |a: Long, b: Long| Ok(a.sub(&b))),
+        Instruction::FSUB => binary(stack, |a: Float, b: Float| Ok(a.sub(&b))),
+        Instruction::DSUB => binary(stack, |a: Double, b: Double| Ok(a.sub(&b))),
+        Instruction::IMUL => binary(stack, |a: Int, b: Int| Ok(a.mul(&b))),
+        Instruction::LMUL => binary(stack, |a: Long, b: Long| Ok(a.mul(&b))),
+        Instruction::FMUL => binary(stack, |a: Float, b: Float| Ok(a.mul(&b))),
+        Instruction::DMUL => binary(stack, |a: Double, b: Double| Ok(a.mul(&b))),
+        Instruction::IDIV => binary(stack, |a: Int, b: Int| a.div(&b)),
+        Instruction::LDIV => binary(stack, |a: Long, b: Long| a.div(&b)),
+        Instruction::FDIV => binary(stack, |a: Float, b: Float| Ok(a.div(&b))),
+        Instruction::DDIV => binary(stack, |a: Double, b: Double| Ok(a.div(&b))),
+        Instruction::IREM => binary(stack, |a: Int, b: Int| Ok(a.rem(&b))),
+        Instruction::LREM => binary(stack, |a: Long, b: Long| Ok(a.rem(&b))),
+        Instruction::FREM => binary(stack, |a: Float, b: Float| Ok(a.rem(&b))),
+        Instruction::DREM => binary(stack, |a: Double, b: Double| Ok(a.rem(&b))),
+        Instruction::INEG => unary(stack, |a: Int| a.neg()),
+        Instruction::LNEG => unary(stack, |a: Long| a.neg()),
+        Instruction::FNEG => unary(stack, |a: Float| a.neg()),
+        Instruction::DNEG => unary(stack, |a: Double| a.neg()),
+        Instruction::ISHL => shift(stack, |a: Int, s| a.shl(&s)),
+        Instruction::LSHL => shift(stack, |a: Long, s| a.shl(&s)),
+        Instruction::ISHR => shift(stack, |a: Int, s| a.shr(&s)),
+        Instruction::LSHR => shift(stack, |a: Long, s| a.shr(&s)),
+        Instruction::IUSHR => shift(stack, |a: Int, s| a.ushr(&s)),
+        Instruction::LUSHR => shift(stack, |a: Long, s| a.ushr(&s)),
+        Instruction::IAND => binary(stack, |a: Int, b: Int| Ok(a.and(&b))),
+        Instruction::LAND => binary(stack, |a: Long, b: Long| Ok(a.and(&b))),
+        Instruction::IOR => binary(stack, |a: Int, b: Int| Ok(a.or(&b))),
+        Instruction::LOR => binary(stack, |a: Long, b: Long| Ok(a.or(&b))),
+        Instruction::IXOR => binary(stack, |a: Int, b: Int| Ok(a.xor(&b))),
+        Instruction::LXOR => binary(stack, |a: Long, b: Long| Ok(a.xor(&b))),
+
+        Instruction::I2L => unary(stack, |a: Int| a.to_long()),
+        Instruction::I2F => unary(stack, |a: Int| a.to_float()),
+        Instruction::I2D => unary(stack, |a: Int| a.to_double()),
+        Instruction::L2I => unary(stack, |a: Long| a.to_int()),
+        Instruction::L2F => unary(stack, |a: Long| a.to_float()),
+        Instruction::L2D => unary(stack, |a: Long| a.to_double()),
+        Instruction::F2I => unary(stack, |a: Float| a.to_int()),
+        Instruction::F2L => unary(stack, |a: Float| a.to_long()),
+        Instruction::F2D => unary(stack, |a: Float| a.to_double()),
+        Instruction::D2I => unary(stack, |a: Double| a.to_int()),
+        Instruction::D2L => unary(stack, |a: Double| a.to_long()),
+        Instruction::D2F => unary(stack, |a: Double| a.to_float()),
+
+        Instruction::LCMP => binary(stack, |a: Long, b: Long| Ok(a.cmp(&b))),
+        Instruction::FCMPL => binary(stack, |a: Float, b: Float| Ok(a.cmpl(&b))),
+        Instruction::FCMPG => binary(stack, |a: Float, b: Float| Ok(a.cmpg(&b))),
+        Instruction::DCMPL => binary(stack, |a: Double, b: Double| Ok(a.cmpl(&b))),
+        Instruction::DCMPG => binary(stack, |a: Double, b: Double| Ok(a.cmpg(&b))),
+
+        instruction => unreachable!("{} is not a computational instruction", instruction.mnemonic()),
+    }
+}
+
+
+fn branch(frame: &mut OwnedFrame, condition: bool, target: isize) -> Step {
+    if condition {
+        frame.pc = target;
+    } else {
+        frame.pc += 1;
+    }
+
+    Step::Next
+}
+
+
+fn pop_value(stack: &mut ValueStack) -> Result<Value, OperandStackError> {
+    stack.pop()
+}
+
+
+fn pop<T>(stack: &mut ValueStack) -> Result<T, ExecError>
+          where T: TryFrom<Value, Error=ValueError> {
+    Ok(T::try_from(pop_value(stack)?)?)
+}
+
+
+fn unary<T, R, F>(stack: &mut ValueStack, op: F) -> Result<(), ExecError>
+                  where T: TryFrom<Value, Error=ValueError>,
+                        R: Into<Value>,
+                        F: FnOnce(T) -> R {
+    let value = pop::<T>(stack)?;
+    stack.push(op(value).into())?;
+    Ok(())
+}
+
+
+fn binary<T, R, F>(stack: &mut ValueStack, op: F) -> Result<(), ExecError>
+                   where T: TryFrom<Value, Error=ValueError>,
+                         R: Into<Value>,
+                         F: FnOnce(T, T) -> Result<R, ValueError> {
+    let value2 = pop::<T>(stack)?;
+    let value1 = pop::<T>(stack)?;
+    stack.push(op(value1, value2)?.into())?;
+    Ok(())
+}
+
+
+fn shift<T, F>(stack: &mut ValueStack, op: F) -> Result<(), ExecError>
+               where T: TryFrom<Value, Error=ValueError> + Into<Value>,
+                     F: FnOnce(T, Int) -> T {
+    let shift = pop::<Int>(stack)?;
+    let value = pop::<T>(stack)?;
+    stack.push(op(value, shift).into())?;
+    Ok(())
+}
+
+
+/// Returns the number of values from the top, after skipping `skip` values,
+/// which have the given total category size.
+fn count_values(stack: &[Value], skip: usize, size: usize) -> Result<usize, OperandStackError> {
+    let mut count = 0;
+    let mut total = 0;
+
+    while total < size {
+        let index = stack.len().checked_sub(skip + count + 1).ok_or(OperandStackError::Underflow)?;
+        total += category_size(&stack[index]);
+        count += 1;
+    }
+
+    if total != size {
+        return Err(OperandStackError::InvalidType);
+    }
+
+    Ok(count)
+}
+
+
+/// Stores the value into the locals the same way as `Locals::store_value`.
+fn store(locals: &mut [Slot], index: usize, value: Value) -> Result<(), LocalsError> {
+    let size = category_size(&value);
+
+    if index >= locals.len() {
+        return Err(LocalsError::IndexOutOfBounds { index, size: locals.len() });
+    }
+
+    if index + size > locals.len() {
+        return Err(LocalsError::InvalidIndex);
+    }
+
+    if index != 0 {
+        if let (Slot::Undefined, Slot::Value(previous)) = (&locals[index], &locals[index - 1]) {
+            if category_size(previous) == 2 {
+                locals[index - 1] = Slot::Undefined;
+            }
+        }
+    }
+
+    if size == 2 {
+        locals[index + 1] = Slot::Undefined;
+    }
+
+    locals[index] = Slot::Value(value);
+    Ok(())
+}
+
+
+/// Returns the category size of the value without creating its value type.
+fn category_size(value: &Value) -> usize {
+    match value {
+        Value::Long(_) | Value::Double(_) => 2,
+        _ => 1,
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::sync::mpsc::channel;
+
+    use crate::vm::class::descriptor::{ReturnDesc, TypeDesc};
+    use crate::vm::class::name::{ClassName, FieldName, MethodName};
+    use crate::vm::class::signature::{FieldSig, MethodSig};
+    use crate::vm::exec::class_source::MemorySource;
+    use crate::vm::exec::config::VmConfig;
+    use crate::vm::exec::error::ExecError;
+    use crate::vm::exec::interpreter::Interpreter;
+    use crate::vm::exec::stack_trace::StackTrace;
+    use crate::vm::exec::thread::Thread;
+    use crate::vm::exec::vm::Vm;
+    use crate::vm::memory::error::{FrameError, OperandStackError};
+    use crate::vm::types::int::Int;
+    use crate::vm::types::long::Long;
+
+
+    #[test]
+    fn run() {
+        let source = MemorySource::default().with_class(ClassName::new("Test").unwrap(), "\
+Test
+FIELD
+static long total
+METHOD
+static long add (long,int) 3
+LLOAD_0
+ILOAD_2
+I2L
+LADD
+LRETURN
+END
+METHOD
+static void main () 1
+BIPUSH 10
+ISTORE_0
+loop:
+GETSTATIC long Test total
+ILOAD_0
+DUP
+ISTORE_0
+INVOKESTATIC long Test add (long,int)
+PUTSTATIC long Test total
+IINC 0 -1
+ILOAD_0
+IFNE loop
+RETURN
+END
+");
+
+        let vm = Arc::new(Vm::new(source));
+        let mut interpreter = Interpreter::new(vm.clone());
+        let class_name = ClassName::new("Test").unwrap();
+        let result = interpreter.run(&class_name, &MethodSig::main());
+        result.unwrap();
+
+        let total = FieldSig::new(TypeDesc::Long, FieldName::new("total").unwrap());
+        let class = vm.resolve_class(&class_name).unwrap();
+        assert_eq!(class.static_field_value(&total).unwrap().to_string(), "55");
+        assert_eq!(interpreter.executed(), 2 + 10 * 14 + 1);
+        assert!(interpreter.snapshot().unwrap().is_empty());
+    }
+
+
+    #[test]
+    fn invoke() {
+        let class_name = ClassName::new("Fib").unwrap();
+        let source = MemorySource::default().with_class(class_name.clone(), "\
+Fib
+METHOD
+static int fib (int) 1
+ILOAD_0
+ICONST_2
+IF_ICMPLT base
+ILOAD_0
+ICONST_1
+ISUB
+INVOKESTATIC int Fib fib (int)
+ILOAD_0
+ICONST_2
+ISUB
+INVOKESTATIC int Fib fib (int)
+IADD
+IRETURN
+base:
+ILOAD_0
+IRETURN
+END
+");
+
+        let vm = Arc::new(Vm::new(source));
+        let fib = MethodSig::new(
+            ReturnDesc::NonVoid(TypeDesc::Int),
+            MethodName::new("fib").unwrap(),
+            vec![TypeDesc::Int].into_iter().collect(),
+        ).unwrap();
+
+        let result = vm.invoke(&class_name, &fib, vec![Int::new(10).into()]).unwrap();
+        assert_eq!(result.map(|value| value.to_string()).as_deref(), Some("55"));
+
+        let error = vm.invoke(&class_name, &fib, Vec::new()).unwrap_err();
+        assert!(matches!(error, ExecError::Frame { source: FrameError::WrongArgumentCount { expected: 1, got: 0 } }));
+
+        let error = vm.invoke(&class_name, &fib, vec![Long::new(10).into()]).unwrap_err();
+        assert!(matches!(error, ExecError::Frame { .. }));
+    }
+
+    #[test]
+    fn exit_status() {
+        let class_name = ClassName::new("Exit").unwrap();
+        let source = MemorySource::default().with_class(class_name.clone(), "\
+Exit
+METHOD
+static int main (int) 1
+ILOAD_0
+IFEQ exit
+ILOAD_0
+IRETURN
+exit:
+BIPUSH 3
+INVOKESTATIC void Exit stop (int)
+ICONST_0
+IRETURN
+END
+METHOD
+static void stop (int) 1
+ILOAD_0
+INVOKESTATIC void System exit (int)
+RETURN
+END
+");
+
+        let vm = Arc::new(Vm::new(source));
+        Interpreter::new(vm.clone()).run_main(&class_name, vec![Int::new(7).into()]).unwrap();
+        assert_eq!(vm.exit_status(), Some(7));
+
+        let mut interpreter = Interpreter::new(vm.clone());
+        interpreter.run_main(&class_name, vec![Int::new(0).into()]).unwrap();
+        assert_eq!(vm.exit_status(), Some(3));
+        assert!(interpreter.snapshot().unwrap().is_empty());
+
+        let stop = MethodSig::new(
+            ReturnDesc::Void,
+            MethodName::new("stop").unwrap(),
+            vec![TypeDesc::Int].into_iter().collect(),
+        ).unwrap();
+
+        // exiting and stopping are told apart from returning nothing
+        let error = vm.invoke(&class_name, &stop, vec![Int::new(5).into()]).unwrap_err();
+        assert!(matches!(error, ExecError::Exited { status: 5 }));
+
+        vm.stop();
+        let error = vm.invoke(&class_name, &stop, vec![Int::new(6).into()]).unwrap_err();
+        assert!(matches!(error, ExecError::Stopped));
+
+        // the stop request is consumed by the stopped execution
+        let error = vm.invoke(&class_name, &stop, vec![Int::new(7).into()]).unwrap_err();
+        assert!(matches!(error, ExecError::Exited { status: 7 }));
+    }
+
+
+    #[test]
+    fn identity_hash() {
+        let class_name = ClassName::new("Hash").unwrap();
+        let source = MemorySource::default().with_class(class_name.clone(), "\
+Hash
+METHOD
+static int hashes () 0
+NEW Hash
+INVOKESTATIC int System identityHashCode (Hash)
+NEW Hash
+INVOKESTATIC int System identityHashCode (Hash)
+SWAP
+ISUB
+ACONST_NULL
+INVOKESTATIC int System identityHashCode (Hash)
+IADD
+IRETURN
+END
+");
+
+        let vm = Arc::new(Vm::new(source));
+        let hashes = MethodSig::new(
+            ReturnDesc::NonVoid(TypeDesc::Int),
+            MethodName::new("hashes").unwrap(),
+            Vec::new().into_iter().collect(),
+        ).unwrap();
+
+        // the instances are numbered in the order of allocation
+        let result = vm.invoke(&class_name, &hashes, Vec::new()).unwrap();
+        assert_eq!(result.map(|value| value.to_string()).as_deref(), Some("1"));
+    }
+
+
+    #[test]
+    fn stack_slots() {
+        let class_name = ClassName::new("Wide").unwrap();
+        let source = MemorySource::default().with_class(class_name.clone(), "\
+Wide
+METHOD
+static void main () 0
+LCONST_0
+LCONST_0
+LCONST_0
+ICONST_1
+ICONST_0
+IDIV
+POP
+RETURN
+END
+");
+        let config = VmConfig::new().with_max_operand_stack(5);
+        let vm = Arc::new(Vm::with_config(source, config));
+
+        // the limit counts slots, so the third long overflows before the division
+        let mut interpreter = Interpreter::new(vm.clone());
+        let error = interpreter.run(&class_name, &MethodSig::main()).unwrap_err();
+        let trace = StackTrace::capture(&interpreter.snapshot().unwrap());
+        assert!(matches!(error, ExecError::OperandStack { source: OperandStackError::Overflow }));
+        assert_eq!(trace.elements()[0].pc(), 2);
+
+        // the stepped thread fails at the same instruction
+        let (tx, rx) = channel();
+        vm.set_error_callback(Some(Box::new(move |error, trace| tx.send((error, trace)).unwrap())));
+
+        let thread = Thread::new(vm.clone(), class_name, Vec::new());
+        for _ in 0..8 {
+            thread.next_step();
+        }
+        thread.join();
+
+        let (error, trace) = rx.recv().unwrap();
+        assert!(matches!(error, ExecError::OperandStack { source: OperandStackError::Overflow }));
+        assert_eq!(trace.elements()[0].pc(), 2);
+    }
+}
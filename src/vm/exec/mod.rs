@@ -1,5 +1,12 @@
 
 pub mod error;
+pub mod config;
 pub mod vm;
 pub mod class_loader;
-pub mod thread;
\ No newline at end of file
+pub mod class_source;
+pub mod archive;
+pub mod thread;
+pub mod interpreter;
+pub mod native;
+pub mod heap_dump;
+pub mod stack_trace;
@@ -0,0 +1,43 @@
+//! Native methods implemented by the virtual machine instead of a class file.
+
+use crate::vm::class::descriptor::{ReturnDesc, TypeDesc};
+use crate::vm::class::symbolic::MethodRef;
+use crate::vm::types::int::Int;
+use crate::vm::types::reference::Reference;
+
+
+/// A native static method.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Native {
+    /// `static void exit (int)` of the class `System` ends the program
+    /// with the given exit status.
+    Exit,
+    /// `static int identityHashCode (C)` of the class `System` returns the identity hash
+    /// of the instance, see `Instance::identity_hash`, or 0 for `null`.
+    IdentityHashCode,
+}
+
+
+impl Native {
+    /// Returns the native method invoked by the static method reference, if any.
+    pub fn find(method_ref: &MethodRef) -> Option<Native> {
+        let signature = method_ref.signature();
+
+        match (method_ref.class_name().as_ref(), signature.name().as_ref()) {
+            ("System", "exit") if signature.return_desc() == &ReturnDesc::Void
+                && signature.params_desc().type_descs() == &[TypeDesc::Int] => Some(Native::Exit),
+            ("System", "identityHashCode") if signature.return_desc() == &ReturnDesc::NonVoid(TypeDesc::Int)
+                && matches!(signature.params_desc().type_descs().as_slice(), [TypeDesc::Reference(_)]) => Some(Native::IdentityHashCode),
+            _ => None,
+        }
+    }
+}
+
+
+/// Returns the identity hash of the referenced instance, 0 for `null`.
+pub fn identity_hash_code(reference: &Reference) -> Int {
+    match reference {
+        Reference::Null => Int::new(0),
+        Reference::Instance(instance) => Int::new(instance.identity_hash()),
+    }
+}
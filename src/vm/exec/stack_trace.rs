@@ -0,0 +1,194 @@
+//! Stack traces of failed threads.
+
+use std::fmt;
+use std::fmt::Display;
+use std::sync::Arc;
+
+use crate::vm::class::name::ClassName;
+use crate::vm::class::signature::MethodSig;
+use crate::vm::memory::frame::Frame;
+
+
+/// The frames of a thread at the point of a failure, the innermost first.
+#[derive(Debug, Clone, Default)]
+pub struct StackTrace {
+    elements: Vec<StackTraceElement>,
+}
+
+
+/// A frame in the stack trace.
+#[derive(Debug, Clone)]
+pub struct StackTraceElement {
+    class_name: ClassName,
+    signature: MethodSig,
+    pc: isize,
+    instruction: Option<String>,
+    source_location: Option<String>,
+}
+
+
+impl StackTrace {
+    /// Captures the stack trace of the frames given in the order of a frame stack,
+    /// the first called first.
+    ///
+    /// The pc of a calling frame is already past the invoke instruction,
+    /// so the outer frames are reported at the call site.
+    pub fn capture(frames: &[Arc<Frame>]) -> Self {
+        let elements = frames.iter()
+            .rev()
+            .enumerate()
+            .map(|(depth, frame)| {
+                let pc = if depth == 0 { frame.pc() } else { frame.pc() - 1 };
+                let code = frame.method().code();
+                let instruction = if pc >= 0 {
+                    code.instruction_str(pc as usize)
+                        .ok()
+                        .map(|instruction| instruction.trim_end().to_owned())
+                } else {
+                    None
+                };
+                let source_location = frame.class().source_file()
+                    .and_then(|file| code.source_line(pc).map(|line| format!("{}:{}", file, line)));
+
+                StackTraceElement {
+                    class_name: frame.class().name().clone(),
+                    signature: frame.method().signature().clone(),
+                    pc,
+                    instruction,
+                    source_location,
+                }
+            })
+            .collect();
+
+        StackTrace { elements }
+    }
+
+    /// Returns the frames, the innermost first.
+    pub fn elements(&self) -> &[StackTraceElement] {
+        &self.elements
+    }
+
+    /// Returns true if there are no frames, e.g. the thread failed before calling the main method.
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+}
+
+
+impl Display for StackTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for element in &self.elements {
+            writeln!(f, "    at {}", element)?;
+        }
+
+        Ok(())
+    }
+}
+
+
+impl StackTraceElement {
+    /// Returns the name of the class of the method.
+    pub fn class_name(&self) -> &ClassName {
+        &self.class_name
+    }
+
+    /// Returns the signature of the method.
+    pub fn signature(&self) -> &MethodSig {
+        &self.signature
+    }
+
+    /// Returns the pc of the frame.
+    pub fn pc(&self) -> isize {
+        self.pc
+    }
+
+    /// Returns the textual representation of the instruction at the pc,
+    /// `None` if the pc is out of the code.
+    pub fn instruction(&self) -> Option<&str> {
+        self.instruction.as_deref()
+    }
+
+    /// Returns the location in the Java source, e.g. `Circle.java:12`, if known.
+    pub fn source_location(&self) -> Option<&str> {
+        self.source_location.as_deref()
+    }
+}
+
+
+impl Display for StackTraceElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}.{} ({}), pc {}",
+               self.signature.return_desc(),
+               self.class_name,
+               self.signature.name(),
+               self.signature.params_desc(),
+               self.pc)?;
+
+        if let Some(instruction) = &self.instruction {
+            write!(f, ": {}", instruction)?;
+        }
+
+        if let Some(location) = &self.source_location {
+            write!(f, " ({})", location)?;
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::vm::class::name::ClassName;
+    use crate::vm::class::signature::MethodSig;
+    use crate::vm::exec::class_source::MemorySource;
+    use crate::vm::exec::interpreter::Interpreter;
+    use crate::vm::exec::stack_trace::StackTrace;
+    use crate::vm::exec::vm::Vm;
+
+
+    #[test]
+    fn capture() {
+        let source = MemorySource::default().with_class(ClassName::new("Div").unwrap(), "\
+Div
+SOURCE Div.java
+METHOD
+static void main () 0
+LINE 3
+ICONST_0
+INVOKESTATIC int Div divide (int)
+POP
+RETURN
+END
+METHOD
+static int divide (int) 1
+LINE 7
+ICONST_1
+ILOAD_0
+IDIV
+IRETURN
+END
+");
+
+        let vm = Arc::new(Vm::new(source));
+        let mut interpreter = Interpreter::new(vm);
+        let result = interpreter.run(&ClassName::new("Div").unwrap(), &MethodSig::main());
+        result.expect_err("division by zero should fail");
+
+        let trace = StackTrace::capture(&interpreter.snapshot().unwrap());
+        let elements = trace.elements();
+
+        assert_eq!(elements.len(), 2);
+        assert_eq!(elements[0].signature().name().to_string(), "divide");
+        assert_eq!(elements[0].pc(), 2);
+        assert_eq!(elements[0].instruction(), Some("IDIV"));
+        assert_eq!(elements[0].source_location(), Some("Div.java:7"));
+        assert_eq!(elements[1].signature().name().to_string(), "main");
+        assert_eq!(elements[1].pc(), 1);
+        assert_eq!(elements[1].instruction(), Some("INVOKESTATIC    int Div divide (int)"));
+        assert_eq!(elements[1].source_location(), Some("Div.java:3"));
+        assert_eq!(trace.to_string().lines().next(), Some("    at int Div.divide (int), pc 2: IDIV (Div.java:7)"));
+    }
+}
@@ -3,11 +3,14 @@ use std::sync::mpsc::{channel, Receiver, Sender};
 use std::thread;
 use std::thread::JoinHandle;
 
+use crate::vm::bytecode::instruction::Instruction;
 use crate::vm::class::name::ClassName;
-use crate::vm::class::signature::MethodSig;
+use crate::vm::exec::error::ExecError;
+use crate::vm::exec::stack_trace::StackTrace;
 use crate::vm::exec::vm::Vm;
 use crate::vm::memory::frame::Frame;
 use crate::vm::memory::frame_stack::FrameStack;
+use crate::vm::types::value::Value;
 
 
 /// An internal command enum for notifying
@@ -22,7 +25,8 @@ enum ThreadCmd {
 /// It runs a system thread.
 /// It can be controlled by calling the next and cancel method.
 pub struct Thread {
-    start_method: (ClassName, MethodSig),
+    main_class: ClassName,
+    args: Mutex<Vec<Value>>,
     runtime: Arc<Vm>,
     stack: FrameStack,
     join_handle: Mutex<Option<JoinHandle<()>>>,
@@ -32,12 +36,14 @@ pub struct Thread {
 
 
 impl Thread {
-    pub fn new(runtime: Arc<Vm>, class_name: ClassName, method_sig: MethodSig) -> Arc<Self> {
+    /// Creates a thread running the main method of the class with the arguments.
+    pub fn new(runtime: Arc<Vm>, main_class: ClassName, args: Vec<Value>) -> Arc<Self> {
         let (tx, rx) = channel();
 
         let thread = Arc::new(Thread {
             runtime,
-            start_method: (class_name, method_sig),
+            main_class,
+            args: Mutex::new(args),
             stack: FrameStack::new(),
             join_handle: Mutex::new(None),
             cmd_rx: Mutex::new(rx),
@@ -55,6 +61,25 @@ impl Thread {
         thread
     }
 
+    /// Creates a thread executing nothing on its own, with the frame on its stack.
+    /// Instructions are executed on the frame by `evaluate`.
+    pub fn detached(runtime: Arc<Vm>, frame: Frame) -> Arc<Self> {
+        let (tx, rx) = channel();
+
+        let thread = Arc::new(Thread {
+            runtime,
+            main_class: frame.class().name().clone(),
+            args: Mutex::new(Vec::new()),
+            stack: FrameStack::new(),
+            join_handle: Mutex::new(None),
+            cmd_rx: Mutex::new(rx),
+            cmd_tx: Mutex::new(tx),
+        });
+
+        thread.stack.push(frame);
+        thread
+    }
+
     pub fn next_step(&self) {
         self.cmd_tx.lock().unwrap().send(ThreadCmd::NextStep).unwrap();
     }
@@ -75,53 +100,39 @@ impl Thread {
     }
 
     fn run(&self) {
-        let (class_name, method_sig) = self.start_method.clone();
-
-        let class = match self.runtime.resolve_class(&class_name) {
-            Ok(class) => class,
-            Err(error) => {
-                self.runtime.notify_error(error.into());
-                return;
-            }
-        };
+        if let Err(error) = self.execute() {
+            let trace = StackTrace::capture(&self.stack.frames());
+            self.runtime.notify_error(error, trace);
+        }
 
-        let method = match class.static_method(&method_sig) {
-            Ok(class) => class.clone(),
-            Err(error) => {
-                self.runtime.notify_error(error.into());
-                return;
-            }
-        };
+        self.runtime.notify_end();
+    }
 
-        assert!(method.is_static());
-        assert!(method.signature().params_desc().is_empty());
-        assert!(method.signature().return_desc().is_void());
+    /// Executes the main method until it returns or the thread is stopped.
+    fn execute(&self) -> Result<(), ExecError> {
+        let class = self.runtime.resolve_class(&self.main_class)?;
+        let method = class.main_method()?.clone();
 
-        let frame = Frame::new(class.clone(), method.clone());
+        let args = std::mem::take(&mut *self.args.lock().unwrap());
+        let frame = Frame::new_from_args(class.clone(), method, args, self.runtime.config().max_operand_stack())?;
 
         self.stack.push(frame);
 
         loop {
-            let instruction = match self.stack.current() {
+            let (method, pc) = match self.stack.current() {
                 None => {
                     // start method returned
                     break;
                 }
                 Some(frame) => {
                     self.runtime.notify_update();
-                    match frame.method().code().instruction(frame.pc()) {
-                        Ok(instruction) => {
-                            instruction
-                        }
-                        Err(error) => {
-                            // probably pc out of bounds
-                            self.runtime.notify_error(error.into());
-                            break;
-                        }
-                    }
+                    (frame.method().clone(), frame.pc())
                 }
             };
 
+            // error is probably pc out of bounds
+            let instruction = method.code().instruction(pc)?;
+
             match self.cmd_rx.lock().unwrap().recv() {
                 Ok(ThreadCmd::NextStep) => {}
                 Ok(ThreadCmd::Stop) => {
@@ -132,18 +143,48 @@ impl Thread {
                 }
             }
 
-            if let Err(error) = instruction.execute(&self) {
-                // error while executing instruction
-                self.runtime.notify_error(error.into());
-                break;
-            }
+            instruction.execute(&self)?;
         }
-        self.runtime.notify_end();
+
+        Ok(())
     }
 }
 
 
 impl Thread {
+    /// Executes the instruction on the current frame, e.g. a paused one, as if it was
+    /// a part of its code. A called method is executed until it returns. The pc of the frame
+    /// is kept, but an instruction returning from the frame removes it from the stack.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ExecError` if the execution fails, the frames of the called methods
+    /// are then discarded.
+    pub fn evaluate(&self, instruction: &Instruction) -> Result<(), ExecError> {
+        let frame = self.stack.current().expect("thread should have a frame to evaluate on");
+        let (pc, depth) = (frame.pc(), self.stack.len());
+
+        // the instruction is placed after the code, so that it does not use
+        // the references resolved by the instruction at the pc
+        frame.set_pc(frame.method().code().instructions().len() as isize);
+
+        let mut result = instruction.execute(self);
+
+        while result.is_ok() && self.stack.len() > depth {
+            let callee = self.stack.current().unwrap();
+            result = callee.method().code().instruction(callee.pc())
+                           .map_err(ExecError::from)
+                           .and_then(|instruction| instruction.execute(self));
+        }
+
+        while self.stack.len() > depth {
+            self.stack.pop();
+        }
+
+        frame.set_pc(pc);
+        result
+    }
+
     pub fn stack(&self) -> &FrameStack {
         &self.stack
     }
@@ -1,64 +1,159 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
 use std::ops::{Deref, DerefMut};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::SystemTime;
 
 use crate::vm::class::class::Class;
-use crate::vm::class::descriptor::{ParamsDesc, ReturnDesc};
 use crate::vm::class::instance::{Instance, InstanceId};
-use crate::vm::class::name::{ClassName, MethodName};
+use crate::vm::class::name::ClassName;
 use crate::vm::class::signature::MethodSig;
 use crate::vm::exec::class_loader::ClassLoader;
+use crate::vm::exec::class_source::ClassSource;
+use crate::vm::exec::config::{Allocations, HeapLimit, VmConfig};
 use crate::vm::exec::error::{ClassLoadError, ExecError};
+use crate::vm::exec::interpreter::Interpreter;
+use crate::vm::exec::stack_trace::StackTrace;
 use crate::vm::exec::thread::Thread;
 use crate::vm::memory::frame::Frame;
+use crate::vm::types::value::Value;
 
 
 /// A virtual machine.
 pub struct Vm {
     class_heap: Mutex<HashMap<ClassName, Arc<Class>>>,
-    object_heap: Mutex<HashMap<InstanceId, Instance>>,
+    object_heap: Mutex<ObjectHeap>,
     class_loader: ClassLoader,
+    class_files: Mutex<HashMap<ClassName, (PathBuf, Option<SystemTime>)>>,
+    class_entries: Mutex<HashMap<ClassName, PathBuf>>,
+    config: VmConfig,
     thread: Mutex<Option<Arc<Thread>>>,
-    error_callback: Mutex<Option<Box<dyn 'static + Send + FnMut(ExecError)>>>,
+    runner: Mutex<Option<JoinHandle<()>>>,
+    stop_requested: AtomicBool,
+    exit_status: Mutex<Option<i32>>,
+    snapshot_requested: AtomicBool,
+    snapshot: Mutex<Vec<Arc<Frame>>>,
+    error_callback: Mutex<Option<Box<dyn 'static + Send + FnMut(ExecError, StackTrace)>>>,
     update_callback: Mutex<Option<Box<dyn 'static + Send + FnMut()>>>,
     end_callback: Mutex<Option<Box<dyn 'static + Send + FnMut()>>>,
 }
 
 
 impl Vm {
-    /// Creates a new virtual machine, but does not start
-    /// any thread nor loads any class.
-    pub fn new(class_path: Vec<PathBuf>) -> Self {
+    /// Creates a new virtual machine loading classes from the source with the default
+    /// configuration, but does not start any thread nor loads any class.
+    pub fn new(source: impl ClassSource + 'static) -> Self {
+        Self::with_config(source, VmConfig::new())
+    }
+
+    /// Creates a new virtual machine loading classes from the source with the given
+    /// configuration, but does not start any thread nor loads any class.
+    pub fn with_config(source: impl ClassSource + 'static, config: VmConfig) -> Self {
         Vm {
             class_heap: Mutex::new(HashMap::new()),
-            object_heap: Mutex::new(HashMap::new()),
-            class_loader: ClassLoader::new(class_path),
+            object_heap: Mutex::new(ObjectHeap::default()),
+            class_loader: ClassLoader::new(source),
+            class_files: Mutex::new(HashMap::new()),
+            class_entries: Mutex::new(HashMap::new()),
+            config,
             thread: Mutex::new(None),
+            runner: Mutex::new(None),
+            stop_requested: AtomicBool::new(false),
+            exit_status: Mutex::new(None),
+            snapshot_requested: AtomicBool::new(false),
+            snapshot: Mutex::new(Vec::new()),
             error_callback: Mutex::new(None),
             update_callback: Mutex::new(None),
             end_callback: Mutex::new(None),
         }
     }
 
-    pub fn start(self: Arc<Self>, class_name: ClassName) {
+    /// Starts a thread stepping the main method of the given class, see `Class::main_method`,
+    /// with the arguments.
+    pub fn start(self: Arc<Self>, class_name: ClassName, args: Vec<Value>) {
         let mut main_thread = self.thread.lock().unwrap();
-        let method_sig = MethodSig::new(
-            ReturnDesc::Void,
-            MethodName::new("main").unwrap(),
-            ParamsDesc::empty(),
-        ).unwrap();
 
         let thread = match *main_thread {
-            None => Thread::new(self.clone(), class_name, method_sig),
+            None => Thread::new(self.clone(), class_name, args),
             Some(_) => panic!("main thread already running"),
         };
 
         *main_thread = Some(thread);
     }
 
+    /// Runs the main method of the given class with the arguments by the interpreter
+    /// on a new thread, without stepping. The frames are only visible through snapshots,
+    /// see `request_snapshot`.
+    pub fn run(self: Arc<Self>, class_name: ClassName, args: Vec<Value>) {
+        let mut runner = self.runner.lock().unwrap();
+
+        if runner.is_some() || self.thread.lock().unwrap().is_some() {
+            panic!("main thread already running");
+        }
+
+        let vm = self.clone();
+        *runner = Some(std::thread::spawn(move || {
+            let mut interpreter = Interpreter::new(vm.clone());
+
+            if let Err(error) = interpreter.run_main(&class_name, args) {
+                let trace = match interpreter.snapshot() {
+                    Ok(frames) => {
+                        let trace = StackTrace::capture(&frames);
+                        vm.publish_snapshot(frames);
+                        vm.notify_update();
+                        trace
+                    }
+                    Err(_) => StackTrace::default(),
+                };
+
+                vm.notify_error(error, trace);
+            }
+
+            vm.notify_end();
+        }));
+    }
+
+    /// Invokes the static method of the given class with the arguments by the interpreter
+    /// on the current thread and waits until it returns. Returns the returned value,
+    /// `None` for void methods.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ExecError` if the class can not be loaded, the method is not found,
+    /// the arguments do not match its parameters or the execution fails,
+    /// see `Interpreter::invoke` for the execution being stopped or exited.
+    pub fn invoke(self: &Arc<Self>, class_name: &ClassName, method_sig: &MethodSig, args: Vec<Value>) -> Result<Option<Value>, ExecError> {
+        Interpreter::new(self.clone()).invoke(class_name, method_sig, args)
+    }
+
+    /// Records the exit status of the program, given by `System.exit`
+    /// or returned by the main method.
+    pub fn exit(&self, status: i32) {
+        *self.exit_status.lock().unwrap() = Some(status);
+    }
+
+    /// Returns the exit status of the program, if it exited by `System.exit`
+    /// or its main method returned it.
+    pub fn exit_status(&self) -> Option<i32> {
+        *self.exit_status.lock().unwrap()
+    }
+
+    /// Returns the exit status of the program like `exit_status` and clears it,
+    /// so the virtual machine can be used to run the code again.
+    pub fn take_exit_status(&self) -> Option<i32> {
+        self.exit_status.lock().unwrap().take()
+    }
+
     /// Join and wait for the main thread.
     pub fn join(&self) {
+        if let Some(runner) = self.runner.lock().unwrap().take() {
+            runner.join().unwrap();
+            return;
+        }
+
         self.thread.lock().unwrap().deref()
             .clone()
             .expect("can not join main thread - not started")
@@ -76,23 +171,87 @@ impl Vm {
                 Ok(class.clone())
             }
             None => {
+                // the time is taken before loading, so that a change while loading makes the class stale
+                let entry = self.class_loader.class_entry(name);
+                let file = self.class_loader.source_file(name);
+                let modified = file.as_deref().and_then(modified_time);
+
                 let class = Arc::new(self.class_loader.load(name)?);
                 heap.insert(name.clone(), class.clone());
+
+                if let Some(file) = file {
+                    self.class_files.lock().unwrap().insert(name.clone(), (file, modified));
+                }
+                if let Some(entry) = entry {
+                    self.class_entries.lock().unwrap().insert(name.clone(), entry);
+                }
+
                 Ok(class)
             }
         }
     }
 
-    pub fn create_instance(&self, class: Arc<Class>) -> Instance {
-        let instance = Instance::new(class);
-        self.object_heap.lock().unwrap().insert(instance.id(), instance.clone());
-        instance
+    /// Creates a new instance of the class on the heap.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ExecError::OutOfMemory` if the instance would exceed a limit of the heap.
+    pub fn create_instance(&self, class: Arc<Class>) -> Result<Instance, ExecError> {
+        let mut heap = self.object_heap.lock().unwrap();
+        let instance = Instance::new(heap.next_id, class);
+        let size = instance.size();
+
+        let limit = match (self.config.max_heap_objects(), self.config.max_heap_bytes()) {
+            (Some(max), _) if heap.objects.len() >= max => Some(HeapLimit::Objects(max)),
+            (_, Some(max)) if heap.bytes + size > max => Some(HeapLimit::Bytes(max)),
+            _ => None,
+        };
+
+        if let Some(limit) = limit {
+            return Err(ExecError::OutOfMemory {
+                class_name: instance.class().name().clone(),
+                limit,
+            });
+        }
+
+        heap.next_id = heap.next_id.next();
+        heap.bytes += size;
+        heap.allocations.entry(instance.class().name().clone()).or_default().add(size);
+        heap.objects.insert(instance.id(), instance.clone());
+        Ok(instance)
+    }
+
+    pub fn config(&self) -> &VmConfig {
+        &self.config
+    }
+}
+
+
+/// Objects allocated by a virtual machine, ordered by their allocation.
+struct ObjectHeap {
+    objects: BTreeMap<InstanceId, Instance>,
+    next_id: InstanceId,
+    bytes: usize,
+    allocations: HashMap<ClassName, Allocations>,
+}
+
+
+impl Default for ObjectHeap {
+    fn default() -> Self {
+        ObjectHeap {
+            objects: BTreeMap::new(),
+            next_id: InstanceId::first(),
+            bytes: 0,
+            allocations: HashMap::new(),
+        }
     }
 }
 
 
 impl Vm {
-    pub fn set_error_callback(&self, callback: Option<Box<dyn 'static + Send + FnMut(ExecError)>>) {
+    /// Sets the callback called with the error the main thread failed with
+    /// and the stack trace of the thread at the point of the failure.
+    pub fn set_error_callback(&self, callback: Option<Box<dyn 'static + Send + FnMut(ExecError, StackTrace)>>) {
         *self.error_callback.lock().unwrap() = callback;
     }
 
@@ -104,10 +263,10 @@ impl Vm {
         *self.end_callback.lock().unwrap() = callback;
     }
 
-    pub fn notify_error(&self, error: ExecError) {
+    pub fn notify_error(&self, error: ExecError, trace: StackTrace) {
         let mut callback = self.error_callback.lock().unwrap();
         if let Some(callback) = callback.deref_mut() {
-            callback(error)
+            callback(error, trace)
         }
     }
 
@@ -135,17 +294,66 @@ impl Vm {
             .collect()
     }
 
+    /// Returns the class path entries, directories or archives, the loaded classes
+    /// were loaded from.
+    pub fn class_entries(&self) -> HashMap<ClassName, PathBuf> {
+        self.class_entries.lock().unwrap().clone()
+    }
+
+    /// Returns the class file the loaded class was parsed from,
+    /// `None` if it was loaded from an archive or not from a file at all.
+    pub fn class_file(&self, name: &ClassName) -> Option<PathBuf> {
+        let file = self.class_files.lock().unwrap().get(name).map(|(file, _)| file.clone())?;
+
+        match self.class_entries.lock().unwrap().get(name) {
+            Some(entry) if *entry == file => None,
+            _ => Some(file),
+        }
+    }
+
+    /// Returns the Java source file of the loaded class, which is looked up
+    /// next to its class file, `None` if the class does not name any.
+    pub fn java_source_file(&self, class: &Class) -> Option<PathBuf> {
+        let source_file = class.source_file()?;
+        let class_file = self.class_file(class.name())?;
+
+        Some(class_file.parent()?.join(source_file))
+    }
+
+    /// Returns the loaded classes whose class files or archives were modified or removed
+    /// since the classes were loaded, sorted by their names.
+    pub fn stale_classes(&self) -> Vec<ClassName> {
+        let mut stale: Vec<_> = self.class_files.lock().unwrap()
+            .iter()
+            .filter(|(_, (file, modified))| modified_time(file) != *modified)
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        stale.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+        stale
+    }
+
+    /// Returns the instances in the order of their allocation.
     pub fn instances(&self) -> Vec<Instance> {
         self.object_heap.lock().unwrap()
+            .objects
             .values()
             .cloned()
             .collect()
     }
 
+    /// Returns the allocation statistics of every class with an allocated instance.
+    pub fn allocations(&self) -> HashMap<ClassName, Allocations> {
+        self.object_heap.lock().unwrap().allocations.clone()
+    }
+
+    /// Returns the frames of the stepped thread, or the last snapshot
+    /// of the frames if the main method was run by the interpreter.
     pub fn frames(&self) -> Option<Vec<Arc<Frame>>> {
         match self.thread.lock().unwrap().deref() {
-            None => None,
             Some(thread) => Some(thread.stack().frames()),
+            None if self.runner.lock().unwrap().is_some() => Some(self.snapshot.lock().unwrap().clone()),
+            None => None,
         }
     }
 
@@ -157,6 +365,8 @@ impl Vm {
     }
 
     pub fn stop(&self) {
+        self.stop_requested.store(true, Ordering::Relaxed);
+
         match self.thread.lock().unwrap().deref_mut() {
             None => {}
             Some(thread) => thread.cancel(),
@@ -165,4 +375,65 @@ impl Vm {
 }
 
 
+/// Snapshots of the frames run by the interpreter.
+impl Vm {
+    /// Requests a snapshot of the frames, the update callback is called when it is published.
+    pub fn request_snapshot(&self) {
+        self.snapshot_requested.store(true, Ordering::Relaxed);
+    }
 
+    /// Returns whether a snapshot was requested and clears the request.
+    pub fn take_snapshot_request(&self) -> bool {
+        self.snapshot_requested.swap(false, Ordering::Relaxed)
+    }
+
+    /// Stores the snapshot of the frames returned by `frames`.
+    pub fn publish_snapshot(&self, frames: Vec<Arc<Frame>>) {
+        *self.snapshot.lock().unwrap() = frames;
+    }
+
+    /// Returns whether the virtual machine was requested to stop and clears the request.
+    pub fn take_stop_request(&self) -> bool {
+        self.stop_requested.swap(false, Ordering::Relaxed)
+    }
+}
+
+
+/// Returns the modification time of the file, if it exists.
+fn modified_time(file: &Path) -> Option<SystemTime> {
+    fs::metadata(file).and_then(|metadata| metadata.modified()).ok()
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::fs::OpenOptions;
+    use std::time::SystemTime;
+
+    use crate::vm::class::name::ClassName;
+    use crate::vm::exec::class_source::DirectorySource;
+    use crate::vm::exec::vm::Vm;
+
+
+    #[test]
+    fn stale_classes() {
+        let class_path = std::env::temp_dir().join(format!("mvm-stale-{}", std::process::id()));
+        fs::create_dir_all(&class_path).unwrap();
+        let file = class_path.join("Test.mvm");
+        fs::write(&file, "Test\n").unwrap();
+
+        let vm = Vm::new(DirectorySource::new(class_path.clone()));
+        let name = ClassName::new("Test").unwrap();
+        vm.resolve_class(&name).unwrap();
+        assert!(vm.stale_classes().is_empty());
+
+        OpenOptions::new().write(true).open(&file).unwrap().set_modified(SystemTime::UNIX_EPOCH).unwrap();
+        assert_eq!(vm.stale_classes(), vec![name.clone()]);
+
+        fs::remove_file(&file).unwrap();
+        assert_eq!(vm.stale_classes(), vec![name]);
+
+        fs::remove_dir_all(&class_path).unwrap();
+    }
+}
@@ -25,7 +25,7 @@ pub enum OperandStackError {
 /// An error caused by the inappropriate Variables manipulation.
 #[derive(Error, Debug)]
 pub enum LocalsError {
-    #[error("index {} is out of bounds, locals size is {size}")]
+    #[error("index {index} is out of bounds, locals size is {size}")]
     IndexOutOfBounds {
         index: usize,
         size: usize,
@@ -47,9 +47,19 @@ pub enum FrameError {
         expected: TypeDesc,
         got: ValueType,
     },
+    #[error("wrong number of arguments, expected {expected}, got {got}")]
+    WrongArgumentCount {
+        expected: usize,
+        got: usize,
+    },
     #[error(transparent)]
     OperandStack {
         #[from]
         source: OperandStackError
     },
+    #[error(transparent)]
+    Locals {
+        #[from]
+        source: LocalsError
+    },
 }
\ No newline at end of file
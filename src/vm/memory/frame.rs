@@ -3,10 +3,10 @@ use std::sync::{Arc, RwLock};
 use crate::vm::class::class::Class;
 use crate::vm::class::method::Method;
 use crate::vm::memory::error::FrameError;
-use crate::vm::memory::locals::Locals;
+use crate::vm::memory::locals::{Locals, Slot};
 use crate::vm::memory::operand_stack::OperandStack;
 use crate::vm::types::reference::Reference;
-use crate::vm::types::value::ValueType;
+use crate::vm::types::value::{Value, ValueType};
 
 
 /// A method frame.
@@ -24,14 +24,14 @@ pub struct Frame {
 impl Frame {
     pub const MAX_STACK: usize = 255;
 
-    /// Create a new frame for method.
-    pub fn new(class: Arc<Class>, method: Arc<Method>) -> Self {
+    /// Create a new frame for method with an operand stack of the given max size.
+    pub fn new(class: Arc<Class>, method: Arc<Method>, max_stack: usize) -> Self {
         let locals = Locals::new(method.code().locals_size());
 
         Frame {
             class,
             method,
-            stack: OperandStack::new(Self::MAX_STACK),
+            stack: OperandStack::new(max_stack),
             locals,
             pc: RwLock::new(0),
         }
@@ -39,7 +39,7 @@ impl Frame {
 
     /// Create a new frame for method, pop arguments from stack
     /// and load them to locals of the new frame.
-    pub fn new_from_call(class: Arc<Class>, method: Arc<Method>, stack: &OperandStack) -> Result<Self, FrameError> {
+    pub fn new_from_call(class: Arc<Class>, method: Arc<Method>, stack: &OperandStack, max_stack: usize) -> Result<Self, FrameError> {
         let locals = Locals::new(method.code().locals_size());
 
         // pop arguments from stack and load them to locals
@@ -71,12 +71,48 @@ impl Frame {
         Ok(Frame {
             class,
             method,
-            stack: OperandStack::new(Self::MAX_STACK),
+            stack: OperandStack::new(max_stack),
             locals,
             pc: RwLock::new(0),
         })
     }
 
+    /// Create a new frame for a method invoked from outside of the virtual machine,
+    /// the arguments are loaded to the locals as if they were pushed by a caller.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FrameError` if the arguments do not match the parameters of the method.
+    pub fn new_from_args(class: Arc<Class>, method: Arc<Method>, args: Vec<Value>, max_stack: usize) -> Result<Self, FrameError> {
+        let expected = method.signature().params_desc().len() + if method.is_static() { 0 } else { 1 };
+
+        if args.len() != expected {
+            return Err(FrameError::WrongArgumentCount {
+                expected,
+                got: args.len(),
+            });
+        }
+
+        let caller = OperandStack::with_values(Self::MAX_STACK, args)?;
+        Self::new_from_call(class, method, &caller, max_stack)
+    }
+
+    /// Create a frame of a method in the given state - with the given pc,
+    /// locals slots and operand stack values (the last one on top).
+    ///
+    /// # Errors
+    ///
+    /// Returns `FrameError` if the locals or the values do not fit in the frame.
+    pub fn with_state(class: Arc<Class>, method: Arc<Method>, pc: isize, locals: Vec<Slot>, stack: Vec<Value>, max_stack: usize) -> Result<Self, FrameError> {
+        Ok(Frame {
+            class,
+            method,
+            stack: OperandStack::with_values(max_stack, stack)?,
+            locals: Locals::with_slots(locals)?,
+            pc: RwLock::new(pc),
+        })
+    }
+
     /// Returns the stack of this Frame.
     pub fn stack(&self) -> &OperandStack {
         &self.stack
@@ -107,8 +143,27 @@ impl Frame {
         *self.pc.write().unwrap() += 1
     }
 
+    /// Sets the value of the pc of this Frame.
+    pub fn set_pc(&self, pc: isize) {
+        *self.pc.write().unwrap() = pc;
+    }
+
     /// Sets the value of the pc of this Frame as current pc + offset.
     pub fn offset_pc(&self, offset: i16) {
         *self.pc.write().unwrap() += offset as isize;
     }
+
+    /// Returns the line of the Java source the instruction at the pc was compiled from, if known.
+    pub fn source_line(&self) -> Option<usize> {
+        self.method.code().source_line(self.pc())
+    }
+
+    /// Returns the location of the instruction at the pc in the Java source
+    /// in the form `File.java:12`, if both the source file and the line are known.
+    pub fn source_location(&self) -> Option<String> {
+        let file = self.class.source_file()?;
+        let line = self.source_line()?;
+
+        Some(format!("{}:{}", file, line))
+    }
 }
\ No newline at end of file
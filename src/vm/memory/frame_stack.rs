@@ -32,6 +32,16 @@ impl FrameStack {
         self.frames.lock().unwrap().pop()
     }
 
+    /// Returns the number of frames.
+    pub fn len(&self) -> usize {
+        self.frames.lock().unwrap().len()
+    }
+
+    /// Returns true if there is no frame.
+    pub fn is_empty(&self) -> bool {
+        self.frames.lock().unwrap().is_empty()
+    }
+
     /// Get all frames. The the first added is first and the last added is last.
     pub fn frames(&self) -> Vec<Arc<Frame>> {
         self.frames.lock().unwrap().clone()
@@ -30,6 +30,24 @@ impl Locals {
         }
     }
 
+    /// Creates a new locals array of the given slots.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LocalsError::IndexOutOfBounds` if there are more slots than allowed.
+    pub fn with_slots(slots: Vec<Slot>) -> Result<Self, LocalsError> {
+        if slots.len() > Self::MAX_SIZE {
+            return Err(LocalsError::IndexOutOfBounds {
+                index: slots.len() - 1,
+                size: Self::MAX_SIZE,
+            });
+        }
+
+        Ok(Locals {
+            values: Mutex::new(slots)
+        })
+    }
+
     /// Load a value from the given index.
     ///
     /// # Errors
@@ -24,6 +24,25 @@ impl OperandStack {
         }
     }
 
+    /// Creates a new OperandStack holding the given values, the last one on top.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `OperandStackError::Overflow` if the values do not fit in the capacity.
+    pub fn with_values(capacity: usize, values: Vec<Value>) -> Result<Self, OperandStackError> {
+        let size = values.iter().map(|value| value.value_type().category().size()).sum();
+
+        if size > capacity {
+            return Err(OperandStackError::Overflow);
+        }
+
+        Ok(OperandStack {
+            values: Mutex::new(values),
+            size: Mutex::new(size),
+            capacity: Mutex::new(capacity),
+        })
+    }
+
     fn check_overflow(&self, size: usize) -> Result<(), OperandStackError> {
         if *self.size.lock().unwrap() + size > *self.capacity.lock().unwrap() {
             Err(OperandStackError::Overflow)
@@ -315,8 +334,10 @@ impl OperandStack {
         match values.last() {
             None => Err(OperandStackError::Underflow),
             Some(comp_value) => {
+                let size = comp_value.value_type().category().size();
                 let value = T::try_from(comp_value.clone())?;
                 values.pop();
+                *self.size.lock().unwrap() -= size;
                 Ok(value)
             }
         }
@@ -364,6 +385,28 @@ impl OperandStack {
         Ok(())
     }
 
+    /// Replace the value on the given index, counted from the bottom
+    /// of the stack as in `values`, with a value of the same category.
+    ///
+    /// # Errors
+    ///
+    /// Can return `OperandStack::Underflow` if there is no value on the index
+    /// or `OperandStack::InvalidType` if the categories of the values differ.
+    pub fn replace_value(&self, index: usize, value: Value) -> Result<(), OperandStackError> {
+        let mut values = self.values.lock().unwrap();
+
+        match values.get(index) {
+            None => Err(OperandStackError::Underflow),
+            Some(previous) if previous.value_type().category() != value.value_type().category() => {
+                Err(OperandStackError::InvalidType)
+            }
+            Some(_) => {
+                values[index] = value;
+                Ok(())
+            }
+        }
+    }
+
     /// Returns all the values of the local variables array.
     pub fn values(&self) -> Vec<Value> {
         self.values.lock().unwrap().clone()
@@ -431,6 +474,23 @@ mod test {
     }
 
 
+    #[test]
+    fn replace_value() {
+        let stack = OperandStack::new(32);
+
+        stack.push(Int::new(1)).unwrap();
+        stack.push(Long::new(2)).unwrap();
+
+        stack.replace_value(0, Float::new(3.0).into()).unwrap();
+        stack.replace_value(1, Long::new(4).into()).unwrap();
+        stack.replace_value(1, Int::new(5).into()).expect_err("replace by a value of other category should fail");
+        stack.replace_value(2, Int::new(6).into()).expect_err("replace above the top should fail");
+
+        assert_eq!(stack.pop::<Long>().unwrap(), Long::new(4));
+        assert_eq!(stack.pop::<Float>().unwrap(), Float::new(3.0));
+    }
+
+
     #[test]
     fn dup1() {
         let mut stack = OperandStack::new(32);
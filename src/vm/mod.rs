@@ -4,3 +4,4 @@ pub mod class;
 pub mod exec;
 pub mod memory;
 pub mod parse;
+pub mod verify;
@@ -16,12 +16,38 @@ pub struct ClassInfo {
     name: ClassName,
     fields: Vec<FieldInfo>,
     methods: Vec<MethodInfo>,
+    source_file: Option<String>,
 }
 
 
 impl ClassInfo {
     pub fn new(name: ClassName, fields: Vec<FieldInfo>, methods: Vec<MethodInfo>) -> Self {
-        ClassInfo { name, fields, methods }
+        ClassInfo { name, fields, methods, source_file: None }
+    }
+
+    /// Sets the name of the Java source file given in the class header.
+    pub fn with_source_file(mut self, source_file: Option<String>) -> Self {
+        self.source_file = source_file;
+        self
+    }
+
+    /// Returns the class name.
+    pub fn name(&self) -> &ClassName {
+        &self.name
+    }
+
+    /// Returns the line of the `FIELD` entry of the field with the given name.
+    pub fn field_line(&self, name: &FieldName) -> Option<usize> {
+        self.fields.iter()
+            .find(|field| field.name == *name)
+            .map(|field| field.line)
+    }
+
+    /// Returns the line of the `METHOD` entry of the method with the given signature.
+    pub fn method_line(&self, signature: &MethodSig) -> Option<usize> {
+        self.methods.iter()
+            .find(|method| method.name == *signature.name() && method.params_desc == *signature.params_desc())
+            .map(|method| method.line)
     }
 }
 
@@ -31,12 +57,19 @@ pub struct FieldInfo {
     name: FieldName,
     type_dec: TypeDesc,
     is_static: bool,
+    line: usize,
 }
 
 
 impl FieldInfo {
     pub fn new(name: FieldName, descriptor: TypeDesc, is_static: bool) -> Self {
-        FieldInfo { name, type_dec: descriptor, is_static }
+        FieldInfo { name, type_dec: descriptor, is_static, line: 0 }
+    }
+
+    /// Sets the line of the `FIELD` entry in the class file.
+    pub fn with_line(mut self, line: usize) -> Self {
+        self.line = line;
+        self
     }
 }
 
@@ -49,6 +82,10 @@ pub struct MethodInfo {
     is_static: bool,
     locals: u8,
     instructions: Vec<Instruction>,
+    labels: Vec<(usize, String)>,
+    lines: Vec<usize>,
+    source_lines: Vec<(usize, usize)>,
+    line: usize,
 }
 
 
@@ -60,6 +97,7 @@ impl MethodInfo {
         is_static: bool,
         locals: u8,
         instructions: Vec<Instruction>,
+        labels: Vec<(usize, String)>,
     ) -> Self {
         MethodInfo {
             name,
@@ -68,8 +106,30 @@ impl MethodInfo {
             is_static,
             locals,
             instructions,
+            labels,
+            lines: Vec::new(),
+            source_lines: Vec::new(),
+            line: 0,
         }
     }
+
+    /// Sets the line of the `METHOD` entry in the class file.
+    pub fn with_line(mut self, line: usize) -> Self {
+        self.line = line;
+        self
+    }
+
+    /// Sets the lines of the instructions in the class file.
+    pub fn with_lines(mut self, lines: Vec<usize>) -> Self {
+        self.lines = lines;
+        self
+    }
+
+    /// Sets the line-number table of the Java source.
+    pub fn with_source_lines(mut self, source_lines: Vec<(usize, usize)>) -> Self {
+        self.source_lines = source_lines;
+        self
+    }
 }
 
 
@@ -91,7 +151,7 @@ impl TryInto<Class> for ClassInfo {
             self.name,
             fields,
             methods,
-        )?)
+        )?.with_source_file(self.source_file))
     }
 }
 
@@ -103,7 +163,9 @@ impl TryInto<Method> for MethodInfo {
         Ok(Method::new(
             MethodSig::new(self.return_desc, self.name, self.params_desc)?,
             self.is_static,
-            Code::new(self.locals as usize, self.instructions)?,
+            Code::new(self.locals as usize, self.instructions, self.labels)?
+                .with_lines(self.lines)
+                .with_source_lines(self.source_lines),
         )?)
     }
 }
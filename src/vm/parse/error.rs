@@ -9,28 +9,152 @@ use thiserror::Error;
 use crate::vm::class::error::{ClassError, CodeError, DescriptorError, MethodError, NameError, SignatureError};
 
 
+/// All errors found while parsing a class file.
+#[derive(Error, Debug)]
+pub struct ParseClassErrors {
+    errors: Vec<ParseClassError>,
+}
+
+
+impl ParseClassErrors {
+    /// Creates a new `ParseClassErrors` from the given errors.
+    pub fn new(errors: Vec<ParseClassError>) -> Self {
+        ParseClassErrors {
+            errors,
+        }
+    }
+
+    /// Returns the errors in order of their occurrence.
+    pub fn errors(&self) -> &[ParseClassError] {
+        &self.errors
+    }
+}
+
+
+impl Display for ParseClassErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for error in &self.errors {
+            writeln!(f, "{}", error.render())?;
+        }
+
+        match self.errors.len() {
+            1 => write!(f, "could not parse class file due to previous error"),
+            n => write!(f, "could not parse class file due to {} previous errors", n),
+        }
+    }
+}
+
+
 /// An error that can occur while parsing a class file.
+/// The error carries the position of the erroneous part
+/// of the class file together with the whole source line.
 #[derive(Error, Debug)]
 pub struct ParseClassError {
     kind: ParseClassErrorKind,
-    line_pos: usize,
+    span: Span,
+    source_line: String,
 }
 
 
 impl ParseClassError {
-    /// Creates a new `ParseClassError` from given kind and line position.
-    pub fn new(kind: ParseClassErrorKind, line_pos: usize) -> Self {
+    /// Creates a new `ParseClassError` from given kind, span and the source line the span points into.
+    pub fn new(kind: ParseClassErrorKind, span: Span, source_line: String) -> Self {
         ParseClassError {
             kind,
-            line_pos,
+            span,
+            source_line,
         }
     }
+
+    /// Returns the kind of this error.
+    pub fn kind(&self) -> &ParseClassErrorKind {
+        &self.kind
+    }
+
+    /// Returns the position of the erroneous part of the class file.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Returns the source line the error points into.
+    pub fn source_line(&self) -> &str {
+        &self.source_line
+    }
+
+    /// Renders the error together with the source line and
+    /// the erroneous part of it marked by carets, for example:
+    ///
+    /// ```text
+    /// error: unknown instruction: IADDD
+    ///  --> 12:5
+    ///    |
+    /// 12 |     IADDD
+    ///    |     ^^^^^
+    /// ```
+    pub fn render(&self) -> String {
+        let line_number = self.span.line().to_string();
+        let gutter = " ".repeat(line_number.len());
+
+        // keep tabs so that the carets are aligned with the source line
+        let indent: String = self.source_line
+                                 .chars()
+                                 .take(self.span.column() - 1)
+                                 .map(|c| if c == '\t' { '\t' } else { ' ' })
+                                 .collect();
+
+        format!("error: {kind}\n{gutter}--> {line}:{column}\n{gutter} |\n{line} | {source}\n{gutter} | {indent}{carets}\n",
+                kind = self.kind,
+                gutter = gutter,
+                line = line_number,
+                column = self.span.column(),
+                source = self.source_line,
+                indent = indent,
+                carets = "^".repeat(self.span.width()))
+    }
 }
 
 
 impl Display for ParseClassError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "on line {}: {}", self.line_pos, self.kind)
+        write!(f, "on line {}, column {}: {}", self.span.line(), self.span.column(), self.kind)
+    }
+}
+
+
+/// A position of a part of a class file source line.
+/// Lines and columns are numbered from 1.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Span {
+    line: usize,
+    column: usize,
+    width: usize,
+}
+
+
+impl Span {
+    /// Creates a new `Span` on the given line starting on the given column
+    /// and spanning the given number of characters.
+    pub fn new(line: usize, column: usize, width: usize) -> Self {
+        Span {
+            line,
+            column,
+            width,
+        }
+    }
+
+    /// Returns the line number.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the column number of the first character.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Returns the number of spanned characters.
+    pub fn width(&self) -> usize {
+        self.width
     }
 }
 
@@ -52,12 +176,26 @@ pub enum ParseClassErrorKind {
     InvalidParamsDescriptor(String),
     #[error("invalid field definition: {0}")]
     InvalidFieldDefinition(String),
-    #[error("invalid method definition: {0}")]
-    InvalidInstructionDefinition(String),
     #[error("invalid instruction definition: {0}")]
+    InvalidInstructionDefinition(String),
+    #[error("invalid method definition: {0}")]
     InvalidMethodDefinition(String),
+    #[error("invalid source file definition: {0}")]
+    InvalidSourceDefinition(String),
+    #[error("invalid source line {0}, lines are numbered from 1")]
+    InvalidSourceLine(String),
     #[error("type descriptor is empty")]
     EmptyTypeDescriptor,
+    #[error("missing END of method")]
+    MissingEnd,
+    #[error("invalid label: {0}")]
+    InvalidLabel(String),
+    #[error("label {0} is already defined")]
+    DuplicateLabel(String),
+    #[error("label {0} is not defined")]
+    UndefinedLabel(String),
+    #[error("label {0} is too far from the branch instruction")]
+    LabelOutOfRange(String),
     #[error(transparent)]
     Descriptor {
         #[from]
@@ -0,0 +1,148 @@
+//! Lexical classification of class file sources for syntax highlighting.
+
+use crate::vm::parse::error::Span;
+
+
+/// A kind of a highlighted token.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TokenKind {
+    /// Keywords `FIELD`, `METHOD`, `END` and `static`.
+    Keyword,
+    /// Instruction mnemonics.
+    Instruction,
+    /// Type, return and method parameter descriptors.
+    Descriptor,
+    /// Numeric operands.
+    Number,
+    /// Label definitions.
+    Label,
+    /// Comments starting with `//`.
+    Comment,
+}
+
+
+/// A highlighted part of a class file source.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Token {
+    kind: TokenKind,
+    span: Span,
+}
+
+
+impl Token {
+    /// Returns the kind of the token.
+    pub fn kind(&self) -> TokenKind {
+        self.kind
+    }
+
+    /// Returns the position of the token, columns are counted in characters.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+
+/// Classifies the tokens of the class file source. The classification
+/// is purely lexical, so it works for sources which do not parse.
+/// Tokens which are not highlighted, e.g. names, are left out.
+pub fn highlight(source: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let (code, comment) = match line.find("//") {
+            None => (line, None),
+            Some(i) => (&line[..i], Some(&line[i..])),
+        };
+
+        let mut column = 1;
+        let mut chars = code.chars().peekable();
+
+        while let Some(c) = chars.peek().copied() {
+            if c.is_whitespace() {
+                chars.next();
+                column += 1;
+                continue;
+            }
+
+            let mut word = String::new();
+            while let Some(c) = chars.peek().copied() {
+                if c.is_whitespace() {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+
+            let width = word.chars().count();
+            if let Some(kind) = classify(&word) {
+                tokens.push(Token {
+                    kind,
+                    span: Span::new(index + 1, column, width),
+                });
+            }
+            column += width;
+        }
+
+        if let Some(comment) = comment {
+            tokens.push(Token {
+                kind: TokenKind::Comment,
+                span: Span::new(index + 1, column, comment.chars().count()),
+            });
+        }
+    }
+
+    tokens
+}
+
+
+fn classify(word: &str) -> Option<TokenKind> {
+    match word {
+        "FIELD" | "METHOD" | "END" | "SOURCE" | "LINE" | "static" => Some(TokenKind::Keyword),
+        "int" | "long" | "float" | "double" | "void" => Some(TokenKind::Descriptor),
+        _ if word.starts_with('(') && word.ends_with(')') => Some(TokenKind::Descriptor),
+        _ if word.len() > 1 && word.ends_with(':') => Some(TokenKind::Label),
+        _ if word.parse::<f64>().is_ok() => Some(TokenKind::Number),
+        _ if word.starts_with(|c: char| c.is_ascii_uppercase())
+            && word.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_') => {
+            Some(TokenKind::Instruction)
+        }
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::vm::parse::error::Span;
+    use crate::vm::parse::highlight::{highlight, TokenKind};
+
+
+    #[test]
+    fn tokens() {
+        let source = "\
+Test
+METHOD
+static int add (int,int) 2 // sum
+loop: ILOAD_0
+    BIPUSH -3
+END";
+
+        let tokens: Vec<_> = highlight(source).iter()
+                                              .map(|token| (token.kind(), token.span()))
+                                              .collect();
+
+        assert_eq!(tokens, vec![
+            (TokenKind::Keyword, Span::new(2, 1, 6)),
+            (TokenKind::Keyword, Span::new(3, 1, 6)),
+            (TokenKind::Descriptor, Span::new(3, 8, 3)),
+            (TokenKind::Descriptor, Span::new(3, 16, 9)),
+            (TokenKind::Number, Span::new(3, 26, 1)),
+            (TokenKind::Comment, Span::new(3, 28, 6)),
+            (TokenKind::Label, Span::new(4, 1, 5)),
+            (TokenKind::Instruction, Span::new(4, 7, 7)),
+            (TokenKind::Instruction, Span::new(5, 5, 6)),
+            (TokenKind::Number, Span::new(5, 12, 2)),
+            (TokenKind::Keyword, Span::new(6, 1, 3)),
+        ]);
+    }
+}
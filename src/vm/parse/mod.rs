@@ -1,3 +1,4 @@
 pub mod classfile;
 pub mod parser;
 pub mod error;
+pub mod highlight;
@@ -1,5 +1,9 @@
 use std::cell::{Cell, RefCell};
-use std::str::{Lines, SplitWhitespace};
+use std::convert::TryFrom;
+use std::str::SplitWhitespace;
+
+use lazy_static::lazy_static;
+use regex::Regex;
 
 use crate::vm::bytecode::instruction::{Instruction, Ldc2Arg, LdcArg};
 use crate::vm::class::descriptor::{ParamsDesc, ReturnDesc, TypeDesc};
@@ -7,61 +11,126 @@ use crate::vm::class::name::{ClassName, FieldName, MethodName};
 use crate::vm::class::signature::{FieldSig, MethodSig};
 use crate::vm::class::symbolic::{FieldRef, MethodRef};
 use crate::vm::parse::classfile::{ClassInfo, FieldInfo, MethodInfo};
-use crate::vm::parse::error::{ParseClassError, ParseClassErrorKind, ParseNumberError};
+use crate::vm::parse::error::{ParseClassError, ParseClassErrorKind, ParseClassErrors, ParseNumberError, Span};
 
 
 /// MVM class file parser.
+///
+/// The parser does not stop on the first error. It reports the error
+/// and recovers on the next field, method or instruction line,
+/// so that all errors in the class file are collected at once.
 pub struct ClassFileParser<'a> {
     input: Input<'a>,
+    errors: RefCell<Vec<ParseClassError>>,
 }
 
 
 impl<'a> ClassFileParser<'a> {
     /// Create a classfile parser from given str input.
-    pub fn new(input: &str) -> ClassFileParser {
+    pub fn new(input: &'a str) -> ClassFileParser<'a> {
         ClassFileParser {
             input: Input::new(input),
+            errors: RefCell::new(Vec::new()),
         }
     }
 
     /// Parse the whole class file.
-    pub fn parse(self) -> Result<ClassInfo, ParseClassError> {
-        self.parse_class_file()
-            .map_err(|e| ParseClassError::new(e, self.input.line_pos()))
+    ///
+    /// # Errors
+    ///
+    /// Returns all errors found in the class file.
+    pub fn parse(self) -> Result<ClassInfo, ParseClassErrors> {
+        let class_info = self.parse_class_file();
+        let errors = self.errors.into_inner();
+
+        match class_info {
+            Some(class_info) if errors.is_empty() => Ok(class_info),
+            _ => Err(ParseClassErrors::new(errors)),
+        }
+    }
+
+    /// Parse a single instruction line, e.g. typed in interactively.
+    /// Labels are not allowed, branch targets must be given by offsets.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error found in the line.
+    pub fn parse_single_instruction(self) -> Result<Instruction, ParseClassErrors> {
+        self.parse_single_instruction_line().map_err(|error| ParseClassErrors::new(vec![error]))
     }
 }
 
 
 impl<'a> ClassFileParser<'a> {
     /// Parse the whole class file.
-    fn parse_class_file(&self) -> Result<ClassInfo, ParseClassErrorKind> {
+    /// Returns `None` if the class file can not be parsed, the errors are reported.
+    fn parse_class_file(&self) -> Option<ClassInfo> {
         // class name
-        let class_name = self.parse_class_name(self.next_line_or_err()?)?;
+        let class_name = match self.next_line() {
+            None => {
+                self.report(self.error_end_of_input());
+                return None;
+            }
+            Some(line) => {
+                let name = self.parse_class_name(line);
+                self.recover(name)
+            }
+        };
+
+        // optional name of the Java source file the class was compiled from
+        let source_file = match self.next_line() {
+            Some(line) if line.split_whitespace().next() == Some("SOURCE") => {
+                let source_file = self.parse_source_file(line);
+                self.recover(source_file)
+            }
+            Some(_) => {
+                self.input.unread_line();
+                None
+            }
+            None => None,
+        };
 
         // parse fields and methods
         let mut fields = Vec::new();
         let mut methods = Vec::new();
 
+        // skip lines until the next entry after an unknown entry
+        let mut recovering = false;
+
         while let Some(line) = self.next_line() {
-            match line.trim() {
+            let line_pos = self.input.line_pos();
+
+            match line {
                 "FIELD" => {
-                    let info = self.parse_field()?;
-                    fields.push(info);
+                    recovering = false;
+                    let field = self.parse_field();
+
+                    if let Some(info) = self.recover(field) {
+                        fields.push(info.with_line(line_pos));
+                    }
                 }
                 "METHOD" => {
-                    let info = self.parse_method()?;
-                    methods.push(info);
+                    recovering = false;
+
+                    if let Some(info) = self.parse_method() {
+                        methods.push(info.with_line(line_pos));
+                    }
+                }
+                _ => {
+                    if !recovering {
+                        self.report(self.error(ParseClassErrorKind::UnknownEntry(line.into()), line));
+                        recovering = true;
+                    }
                 }
-                _ => return Err(ParseClassErrorKind::UnknownEntry(line.into())),
             }
         }
 
-        Ok(ClassInfo::new(class_name, fields, methods))
+        Some(ClassInfo::new(class_name?, fields, methods).with_source_file(source_file))
     }
 
     /// Get next non empty line from input. Comments are stripped
     /// and line is trimmed from whitespaces.
-    fn next_line(&self) -> Option<&str> {
+    fn next_line(&self) -> Option<&'a str> {
         while let Some(mut line) = self.input.next_line() {
             // strip comment
             line = match line.find("//") {
@@ -85,23 +154,111 @@ impl<'a> ClassFileParser<'a> {
     /// # Errors
     ///
     /// When no line is available, returns `ParseError::UnexpectedEndOfInput`
-    fn next_line_or_err(&self) -> Result<&str, ParseClassErrorKind> {
+    fn next_line_or_err(&self) -> Result<&'a str, ParseClassError> {
         self.next_line()
-            .ok_or_else(|| ParseClassErrorKind::UnexpectedEndOfInput)
+            .ok_or_else(|| self.error_end_of_input())
+    }
+
+    /// Get next token of the current line.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ParseClassErrorKind::UnexpectedEndOfLine` if there
+    /// are no more available tokens.
+    fn next_token<'t>(&self, tokens: &mut Tokens<'t, SplitWhitespace<'t>>) -> Result<&'t str, ParseClassError> {
+        match tokens.next() {
+            Some(token) => Ok(token),
+            None => {
+                let line = self.input.current_line();
+                Err(self.error(ParseClassErrorKind::UnexpectedEndOfLine, &line[line.len()..]))
+            }
+        }
+    }
+
+    /// Returns true if the line starts a new class file entry.
+    fn is_entry(line: &str) -> bool {
+        line == "FIELD" || line == "METHOD"
     }
 }
 
 
+/// Errors.
 impl<'a> ClassFileParser<'a> {
-    fn parse_field(&self) -> Result<FieldInfo, ParseClassErrorKind> {
+    /// Creates an error of the given kind pointing to the given token
+    /// which must be a part of the current line.
+    fn error<K>(&self, kind: K, token: &str) -> ParseClassError
+                where K: Into<ParseClassErrorKind> {
+        let line = self.input.current_line();
+        ParseClassError::new(kind.into(), self.span(token), line.into())
+    }
+
+    /// Creates an error of unexpected end of input pointing to the end of the last line.
+    fn error_end_of_input(&self) -> ParseClassError {
+        let line = self.input.current_line();
+        self.error(ParseClassErrorKind::UnexpectedEndOfInput, &line[line.len()..])
+    }
+
+    /// Returns the span of the given token which must be a part of the current line.
+    fn span(&self, token: &str) -> Span {
+        let line = self.input.current_line();
+        let start = line.as_ptr() as usize;
+        let offset = (token.as_ptr() as usize)
+            .checked_sub(start)
+            .filter(|offset| *offset <= line.len())
+            .unwrap_or(0);
+
+        Span::new(
+            self.input.line_pos(),
+            line[..offset].chars().count() + 1,
+            token.chars().count().max(1),
+        )
+    }
+
+    /// Adds the error to the reported errors.
+    fn report(&self, error: ParseClassError) {
+        self.errors.borrow_mut().push(error);
+    }
+
+    /// Reports the error if there is one and returns the result value as an option.
+    fn recover<T>(&self, result: Result<T, ParseClassError>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.report(error);
+                None
+            }
+        }
+    }
+}
+
+
+impl<'a> ClassFileParser<'a> {
+    /// Parse the `SOURCE` line of the class header naming the Java source file.
+    fn parse_source_file(&self, line: &str) -> Result<String, ParseClassError> {
+        let mut tokens = line.split_whitespace().skip(1);
+
+        match (tokens.next(), tokens.next()) {
+            (Some(file), None) => Ok(file.into()),
+            _ => Err(self.error(ParseClassErrorKind::InvalidSourceDefinition(line.into()), line)),
+        }
+    }
+
+    fn parse_field(&self) -> Result<FieldInfo, ParseClassError> {
         let line = self.next_line_or_err()?;
+
+        if Self::is_entry(line) {
+            let error = self.error(ParseClassErrorKind::InvalidFieldDefinition(line.into()), line);
+            self.input.unread_line();
+            return Err(error);
+        }
+
         let mut tokens = line.split_whitespace();
 
         // check static flag
         let mut is_static = false;
         let mut token = tokens.next();
 
-        if token.is_some() && token.unwrap() == "static" {
+        if token == Some("static") {
             is_static = true;
             token = tokens.next()
         }
@@ -111,7 +268,7 @@ impl<'a> ClassFileParser<'a> {
 
         if desc.is_none() || name.is_none() || tokens.next().is_some() {
             // too few or too many items in field definition
-            return Err(ParseClassErrorKind::InvalidFieldDefinition(line.into()));
+            return Err(self.error(ParseClassErrorKind::InvalidFieldDefinition(line.into()), line));
         }
 
         let desc = self.parse_type_desc(desc.unwrap())?;
@@ -120,15 +277,45 @@ impl<'a> ClassFileParser<'a> {
         Ok(FieldInfo::new(name, desc, is_static))
     }
 
-    fn parse_method(&self) -> Result<MethodInfo, ParseClassErrorKind> {
+    /// Parse the method header and the method instructions.
+    /// Returns `None` if the method can not be parsed, the errors are reported.
+    fn parse_method(&self) -> Option<MethodInfo> {
+        let header = self.parse_method_header();
+        let header = self.recover(header);
+
+        // instructions are parsed even when the header is invalid to report all errors
+        let code = self.parse_instructions();
+
+        let (name, ret, params, is_static, locals) = header?;
+        let (instructions, labels, lines, source_lines) = code?;
+
+        Some(MethodInfo::new(
+            name,
+            ret,
+            params,
+            is_static,
+            locals,
+            instructions,
+            labels,
+        ).with_lines(lines).with_source_lines(source_lines))
+    }
+
+    fn parse_method_header(&self) -> Result<(MethodName, ReturnDesc, ParamsDesc, bool, u8), ParseClassError> {
         let line = self.next_line_or_err()?;
+
+        if Self::is_entry(line) {
+            let error = self.error(ParseClassErrorKind::InvalidMethodDefinition(line.into()), line);
+            self.input.unread_line();
+            return Err(error);
+        }
+
         let mut tokens = line.split_whitespace();
 
         // check static flag
         let mut is_static = false;
         let mut token = tokens.next();
 
-        if token.is_some() && token.unwrap() == "static" {
+        if token == Some("static") {
             is_static = true;
             token = tokens.next()
         }
@@ -138,9 +325,9 @@ impl<'a> ClassFileParser<'a> {
         let params = tokens.next();
         let locals = tokens.next();
 
-        if ret.is_none() || name.is_none() || params.is_none() || tokens.next().is_some() {
+        if ret.is_none() || name.is_none() || params.is_none() || locals.is_none() || tokens.next().is_some() {
             // too few or too many items in method definition
-            return Err(ParseClassErrorKind::InvalidMethodDefinition(line.into()));
+            return Err(self.error(ParseClassErrorKind::InvalidMethodDefinition(line.into()), line));
         }
 
         let ret = self.parse_return_desc(ret.unwrap())?;
@@ -148,274 +335,437 @@ impl<'a> ClassFileParser<'a> {
         let params = self.parse_method_params(params.unwrap())?;
         let locals = self.parse_u8(locals.unwrap())?;
 
-        let instructions = self.parse_instructions()?;
-
-        Ok(MethodInfo::new(
-            name,
-            ret,
-            params,
-            is_static,
-            locals,
-            instructions,
-        ))
+        Ok((name, ret, params, is_static, locals))
     }
 
     /// Parse all instructions line by line until the line with
     /// keyword `END` is reached.
-    fn parse_instructions(&self) -> Result<Vec<Instruction>, ParseClassErrorKind> {
+    /// Returns the instructions together with the labels defined among them,
+    /// the lines of the instructions and the line-number table of the Java source
+    /// or `None` if there were errors, which are reported.
+    fn parse_instructions(&self) -> Option<(Vec<Instruction>, Labels, Vec<usize>, SourceLines)> {
         let mut instructions = Vec::new();
-        let mut ended = false;
+        let mut labels: Labels = Vec::new();
+        let mut lines = Vec::new();
+        let mut source_lines: SourceLines = Vec::new();
+        let mut branches = Vec::new();
+        let mut valid = true;
+
+        loop {
+            let line = match self.next_line() {
+                None => {
+                    self.report(self.error_end_of_input());
+                    return None;
+                }
+                Some(line) => line,
+            };
 
-        while let Some(line) = self.next_line() {
-            if line.is_empty() {
-                continue;
+            if Self::is_entry(line) {
+                // missing end of method, recover on the next entry
+                self.report(self.error(ParseClassErrorKind::MissingEnd, line));
+                self.input.unread_line();
+                return None;
             }
 
-            let mut tokens = Tokens::whitespaces(line);
+            let result = self.parse_instruction_line(line, &mut instructions, &mut labels, &mut source_lines, &mut branches);
+
+            // the invalid instructions replaced by NOP keep their lines too
+            lines.resize(instructions.len(), self.input.line_pos());
 
-            let instruction = match tokens.next_or_err()? {
-                "END" => {
-                    ended = true;
-                    break;
+            match result {
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(error) => {
+                    self.report(error);
+                    valid = false;
                 }
-                name => match name {
-                    "NOP" => Instruction::NOP,
-                    "ACONST_NULL" => Instruction::ACONST_NULL,
-                    "ICONST_M1" => Instruction::ICONST_M1,
-                    "ICONST_0" => Instruction::ICONST_0,
-                    "ICONST_1" => Instruction::ICONST_1,
-                    "ICONST_2" => Instruction::ICONST_2,
-                    "ICONST_3" => Instruction::ICONST_3,
-                    "ICONST_4" => Instruction::ICONST_4,
-                    "ICONST_5" => Instruction::ICONST_5,
-                    "LCONST_0" => Instruction::LCONST_0,
-                    "LCONST_1" => Instruction::LCONST_1,
-                    "FCONST_0" => Instruction::FCONST_0,
-                    "FCONST_1" => Instruction::FCONST_1,
-                    "FCONST_2" => Instruction::FCONST_2,
-                    "DCONST_0" => Instruction::DCONST_0,
-                    "DCONST_1" => Instruction::DCONST_1,
-                    "BIPUSH" => Instruction::BIPUSH(self.parse_i8(tokens.next_or_err()?)?),
-                    "SIPUSH" => Instruction::SIPUSH(self.parse_i16(tokens.next_or_err()?)?),
-                    "LDC" => Instruction::LDC(self.parse_ldc_arg(tokens.next_or_err()?)?),
-                    "LDC_W" => Instruction::LDC_W(self.parse_ldc_arg(tokens.next_or_err()?)?),
-                    "LDC2_W" => Instruction::LDC2_W(self.parse_ldc2_arg(tokens.next_or_err()?)?),
-                    "ILOAD" => Instruction::ILOAD(self.parse_u8(tokens.next_or_err()?)?),
-                    "LLOAD" => Instruction::LLOAD(self.parse_u8(tokens.next_or_err()?)?),
-                    "FLOAD" => Instruction::FLOAD(self.parse_u8(tokens.next_or_err()?)?),
-                    "DLOAD" => Instruction::DLOAD(self.parse_u8(tokens.next_or_err()?)?),
-                    "ALOAD" => Instruction::ALOAD(self.parse_u8(tokens.next_or_err()?)?),
-                    "ILOAD_0" => Instruction::ILOAD_0,
-                    "ILOAD_1" => Instruction::ILOAD_1,
-                    "ILOAD_2" => Instruction::ILOAD_2,
-                    "ILOAD_3" => Instruction::ILOAD_3,
-                    "LLOAD_0" => Instruction::LLOAD_0,
-                    "LLOAD_1" => Instruction::LLOAD_1,
-                    "LLOAD_2" => Instruction::LLOAD_2,
-                    "LLOAD_3" => Instruction::LLOAD_3,
-                    "FLOAD_0" => Instruction::FLOAD_0,
-                    "FLOAD_1" => Instruction::FLOAD_1,
-                    "FLOAD_2" => Instruction::FLOAD_2,
-                    "FLOAD_3" => Instruction::FLOAD_3,
-                    "DLOAD_0" => Instruction::DLOAD_0,
-                    "DLOAD_1" => Instruction::DLOAD_1,
-                    "DLOAD_2" => Instruction::DLOAD_2,
-                    "DLOAD_3" => Instruction::DLOAD_3,
-                    "ALOAD_0" => Instruction::ALOAD_0,
-                    "ALOAD_1" => Instruction::ALOAD_1,
-                    "ALOAD_2" => Instruction::ALOAD_2,
-                    "ALOAD_3" => Instruction::ALOAD_3,
-                    "ISTORE" => Instruction::ISTORE(self.parse_u8(tokens.next_or_err()?)?),
-                    "LSTORE" => Instruction::LSTORE(self.parse_u8(tokens.next_or_err()?)?),
-                    "FSTORE" => Instruction::FSTORE(self.parse_u8(tokens.next_or_err()?)?),
-                    "DSTORE" => Instruction::DSTORE(self.parse_u8(tokens.next_or_err()?)?),
-                    "ASTORE" => Instruction::ASTORE(self.parse_u8(tokens.next_or_err()?)?),
-                    "ISTORE_0" => Instruction::ISTORE_0,
-                    "ISTORE_1" => Instruction::ISTORE_1,
-                    "ISTORE_2" => Instruction::ISTORE_2,
-                    "ISTORE_3" => Instruction::ISTORE_3,
-                    "LSTORE_0" => Instruction::LSTORE_0,
-                    "LSTORE_1" => Instruction::LSTORE_1,
-                    "LSTORE_2" => Instruction::LSTORE_2,
-                    "LSTORE_3" => Instruction::LSTORE_3,
-                    "FSTORE_0" => Instruction::FSTORE_0,
-                    "FSTORE_1" => Instruction::FSTORE_1,
-                    "FSTORE_2" => Instruction::FSTORE_2,
-                    "FSTORE_3" => Instruction::FSTORE_3,
-                    "DSTORE_0" => Instruction::DSTORE_0,
-                    "DSTORE_1" => Instruction::DSTORE_1,
-                    "DSTORE_2" => Instruction::DSTORE_2,
-                    "DSTORE_3" => Instruction::DSTORE_3,
-                    "ASTORE_0" => Instruction::ASTORE_0,
-                    "ASTORE_1" => Instruction::ASTORE_1,
-                    "ASTORE_2" => Instruction::ASTORE_2,
-                    "ASTORE_3" => Instruction::ASTORE_3,
-                    "POP" => Instruction::POP,
-                    "POP2" => Instruction::POP2,
-                    "DUP" => Instruction::DUP,
-                    "DUP_X1" => Instruction::DUP_X1,
-                    "DUP_X2" => Instruction::DUP_X2,
-                    "DUP2" => Instruction::DUP2,
-                    "DUP2_X1" => Instruction::DUP2_X1,
-                    "DUP2_X2" => Instruction::DUP2_X2,
-                    "SWAP" => Instruction::SWAP,
-                    "IADD" => Instruction::IADD,
-                    "LADD" => Instruction::LADD,
-                    "FADD" => Instruction::FADD,
-                    "DADD" => Instruction::DADD,
-                    "ISUB" => Instruction::ISUB,
-                    "LSUB" => Instruction::LSUB,
-                    "FSUB" => Instruction::FSUB,
-                    "DSUB" => Instruction::DSUB,
-                    "IMUL" => Instruction::IMUL,
-                    "LMUL" => Instruction::LMUL,
-                    "FMUL" => Instruction::FMUL,
-                    "DMUL" => Instruction::DMUL,
-                    "IDIV" => Instruction::IDIV,
-                    "LDIV" => Instruction::LDIV,
-                    "FDIV" => Instruction::FDIV,
-                    "DDIV" => Instruction::DDIV,
-                    "IREM" => Instruction::IREM,
-                    "LREM" => Instruction::LREM,
-                    "FREM" => Instruction::FREM,
-                    "DREM" => Instruction::DREM,
-                    "INEG" => Instruction::INEG,
-                    "LNEG" => Instruction::LNEG,
-                    "FNEG" => Instruction::FNEG,
-                    "DNEG" => Instruction::DNEG,
-                    "ISHL" => Instruction::ISHL,
-                    "LSHL" => Instruction::LSHL,
-                    "ISHR" => Instruction::ISHR,
-                    "LSHR" => Instruction::LSHR,
-                    "IUSHR" => Instruction::IUSHR,
-                    "LUSHR" => Instruction::LUSHR,
-                    "IAND" => Instruction::IAND,
-                    "LAND" => Instruction::LAND,
-                    "IOR" => Instruction::IOR,
-                    "LOR" => Instruction::LOR,
-                    "IXOR" => Instruction::IXOR,
-                    "LXOR" => Instruction::LXOR,
-                    "IINC" => Instruction::IINC(
-                        self.parse_u8(tokens.next_or_err()?)?,
-                        self.parse_i8(tokens.next_or_err()?)?,
-                    ),
-                    "I2L" => Instruction::I2L,
-                    "I2F" => Instruction::I2F,
-                    "I2D" => Instruction::I2D,
-                    "L2I" => Instruction::L2I,
-                    "L2F" => Instruction::L2F,
-                    "L2D" => Instruction::L2D,
-                    "F2I" => Instruction::F2I,
-                    "F2L" => Instruction::F2L,
-                    "F2D" => Instruction::F2D,
-                    "D2I" => Instruction::D2I,
-                    "D2L" => Instruction::D2L,
-                    "D2F" => Instruction::D2F,
-                    "LCMP" => Instruction::LCMP,
-                    "FCMPL" => Instruction::FCMPL,
-                    "FCMPG" => Instruction::FCMPG,
-                    "DCMPL" => Instruction::DCMPL,
-                    "DCMPG" => Instruction::DCMPG,
-                    "IFEQ" => Instruction::IFEQ(self.parse_i16(tokens.next_or_err()?)?),
-                    "IFNE" => Instruction::IFNE(self.parse_i16(tokens.next_or_err()?)?),
-                    "IFLT" => Instruction::IFLT(self.parse_i16(tokens.next_or_err()?)?),
-                    "IFGE" => Instruction::IFGE(self.parse_i16(tokens.next_or_err()?)?),
-                    "IFGT" => Instruction::IFGT(self.parse_i16(tokens.next_or_err()?)?),
-                    "IFLE" => Instruction::IFLE(self.parse_i16(tokens.next_or_err()?)?),
-                    "IF_ICMPEQ" => Instruction::IF_ICMPEQ(self.parse_i16(tokens.next_or_err()?)?),
-                    "IF_ICMPNE" => Instruction::IF_ICMPNE(self.parse_i16(tokens.next_or_err()?)?),
-                    "IF_ICMPLT" => Instruction::IF_ICMPLT(self.parse_i16(tokens.next_or_err()?)?),
-                    "IF_ICMPGE" => Instruction::IF_ICMPGE(self.parse_i16(tokens.next_or_err()?)?),
-                    "IF_ICMPGT" => Instruction::IF_ICMPGT(self.parse_i16(tokens.next_or_err()?)?),
-                    "IF_ICMPLE" => Instruction::IF_ICMPLE(self.parse_i16(tokens.next_or_err()?)?),
-                    "IF_ACMPEQ" => Instruction::IF_ACMPEQ(self.parse_i16(tokens.next_or_err()?)?),
-                    "IF_ACMPNE" => Instruction::IF_ACMPNE(self.parse_i16(tokens.next_or_err()?)?),
-                    "GOTO" => Instruction::GOTO(self.parse_i16(tokens.next_or_err()?)?),
-                    "IFNULL" => Instruction::IFNULL(self.parse_i16(tokens.next_or_err()?)?),
-                    "IFNONNULL" => Instruction::IFNONNULL(self.parse_i16(tokens.next_or_err()?)?),
-                    "IRETURN" => Instruction::IRETURN,
-                    "LRETURN" => Instruction::LRETURN,
-                    "FRETURN" => Instruction::FRETURN,
-                    "DRETURN" => Instruction::DRETURN,
-                    "ARETURN" => Instruction::ARETURN,
-                    "RETURN" => Instruction::RETURN,
-                    "GETSTATIC" => {
-                        let t = self.parse_type_desc(tokens.next_or_err()?)?;
-                        let c = self.parse_class_name(tokens.next_or_err()?)?;
-                        let n = self.parse_field_name(tokens.next_or_err()?)?;
-                        Instruction::GETSTATIC(FieldRef::new(c, FieldSig::new(t, n)))
-                    }
-                    "PUTSTATIC" => {
-                        let t = self.parse_type_desc(tokens.next_or_err()?)?;
-                        let c = self.parse_class_name(tokens.next_or_err()?)?;
-                        let n = self.parse_field_name(tokens.next_or_err()?)?;
-                        Instruction::PUTSTATIC(FieldRef::new(c, FieldSig::new(t, n)))
-                    }
-                    "GETFIELD" => {
-                        let t = self.parse_type_desc(tokens.next_or_err()?)?;
-                        let c = self.parse_class_name(tokens.next_or_err()?)?;
-                        let n = self.parse_field_name(tokens.next_or_err()?)?;
-                        Instruction::GETFIELD(FieldRef::new(c, FieldSig::new(t, n)))
-                    }
-                    "PUTFIELD" => {
-                        let t = self.parse_type_desc(tokens.next_or_err()?)?;
-                        let c = self.parse_class_name(tokens.next_or_err()?)?;
-                        let n = self.parse_field_name(tokens.next_or_err()?)?;
-                        Instruction::PUTFIELD(FieldRef::new(c, FieldSig::new(t, n)))
-                    }
-                    "INVOKEVIRTUAL" => {
-                        let r = self.parse_return_desc(tokens.next_or_err()?)?;
-                        let c = self.parse_class_name(tokens.next_or_err()?)?;
-                        let n = self.parse_method_name(tokens.next_or_err()?)?;
-                        let p = self.parse_method_params(tokens.next_or_err()?)?;
-                        Instruction::INVOKEVIRTUAL(MethodRef::new(c, MethodSig::new(r, n, p)?))
-                    }
-                    "INVOKESTATIC" => {
-                        let r = self.parse_return_desc(tokens.next_or_err()?)?;
-                        let c = self.parse_class_name(tokens.next_or_err()?)?;
-                        let n = self.parse_method_name(tokens.next_or_err()?)?;
-                        let p = self.parse_method_params(tokens.next_or_err()?)?;
-                        Instruction::INVOKESTATIC(MethodRef::new(c, MethodSig::new(r, n, p)?))
-                    }
-                    "NEW" => Instruction::NEW(self.parse_class_name(tokens.next_or_err()?)?),
-                    _ => return Err(ParseClassErrorKind::UnknownInstruction(name.into())),
-                },
+            }
+        }
+
+        // resolve branch targets given by labels
+        for branch in branches {
+            let target = labels.iter()
+                               .find(|(_, label)| *label == branch.label)
+                               .map(|(index, _)| *index);
+
+            let target = match target {
+                None => {
+                    self.report(branch.error(ParseClassErrorKind::UndefinedLabel(branch.label.clone())));
+                    valid = false;
+                    continue;
+                }
+                Some(target) => target,
             };
 
-            if tokens.next().is_some() {
-                return Err(ParseClassErrorKind::InvalidInstructionDefinition(
-                    line.into(),
-                ));
+            match i16::try_from(target as isize - branch.index as isize) {
+                Ok(offset) => instructions[branch.index] = (branch.instruction)(offset),
+                Err(_) => {
+                    self.report(branch.error(ParseClassErrorKind::LabelOutOfRange(branch.label.clone())));
+                    valid = false;
+                }
             }
+        }
 
-            instructions.push(instruction);
+        if !valid {
+            return None;
         }
 
-        if !ended {
-            self.next_line_or_err()?;
+        Some((instructions, labels, lines, source_lines))
+    }
+
+    /// Parse a single line of the method body - an instruction
+    /// optionally preceded by a label definition, a sole label definition,
+    /// a `LINE` entry of the line-number table or the keyword `END`.
+    /// Returns true if the end of the method was reached.
+    ///
+    /// An invalid instruction is replaced by `NOP` to keep the
+    /// indexes of the following instructions.
+    fn parse_instruction_line(&self,
+                              line: &str,
+                              instructions: &mut Vec<Instruction>,
+                              labels: &mut Labels,
+                              source_lines: &mut SourceLines,
+                              branches: &mut Vec<Branch>) -> Result<bool, ParseClassError> {
+        let mut tokens = Tokens::whitespaces(line);
+        let mut token = self.next_token(&mut tokens)?;
+
+        // the following instructions were compiled from the given line of the Java source
+        if token == "LINE" {
+            let number = self.next_token(&mut tokens)?;
+            let source_line = self.parse_usize(number)?;
+
+            if source_line == 0 {
+                return Err(self.error(ParseClassErrorKind::InvalidSourceLine(number.into()), number));
+            }
+
+            if let Some(token) = tokens.next() {
+                return Err(self.error(ParseClassErrorKind::UnexpectedToken(token.into()), token));
+            }
+
+            source_lines.retain(|(index, _)| *index != instructions.len());
+            source_lines.push((instructions.len(), source_line));
+            return Ok(false);
+        }
+
+        // label definition, optionally followed by the labeled instruction
+        if token.ends_with(':') {
+            let name = &token[..(token.len() - 1)];
+            let label = self.parse_label(name)?;
+
+            if labels.iter().any(|(_, defined)| *defined == label) {
+                return Err(self.error(ParseClassErrorKind::DuplicateLabel(label), name));
+            }
+
+            labels.push((instructions.len(), label));
+
+            token = match tokens.next() {
+                None => return Ok(false),
+                Some(token) => token,
+            };
+        }
+
+        if token == "END" {
+            return Ok(true);
+        }
+
+        let index = instructions.len();
+        instructions.push(Instruction::NOP);
+        instructions[index] = self.parse_instruction(token, tokens, line, index, branches)?;
+
+        Ok(false)
+    }
+
+    /// Parse the only instruction line of the input.
+    fn parse_single_instruction_line(&self) -> Result<Instruction, ParseClassError> {
+        let line = self.next_line_or_err()?;
+        let mut tokens = Tokens::whitespaces(line);
+        let name = self.next_token(&mut tokens)?;
+
+        let mut branches = Vec::new();
+        let instruction = self.parse_instruction(name, tokens, line, 0, &mut branches)?;
+
+        if let Some(branch) = branches.pop() {
+            return Err(branch.error(ParseClassErrorKind::UndefinedLabel(branch.label.clone())));
+        }
+
+        if let Some(line) = self.next_line() {
+            return Err(self.error(ParseClassErrorKind::UnexpectedToken(line.into()), line));
+        }
+
+        Ok(instruction)
+    }
+
+    /// Parse an instruction of the given name with operands given by the remaining tokens.
+    fn parse_instruction<'t>(&self,
+                             name: &str,
+                             mut tokens: Tokens<'t, SplitWhitespace<'t>>,
+                             line: &str,
+                             index: usize,
+                             branches: &mut Vec<Branch>) -> Result<Instruction, ParseClassError> {
+        let instruction = match name {
+            "NOP" => Instruction::NOP,
+            "ACONST_NULL" => Instruction::ACONST_NULL,
+            "ICONST_M1" => Instruction::ICONST_M1,
+            "ICONST_0" => Instruction::ICONST_0,
+            "ICONST_1" => Instruction::ICONST_1,
+            "ICONST_2" => Instruction::ICONST_2,
+            "ICONST_3" => Instruction::ICONST_3,
+            "ICONST_4" => Instruction::ICONST_4,
+            "ICONST_5" => Instruction::ICONST_5,
+            "LCONST_0" => Instruction::LCONST_0,
+            "LCONST_1" => Instruction::LCONST_1,
+            "FCONST_0" => Instruction::FCONST_0,
+            "FCONST_1" => Instruction::FCONST_1,
+            "FCONST_2" => Instruction::FCONST_2,
+            "DCONST_0" => Instruction::DCONST_0,
+            "DCONST_1" => Instruction::DCONST_1,
+            "BIPUSH" => Instruction::BIPUSH(self.parse_i8(self.next_token(&mut tokens)?)?),
+            "SIPUSH" => Instruction::SIPUSH(self.parse_i16(self.next_token(&mut tokens)?)?),
+            "LDC" => Instruction::LDC(self.parse_ldc_arg(self.next_token(&mut tokens)?)?),
+            "LDC_W" => Instruction::LDC_W(self.parse_ldc_arg(self.next_token(&mut tokens)?)?),
+            "LDC2_W" => Instruction::LDC2_W(self.parse_ldc2_arg(self.next_token(&mut tokens)?)?),
+            "ILOAD" => Instruction::ILOAD(self.parse_u8(self.next_token(&mut tokens)?)?),
+            "LLOAD" => Instruction::LLOAD(self.parse_u8(self.next_token(&mut tokens)?)?),
+            "FLOAD" => Instruction::FLOAD(self.parse_u8(self.next_token(&mut tokens)?)?),
+            "DLOAD" => Instruction::DLOAD(self.parse_u8(self.next_token(&mut tokens)?)?),
+            "ALOAD" => Instruction::ALOAD(self.parse_u8(self.next_token(&mut tokens)?)?),
+            "ILOAD_0" => Instruction::ILOAD_0,
+            "ILOAD_1" => Instruction::ILOAD_1,
+            "ILOAD_2" => Instruction::ILOAD_2,
+            "ILOAD_3" => Instruction::ILOAD_3,
+            "LLOAD_0" => Instruction::LLOAD_0,
+            "LLOAD_1" => Instruction::LLOAD_1,
+            "LLOAD_2" => Instruction::LLOAD_2,
+            "LLOAD_3" => Instruction::LLOAD_3,
+            "FLOAD_0" => Instruction::FLOAD_0,
+            "FLOAD_1" => Instruction::FLOAD_1,
+            "FLOAD_2" => Instruction::FLOAD_2,
+            "FLOAD_3" => Instruction::FLOAD_3,
+            "DLOAD_0" => Instruction::DLOAD_0,
+            "DLOAD_1" => Instruction::DLOAD_1,
+            "DLOAD_2" => Instruction::DLOAD_2,
+            "DLOAD_3" => Instruction::DLOAD_3,
+            "ALOAD_0" => Instruction::ALOAD_0,
+            "ALOAD_1" => Instruction::ALOAD_1,
+            "ALOAD_2" => Instruction::ALOAD_2,
+            "ALOAD_3" => Instruction::ALOAD_3,
+            "ISTORE" => Instruction::ISTORE(self.parse_u8(self.next_token(&mut tokens)?)?),
+            "LSTORE" => Instruction::LSTORE(self.parse_u8(self.next_token(&mut tokens)?)?),
+            "FSTORE" => Instruction::FSTORE(self.parse_u8(self.next_token(&mut tokens)?)?),
+            "DSTORE" => Instruction::DSTORE(self.parse_u8(self.next_token(&mut tokens)?)?),
+            "ASTORE" => Instruction::ASTORE(self.parse_u8(self.next_token(&mut tokens)?)?),
+            "ISTORE_0" => Instruction::ISTORE_0,
+            "ISTORE_1" => Instruction::ISTORE_1,
+            "ISTORE_2" => Instruction::ISTORE_2,
+            "ISTORE_3" => Instruction::ISTORE_3,
+            "LSTORE_0" => Instruction::LSTORE_0,
+            "LSTORE_1" => Instruction::LSTORE_1,
+            "LSTORE_2" => Instruction::LSTORE_2,
+            "LSTORE_3" => Instruction::LSTORE_3,
+            "FSTORE_0" => Instruction::FSTORE_0,
+            "FSTORE_1" => Instruction::FSTORE_1,
+            "FSTORE_2" => Instruction::FSTORE_2,
+            "FSTORE_3" => Instruction::FSTORE_3,
+            "DSTORE_0" => Instruction::DSTORE_0,
+            "DSTORE_1" => Instruction::DSTORE_1,
+            "DSTORE_2" => Instruction::DSTORE_2,
+            "DSTORE_3" => Instruction::DSTORE_3,
+            "ASTORE_0" => Instruction::ASTORE_0,
+            "ASTORE_1" => Instruction::ASTORE_1,
+            "ASTORE_2" => Instruction::ASTORE_2,
+            "ASTORE_3" => Instruction::ASTORE_3,
+            "POP" => Instruction::POP,
+            "POP2" => Instruction::POP2,
+            "DUP" => Instruction::DUP,
+            "DUP_X1" => Instruction::DUP_X1,
+            "DUP_X2" => Instruction::DUP_X2,
+            "DUP2" => Instruction::DUP2,
+            "DUP2_X1" => Instruction::DUP2_X1,
+            "DUP2_X2" => Instruction::DUP2_X2,
+            "SWAP" => Instruction::SWAP,
+            "IADD" => Instruction::IADD,
+            "LADD" => Instruction::LADD,
+            "FADD" => Instruction::FADD,
+            "DADD" => Instruction::DADD,
+            "ISUB" => Instruction::ISUB,
+            "LSUB" => Instruction::LSUB,
+            "FSUB" => Instruction::FSUB,
+            "DSUB" => Instruction::DSUB,
+            "IMUL" => Instruction::IMUL,
+            "LMUL" => Instruction::LMUL,
+            "FMUL" => Instruction::FMUL,
+            "DMUL" => Instruction::DMUL,
+            "IDIV" => Instruction::IDIV,
+            "LDIV" => Instruction::LDIV,
+            "FDIV" => Instruction::FDIV,
+            "DDIV" => Instruction::DDIV,
+            "IREM" => Instruction::IREM,
+            "LREM" => Instruction::LREM,
+            "FREM" => Instruction::FREM,
+            "DREM" => Instruction::DREM,
+            "INEG" => Instruction::INEG,
+            "LNEG" => Instruction::LNEG,
+            "FNEG" => Instruction::FNEG,
+            "DNEG" => Instruction::DNEG,
+            "ISHL" => Instruction::ISHL,
+            "LSHL" => Instruction::LSHL,
+            "ISHR" => Instruction::ISHR,
+            "LSHR" => Instruction::LSHR,
+            "IUSHR" => Instruction::IUSHR,
+            "LUSHR" => Instruction::LUSHR,
+            "IAND" => Instruction::IAND,
+            "LAND" => Instruction::LAND,
+            "IOR" => Instruction::IOR,
+            "LOR" => Instruction::LOR,
+            "IXOR" => Instruction::IXOR,
+            "LXOR" => Instruction::LXOR,
+            "IINC" => Instruction::IINC(
+                self.parse_u8(self.next_token(&mut tokens)?)?,
+                self.parse_i8(self.next_token(&mut tokens)?)?,
+            ),
+            "I2L" => Instruction::I2L,
+            "I2F" => Instruction::I2F,
+            "I2D" => Instruction::I2D,
+            "L2I" => Instruction::L2I,
+            "L2F" => Instruction::L2F,
+            "L2D" => Instruction::L2D,
+            "F2I" => Instruction::F2I,
+            "F2L" => Instruction::F2L,
+            "F2D" => Instruction::F2D,
+            "D2I" => Instruction::D2I,
+            "D2L" => Instruction::D2L,
+            "D2F" => Instruction::D2F,
+            "LCMP" => Instruction::LCMP,
+            "FCMPL" => Instruction::FCMPL,
+            "FCMPG" => Instruction::FCMPG,
+            "DCMPL" => Instruction::DCMPL,
+            "DCMPG" => Instruction::DCMPG,
+            "IFEQ" => self.parse_branch(Instruction::IFEQ, self.next_token(&mut tokens)?, index, branches)?,
+            "IFNE" => self.parse_branch(Instruction::IFNE, self.next_token(&mut tokens)?, index, branches)?,
+            "IFLT" => self.parse_branch(Instruction::IFLT, self.next_token(&mut tokens)?, index, branches)?,
+            "IFGE" => self.parse_branch(Instruction::IFGE, self.next_token(&mut tokens)?, index, branches)?,
+            "IFGT" => self.parse_branch(Instruction::IFGT, self.next_token(&mut tokens)?, index, branches)?,
+            "IFLE" => self.parse_branch(Instruction::IFLE, self.next_token(&mut tokens)?, index, branches)?,
+            "IF_ICMPEQ" => self.parse_branch(Instruction::IF_ICMPEQ, self.next_token(&mut tokens)?, index, branches)?,
+            "IF_ICMPNE" => self.parse_branch(Instruction::IF_ICMPNE, self.next_token(&mut tokens)?, index, branches)?,
+            "IF_ICMPLT" => self.parse_branch(Instruction::IF_ICMPLT, self.next_token(&mut tokens)?, index, branches)?,
+            "IF_ICMPGE" => self.parse_branch(Instruction::IF_ICMPGE, self.next_token(&mut tokens)?, index, branches)?,
+            "IF_ICMPGT" => self.parse_branch(Instruction::IF_ICMPGT, self.next_token(&mut tokens)?, index, branches)?,
+            "IF_ICMPLE" => self.parse_branch(Instruction::IF_ICMPLE, self.next_token(&mut tokens)?, index, branches)?,
+            "IF_ACMPEQ" => self.parse_branch(Instruction::IF_ACMPEQ, self.next_token(&mut tokens)?, index, branches)?,
+            "IF_ACMPNE" => self.parse_branch(Instruction::IF_ACMPNE, self.next_token(&mut tokens)?, index, branches)?,
+            "GOTO" => self.parse_branch(Instruction::GOTO, self.next_token(&mut tokens)?, index, branches)?,
+            "IFNULL" => self.parse_branch(Instruction::IFNULL, self.next_token(&mut tokens)?, index, branches)?,
+            "IFNONNULL" => self.parse_branch(Instruction::IFNONNULL, self.next_token(&mut tokens)?, index, branches)?,
+            "IRETURN" => Instruction::IRETURN,
+            "LRETURN" => Instruction::LRETURN,
+            "FRETURN" => Instruction::FRETURN,
+            "DRETURN" => Instruction::DRETURN,
+            "ARETURN" => Instruction::ARETURN,
+            "RETURN" => Instruction::RETURN,
+            "GETSTATIC" => {
+                let t = self.parse_type_desc(self.next_token(&mut tokens)?)?;
+                let c = self.parse_class_name(self.next_token(&mut tokens)?)?;
+                let n = self.parse_field_name(self.next_token(&mut tokens)?)?;
+                Instruction::GETSTATIC(FieldRef::new(c, FieldSig::new(t, n)))
+            }
+            "PUTSTATIC" => {
+                let t = self.parse_type_desc(self.next_token(&mut tokens)?)?;
+                let c = self.parse_class_name(self.next_token(&mut tokens)?)?;
+                let n = self.parse_field_name(self.next_token(&mut tokens)?)?;
+                Instruction::PUTSTATIC(FieldRef::new(c, FieldSig::new(t, n)))
+            }
+            "GETFIELD" => {
+                let t = self.parse_type_desc(self.next_token(&mut tokens)?)?;
+                let c = self.parse_class_name(self.next_token(&mut tokens)?)?;
+                let n = self.parse_field_name(self.next_token(&mut tokens)?)?;
+                Instruction::GETFIELD(FieldRef::new(c, FieldSig::new(t, n)))
+            }
+            "PUTFIELD" => {
+                let t = self.parse_type_desc(self.next_token(&mut tokens)?)?;
+                let c = self.parse_class_name(self.next_token(&mut tokens)?)?;
+                let n = self.parse_field_name(self.next_token(&mut tokens)?)?;
+                Instruction::PUTFIELD(FieldRef::new(c, FieldSig::new(t, n)))
+            }
+            "INVOKEVIRTUAL" => {
+                let r = self.parse_return_desc(self.next_token(&mut tokens)?)?;
+                let c = self.parse_class_name(self.next_token(&mut tokens)?)?;
+                let n = self.parse_method_name(self.next_token(&mut tokens)?)?;
+                let p = self.parse_method_params(self.next_token(&mut tokens)?)?;
+                Instruction::INVOKEVIRTUAL(MethodRef::new(c, MethodSig::new(r, n, p).map_err(|e| self.error(e, line))?))
+            }
+            "INVOKESTATIC" => {
+                let r = self.parse_return_desc(self.next_token(&mut tokens)?)?;
+                let c = self.parse_class_name(self.next_token(&mut tokens)?)?;
+                let n = self.parse_method_name(self.next_token(&mut tokens)?)?;
+                let p = self.parse_method_params(self.next_token(&mut tokens)?)?;
+                Instruction::INVOKESTATIC(MethodRef::new(c, MethodSig::new(r, n, p).map_err(|e| self.error(e, line))?))
+            }
+            "NEW" => Instruction::NEW(self.parse_class_name(self.next_token(&mut tokens)?)?),
+            _ => return Err(self.error(ParseClassErrorKind::UnknownInstruction(name.into()), name)),
+        };
+
+        if let Some(token) = tokens.next() {
+            return Err(self.error(ParseClassErrorKind::InvalidInstructionDefinition(line.into()), token));
         }
 
-        Ok(instructions)
+        Ok(instruction)
     }
 
-    fn parse_class_name(&self, name: &str) -> Result<ClassName, ParseClassErrorKind> {
-        let name = ClassName::new(name)?;
-        Ok(name)
+    /// Parse a branch instruction target which is either a relative offset
+    /// or a label. Label targets are recorded in `branches` to be resolved
+    /// once all labels are known and the instruction is created with a zero offset.
+    fn parse_branch(&self,
+                    instruction: fn(i16) -> Instruction,
+                    target: &str,
+                    index: usize,
+                    branches: &mut Vec<Branch>) -> Result<Instruction, ParseClassError> {
+        if !Self::label_regex().is_match(target) {
+            return Ok(instruction(self.parse_i16(target)?));
+        }
+
+        branches.push(Branch {
+            index,
+            label: target.into(),
+            instruction,
+            span: self.span(target),
+            line: self.input.current_line().into(),
+        });
+
+        Ok(instruction(0))
     }
 
-    fn parse_method_name(&self, name: &str) -> Result<MethodName, ParseClassErrorKind> {
-        let name = MethodName::new(name)?;
-        Ok(name)
+    fn parse_label(&self, label: &str) -> Result<String, ParseClassError> {
+        if !Self::label_regex().is_match(label) {
+            return Err(self.error(ParseClassErrorKind::InvalidLabel(label.into()), label));
+        }
+
+        Ok(label.into())
     }
 
-    fn parse_field_name(&self, name: &str) -> Result<FieldName, ParseClassErrorKind> {
-        let name = FieldName::new(name)?;
-        Ok(name)
+    fn label_regex() -> &'static Regex {
+        lazy_static! {
+            static ref REGEX: Regex = Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap();
+        }
+
+        &REGEX
     }
 
-    fn parse_type_desc(&self, desc: &str) -> Result<TypeDesc, ParseClassErrorKind> {
+    fn parse_class_name(&self, name: &str) -> Result<ClassName, ParseClassError> {
+        ClassName::new(name).map_err(|e| self.error(e, name))
+    }
+
+    fn parse_method_name(&self, name: &str) -> Result<MethodName, ParseClassError> {
+        MethodName::new(name).map_err(|e| self.error(e, name))
+    }
+
+    fn parse_field_name(&self, name: &str) -> Result<FieldName, ParseClassError> {
+        FieldName::new(name).map_err(|e| self.error(e, name))
+    }
+
+    fn parse_type_desc(&self, desc: &str) -> Result<TypeDesc, ParseClassError> {
         if desc.is_empty() {
-            return Err(ParseClassErrorKind::EmptyTypeDescriptor);
+            return Err(self.error(ParseClassErrorKind::EmptyTypeDescriptor, desc));
         }
 
         Ok(match desc {
@@ -427,9 +777,9 @@ impl<'a> ClassFileParser<'a> {
         })
     }
 
-    fn parse_return_desc(&self, desc: &str) -> Result<ReturnDesc, ParseClassErrorKind> {
+    fn parse_return_desc(&self, desc: &str) -> Result<ReturnDesc, ParseClassError> {
         if desc.is_empty() {
-            return Err(ParseClassErrorKind::EmptyTypeDescriptor);
+            return Err(self.error(ParseClassErrorKind::EmptyTypeDescriptor, desc));
         }
 
         Ok(match desc {
@@ -438,9 +788,9 @@ impl<'a> ClassFileParser<'a> {
         })
     }
 
-    fn parse_method_params(&self, desc: &str) -> Result<ParamsDesc, ParseClassErrorKind> {
-        if !desc.starts_with("(") || !desc.ends_with(")") {
-            return Err(ParseClassErrorKind::InvalidParamsDescriptor(desc.into()));
+    fn parse_method_params(&self, desc: &str) -> Result<ParamsDesc, ParseClassError> {
+        if !desc.starts_with('(') || !desc.ends_with(')') || desc.len() < 2 {
+            return Err(self.error(ParseClassErrorKind::InvalidParamsDescriptor(desc.into()), desc));
         }
 
         let desc = &desc[1..(desc.len() - 1)];
@@ -449,57 +799,85 @@ impl<'a> ClassFileParser<'a> {
             return Ok(ParamsDesc::empty());
         }
 
-        let tokens = desc.split(",");
+        let tokens = desc.split(',');
 
         tokens.map(|t| self.parse_type_desc(t)).collect()
     }
 
-    fn parse_u8(&self, i: &str) -> Result<u8, ParseNumberError> {
-        let n = i.parse::<u8>()?;
-        Ok(n)
+    fn parse_u8(&self, i: &str) -> Result<u8, ParseClassError> {
+        i.parse::<u8>().map_err(|e| self.error(ParseNumberError::from(e), i))
     }
 
-    fn parse_i8(&self, i: &str) -> Result<i8, ParseNumberError> {
-        let n = i.parse::<i8>()?;
-        Ok(n)
+    fn parse_i8(&self, i: &str) -> Result<i8, ParseClassError> {
+        i.parse::<i8>().map_err(|e| self.error(ParseNumberError::from(e), i))
     }
 
-    fn parse_i16(&self, i: &str) -> Result<i16, ParseNumberError> {
-        let n = i.parse::<i16>()?;
-        Ok(n)
+    fn parse_usize(&self, i: &str) -> Result<usize, ParseClassError> {
+        i.parse::<usize>().map_err(|e| self.error(ParseNumberError::from(e), i))
     }
 
-    fn parse_ldc_arg(&self, i: &str) -> Result<LdcArg, ParseNumberError> {
+    fn parse_i16(&self, i: &str) -> Result<i16, ParseClassError> {
+        i.parse::<i16>().map_err(|e| self.error(ParseNumberError::from(e), i))
+    }
+
+    fn parse_ldc_arg(&self, i: &str) -> Result<LdcArg, ParseClassError> {
         match i.parse::<i32>() {
             Ok(n) => Ok(LdcArg::Int(n)),
             Err(ei) => match i.parse::<f32>() {
                 Ok(n) => Ok(LdcArg::Float(n)),
-                Err(ef) => Err(ParseNumberError::NotIntOrFloat {
+                Err(ef) => Err(self.error(ParseNumberError::NotIntOrFloat {
                     i_error: ei,
                     f_error: ef,
-                }),
+                }, i)),
             },
         }
     }
 
-    fn parse_ldc2_arg(&self, i: &str) -> Result<Ldc2Arg, ParseNumberError> {
+    fn parse_ldc2_arg(&self, i: &str) -> Result<Ldc2Arg, ParseClassError> {
         match i.parse::<i64>() {
             Ok(n) => Ok(Ldc2Arg::Long(n)),
             Err(ei) => match i.parse::<f64>() {
                 Ok(n) => Ok(Ldc2Arg::Double(n)),
-                Err(ef) => Err(ParseNumberError::NotLongOrDouble {
+                Err(ef) => Err(self.error(ParseNumberError::NotLongOrDouble {
                     i_error: ei,
                     f_error: ef,
-                }),
+                }, i)),
             },
         }
     }
 }
 
 
+/// Labels of instructions as pairs of the instruction index and the label name.
+type Labels = Vec<(usize, String)>;
+
+
+/// Line-number table of the Java source as pairs of the index of the first
+/// instruction compiled from a source line and the line.
+type SourceLines = Vec<(usize, usize)>;
+
+
+/// A branch instruction waiting for its target label to be resolved.
+struct Branch {
+    index: usize,
+    label: String,
+    instruction: fn(i16) -> Instruction,
+    span: Span,
+    line: String,
+}
+
+
+impl Branch {
+    /// Creates an error of the given kind pointing to the branch target.
+    fn error(&self, kind: ParseClassErrorKind) -> ParseClassError {
+        ParseClassError::new(kind, self.span, self.line.clone())
+    }
+}
+
+
 /// Helper struct for input tokenized into lines.
 struct Input<'a> {
-    lines: RefCell<Lines<'a>>,
+    lines: Vec<&'a str>,
     line_pos: Cell<usize>,
 }
 
@@ -508,27 +886,43 @@ impl<'a> Input<'a> {
     /// Create new input by tokenizing the input into lines.
     fn new(input: &'a str) -> Self {
         Input {
-            lines: RefCell::new(input.lines()),
+            lines: input.lines().collect(),
             line_pos: Cell::new(0),
         }
     }
 
     /// Get next line from input and update current line.
     /// Returns `None`, if there are no more lines.
-    /// [None][None]
-    fn next_line(&self) -> Option<&str> {
-        let line = self.lines.borrow_mut().next();
+    fn next_line(&self) -> Option<&'a str> {
+        let line = self.lines.get(self.line_pos.get()).cloned();
 
-        if let Some(_) = line {
+        if line.is_some() {
             self.line_pos.set(self.line_pos.get() + 1);
         }
 
         line
     }
 
+    /// Return the current line back to the input so that
+    /// it is returned by the next call of `next_line`.
+    fn unread_line(&self) {
+        if self.line_pos.get() > 0 {
+            self.line_pos.set(self.line_pos.get() - 1);
+        }
+    }
+
+    /// Returns the number of the current line, starting from 1.
     fn line_pos(&self) -> usize {
         self.line_pos.get()
     }
+
+    /// Returns the current line or an empty string if no line was read yet.
+    fn current_line(&self) -> &'a str {
+        match self.line_pos.get() {
+            0 => "",
+            pos => self.lines[pos - 1],
+        }
+    }
 }
 
 
@@ -549,16 +943,6 @@ impl<'a, I> Tokens<'a, I>
     fn next(&mut self) -> Option<&'a str> {
         self.tokens.next()
     }
-
-    /// Returns next available token.
-    ///
-    /// # Errors
-    ///
-    /// Returns `ParseClassErrorKind::UnexpectedEndOfLine` if there
-    /// are no more available tokens.
-    fn next_or_err(&mut self) -> Result<&'a str, ParseClassErrorKind> {
-        self.next().ok_or(ParseClassErrorKind::UnexpectedEndOfLine)
-    }
 }
 
 
@@ -569,3 +953,154 @@ impl<'a> Tokens<'a, SplitWhitespace<'a>> {
         }
     }
 }
+
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryInto;
+
+    use crate::vm::bytecode::instruction::Instruction;
+    use crate::vm::class::class::Class;
+    use crate::vm::class::descriptor::{ParamsDesc, ReturnDesc};
+    use crate::vm::class::name::MethodName;
+    use crate::vm::class::signature::MethodSig;
+    use crate::vm::parse::error::{CreateClassError, Span};
+    use crate::vm::parse::parser::ClassFileParser;
+
+
+    fn parse_main(body: &str) -> Result<Class, String> {
+        let source = format!("Test\nMETHOD\nstatic void main () 1\n{}\nEND\n", body);
+        let info = ClassFileParser::new(&source).parse().map_err(|e| e.to_string())?;
+        info.try_into().map_err(|e: CreateClassError| e.to_string())
+    }
+
+
+    fn main_sig() -> MethodSig {
+        MethodSig::new(ReturnDesc::Void, MethodName::new("main").unwrap(), ParamsDesc::empty()).unwrap()
+    }
+
+
+    #[test]
+    fn labels() {
+        let class = parse_main("
+            ICONST_5
+            ISTORE_0
+        loop:
+            IINC 0 -1
+            ILOAD_0
+            IFNE loop
+            GOTO end
+            NOP
+        end: RETURN
+        ").unwrap();
+
+        let code = class.static_method(&main_sig()).unwrap().code().clone();
+        let instructions: Vec<_> = code.instructions().cloned().collect();
+
+        assert_eq!(instructions[4], Instruction::IFNE(-2));
+        assert_eq!(instructions[5], Instruction::GOTO(2));
+        assert_eq!(code.labels_at(2).collect::<Vec<_>>(), vec!["loop"]);
+        assert_eq!(code.labels_at(7).collect::<Vec<_>>(), vec!["end"]);
+        assert!(code.instruction_str(4).unwrap().ends_with(" loop"));
+        assert_eq!(code.line(0), Some(5));
+        assert_eq!(code.line(2), Some(8));
+        assert_eq!(code.line(7), Some(13));
+        assert_eq!(code.index_at_line(7), None);
+    }
+
+
+    #[test]
+    fn source_lines() {
+        let source = "Test\nSOURCE Test.java\nMETHOD\nstatic void main () 1\nLINE 3\nICONST_1\nISTORE_0\nLINE 4\nloop: IINC 0 1\nRETURN\nEND\n";
+        let info = ClassFileParser::new(source).parse().unwrap();
+        let class: Class = info.try_into().unwrap();
+        let code = class.static_method(&main_sig()).unwrap().code().clone();
+
+        assert_eq!(class.source_file(), Some("Test.java"));
+        assert_eq!(code.instructions().len(), 4);
+        assert_eq!(code.source_line(0), Some(3));
+        assert_eq!(code.source_line(1), Some(3));
+        assert_eq!(code.source_line(2), Some(4));
+        assert_eq!(code.source_line(3), Some(4));
+        assert_eq!(code.source_line(4), None);
+        assert_eq!(code.line(2), Some(9));
+
+        let class = parse_main("NOP\nLINE 70000\nRETURN").unwrap();
+        let code = class.static_method(&main_sig()).unwrap().code().clone();
+
+        assert_eq!(class.source_file(), None);
+        assert_eq!(code.source_line(0), None);
+        assert_eq!(code.source_line(1), Some(70000));
+
+        parse_main("LINE\nRETURN").expect_err("missing line number should not be accepted");
+        parse_main("LINE 1 2\nRETURN").expect_err("extra tokens should not be accepted");
+        parse_main("LINE 0\nRETURN").expect_err("lines are numbered from 1");
+        ClassFileParser::new("Test\nSOURCE\n").parse().expect_err("missing source file should not be accepted");
+    }
+
+
+    #[test]
+    fn numeric_offsets() {
+        let class = parse_main("GOTO 1\nRETURN").unwrap();
+        let code = class.static_method(&main_sig()).unwrap().code().clone();
+
+        assert_eq!(*code.instruction(0).unwrap(), Instruction::GOTO(1));
+    }
+
+
+    #[test]
+    fn invalid_labels() {
+        parse_main("GOTO missing\nRETURN").expect_err("undefined label should not be accepted");
+        parse_main("a: NOP\na: RETURN").expect_err("duplicate label should not be accepted");
+        parse_main("1a: RETURN").expect_err("invalid label name should not be accepted");
+        parse_main("RETURN\nend:").expect_err("label without instruction should not be accepted");
+    }
+
+
+    #[test]
+    fn error_recovery() {
+        let source = "Test\n\
+                      FIELD\n\
+                      int\n\
+                      METHOD\n\
+                      static void main () 1\n\
+                      \tICONST_1\n\
+                      \tIADDD\n\
+                      \tISTORE 300\n\
+                      \tGOTO nowhere\n\
+                      METHOD\n\
+                      void foo ()\n\
+                      RETURN\n\
+                      END\n";
+
+        let errors = ClassFileParser::new(source).parse().expect_err("class file should be invalid");
+        let spans: Vec<_> = errors.errors().iter().map(|e| e.span()).collect();
+
+        assert_eq!(spans, vec![
+            Span::new(3, 1, 3),
+            Span::new(7, 2, 5),
+            Span::new(8, 9, 3),
+            Span::new(10, 1, 6),
+            Span::new(11, 1, 11),
+        ]);
+
+        assert_eq!(errors.errors()[1].render(),
+                   "error: unknown instruction: IADDD\n \
+                    --> 7:2\n  \
+                    |\n\
+                    7 | \tIADDD\n  \
+                    | \t^^^^^\n");
+    }
+
+
+    #[test]
+    fn single_instruction() {
+        let parse = |line| ClassFileParser::new(line).parse_single_instruction().map_err(|e| e.to_string());
+
+        assert_eq!(parse("  DUP2_X1 // comment"), Ok(Instruction::DUP2_X1));
+        assert_eq!(parse("IFEQ -2"), Ok(Instruction::IFEQ(-2)));
+        assert!(parse("IFEQ loop").is_err());
+        assert!(parse("ICONST_1\nICONST_2").is_err());
+        assert!(parse("").is_err());
+    }
+}
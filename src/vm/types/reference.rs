@@ -56,7 +56,7 @@ impl fmt::Display for Reference {
         match self {
             Reference::Null => write!(f, "null"),
             Reference::Instance(instance) => {
-                write!(f, "{}", instance.id())
+                write!(f, "{}@{}", instance.class().name(), instance.id())
             }
         }
     }
@@ -28,7 +28,7 @@ impl ValueCategory {
 }
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum ValueType {
     Int,
     Long,
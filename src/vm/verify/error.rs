@@ -0,0 +1,104 @@
+use thiserror::Error;
+
+use crate::vm::class::descriptor::ReturnDesc;
+use crate::vm::class::signature::MethodSig;
+use crate::vm::types::value::ValueType;
+
+
+/// An error caused by a method code which did not pass the verification.
+#[derive(Error, Debug)]
+#[error("verification of method {method} failed at pc {pc} ({instruction}): {kind}")]
+pub struct VerifyError {
+    method: MethodSig,
+    pc: usize,
+    instruction: &'static str,
+    kind: Box<VerifyErrorKind>,
+}
+
+
+impl VerifyError {
+    /// Creates a new `VerifyError` of the given kind which occurred
+    /// in the given method on the instruction at `pc`.
+    pub fn new(method: MethodSig, pc: usize, instruction: &'static str, kind: VerifyErrorKind) -> Self {
+        VerifyError {
+            method,
+            pc,
+            instruction,
+            kind: Box::new(kind),
+        }
+    }
+
+    /// Returns the signature of the rejected method.
+    pub fn method(&self) -> &MethodSig {
+        &self.method
+    }
+
+    /// Returns the index of the rejected instruction.
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// Returns the mnemonic of the rejected instruction.
+    pub fn instruction(&self) -> &'static str {
+        self.instruction
+    }
+
+    /// Returns the reason of the rejection.
+    pub fn kind(&self) -> &VerifyErrorKind {
+        &self.kind
+    }
+}
+
+
+#[derive(Error, Debug)]
+pub enum VerifyErrorKind {
+    #[error("branch target {target} is out of bounds, the code length is {len}")]
+    BranchOutOfBounds {
+        target: isize,
+        len: usize,
+    },
+    #[error("execution falls off the end of the code")]
+    FallsOffEnd,
+    #[error("operand stack underflow")]
+    StackUnderflow,
+    #[error("operand stack overflow, the max size is {max}")]
+    StackOverflow {
+        max: usize
+    },
+    #[error("expected {expected} on the operand stack, found {found}")]
+    StackTypeMismatch {
+        expected: ValueType,
+        found: ValueType,
+    },
+    #[error("operation would split a value of category 2 on the operand stack")]
+    SplitValue,
+    #[error("local variable index {index} is out of bounds, locals size is {size}")]
+    LocalOutOfBounds {
+        index: usize,
+        size: usize,
+    },
+    #[error("local variable {index} is not initialized")]
+    UndefinedLocal {
+        index: usize
+    },
+    #[error("expected {expected} in local variable {index}, found {found}")]
+    LocalTypeMismatch {
+        index: usize,
+        expected: ValueType,
+        found: ValueType,
+    },
+    #[error("operand stack size differs between control flow paths: {size} and {other}")]
+    StackSizeMismatch {
+        size: usize,
+        other: usize,
+    },
+    #[error("operand stack types differ between control flow paths: {value_type} and {other}")]
+    StackMergeMismatch {
+        value_type: ValueType,
+        other: ValueType,
+    },
+    #[error("invalid return, the method returns {expected}")]
+    InvalidReturn {
+        expected: ReturnDesc
+    },
+}
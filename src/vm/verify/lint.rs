@@ -0,0 +1,466 @@
+//! Static analysis of loaded classes.
+//!
+//! Unlike the verifier, the linter reports code which is valid
+//! but most likely not intended - dead code, unused locals or references
+//! to members which are not on the class path.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::vm::bytecode::instruction::Instruction;
+use crate::vm::class::class::Class;
+use crate::vm::class::code::Code;
+use crate::vm::class::method::Method;
+use crate::vm::class::name::ClassName;
+use crate::vm::class::signature::MethodSig;
+use crate::vm::class::symbolic::{FieldRef, MethodRef};
+use crate::vm::exec::native::Native;
+use crate::vm::types::value::ValueType;
+
+
+/// A kind of a warning reported by the linter.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum LintKind {
+    UnreachableCode {
+        count: usize
+    },
+    UnusedLocal {
+        index: usize
+    },
+    NeverInvoked,
+    ClinitNeverCalled,
+    MissingClass {
+        class_name: ClassName
+    },
+    MissingField {
+        field_ref: FieldRef
+    },
+    MissingMethod {
+        method_ref: MethodRef
+    },
+}
+
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintKind::UnreachableCode { count: 1 } => write!(f, "unreachable instruction"),
+            LintKind::UnreachableCode { count } => write!(f, "{} unreachable instructions", count),
+            LintKind::UnusedLocal { index } => write!(f, "local variable slot {} is never used", index),
+            LintKind::NeverInvoked => write!(f, "method is never invoked from main"),
+            LintKind::ClinitNeverCalled => write!(f, "class initialization method is never called"),
+            LintKind::MissingClass { class_name } => write!(f, "class {} is not on the class path", class_name),
+            LintKind::MissingField { field_ref } => write!(f, "referenced field {} does not exist", field_ref),
+            LintKind::MissingMethod { method_ref } => write!(f, "referenced method {} does not exist", method_ref),
+        }
+    }
+}
+
+
+/// A warning reported by the linter.
+#[derive(Debug, Clone)]
+pub struct Lint {
+    class_name: ClassName,
+    method: MethodSig,
+    pc: Option<usize>,
+    kind: LintKind,
+}
+
+
+impl Lint {
+    /// Creates a new warning of the given kind in the given method,
+    /// optionally pointing to the instruction at `pc`.
+    pub fn new(class_name: ClassName, method: MethodSig, pc: Option<usize>, kind: LintKind) -> Self {
+        Lint {
+            class_name,
+            method,
+            pc,
+            kind,
+        }
+    }
+
+    /// Returns the name of the class containing the method.
+    pub fn class_name(&self) -> &ClassName {
+        &self.class_name
+    }
+
+    /// Returns the signature of the method.
+    pub fn method(&self) -> &MethodSig {
+        &self.method
+    }
+
+    /// Returns the index of the instruction this warning points to.
+    pub fn pc(&self) -> Option<usize> {
+        self.pc
+    }
+
+    /// Returns the kind of this warning.
+    pub fn kind(&self) -> &LintKind {
+        &self.kind
+    }
+}
+
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.pc {
+            None => write!(f, "in method {} of class {}: {}", self.method, self.class_name, self.kind),
+            Some(pc) => write!(f, "in method {} of class {} at pc {}: {}", self.method, self.class_name, pc, self.kind),
+        }
+    }
+}
+
+
+/// A linter of a set of classes.
+pub struct Linter<'a> {
+    classes: HashMap<&'a ClassName, &'a Class>,
+}
+
+
+impl<'a> Linter<'a> {
+    /// Creates a new linter of the given classes.
+    /// References to classes not contained in the set are reported as missing.
+    pub fn new<I>(classes: I) -> Self
+                  where I: IntoIterator<Item=&'a Class> {
+        Linter {
+            classes: classes.into_iter().map(|class| (class.name(), class)).collect()
+        }
+    }
+
+    /// Analyzes all the classes and returns found warnings.
+    ///
+    /// Methods are checked for being invoked from `static void main ()` of the `main_class`
+    /// or, if no main class is given, of any class which has the main method.
+    pub fn lint(&self, main_class: Option<&ClassName>) -> Vec<Lint> {
+        let mut lints = Vec::new();
+        let mut class_names: Vec<_> = self.classes.keys().cloned().collect();
+        class_names.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+        for class_name in &class_names {
+            for method in self.classes[class_name].methods() {
+                let reachable = reachable(method.code());
+                self.lint_unreachable(class_name, method, &reachable, &mut lints);
+                self.lint_locals(class_name, method, &mut lints);
+                self.lint_references(class_name, method, &reachable, &mut lints);
+            }
+        }
+
+        let roots: Vec<_> = class_names.iter()
+                                       .filter(|class_name| main_class.is_none() || main_class == Some(**class_name))
+                                       .filter_map(|class_name| {
+                                           let method = self.classes[*class_name].main_method().ok()?;
+                                           Some(((*class_name).clone(), method.signature().clone()))
+                                       })
+                                       .collect();
+
+        if roots.is_empty() {
+            return lints;
+        }
+
+        let invoked = self.invoked(roots);
+
+        for class_name in &class_names {
+            for method in self.classes[class_name].methods() {
+                if invoked.contains(&((*class_name).clone(), method.signature().clone())) {
+                    continue;
+                }
+
+                let kind = if method.signature().is_clinit() {
+                    LintKind::ClinitNeverCalled
+                } else {
+                    LintKind::NeverInvoked
+                };
+
+                lints.push(Lint::new((*class_name).clone(), method.signature().clone(), None, kind));
+            }
+        }
+
+        lints
+    }
+
+    fn lint_unreachable(&self, class_name: &ClassName, method: &Method, reachable: &[bool], lints: &mut Vec<Lint>) {
+        let mut pc = 0;
+
+        while pc < reachable.len() {
+            if reachable[pc] {
+                pc += 1;
+                continue;
+            }
+
+            let count = reachable[pc..].iter().take_while(|reachable| !**reachable).count();
+            lints.push(Lint::new(class_name.clone(), method.signature().clone(), Some(pc), LintKind::UnreachableCode { count }));
+            pc += count;
+        }
+    }
+
+    fn lint_locals(&self, class_name: &ClassName, method: &Method, lints: &mut Vec<Lint>) {
+        let mut used = vec![false; method.code().locals_size()];
+        let mut params_size = method.signature().params_desc().size();
+
+        if !method.is_static() {
+            params_size += ValueType::AnyReference.category().size();
+        }
+
+        for slot in used.iter_mut().take(params_size) {
+            *slot = true;
+        }
+
+        for instruction in method.code().instructions() {
+            if let Some((index, value_type)) = local_access(instruction) {
+                let end = (index + value_type.category().size()).min(used.len());
+
+                for slot in used.iter_mut().take(end).skip(index) {
+                    *slot = true;
+                }
+            }
+        }
+
+        for (index, _) in used.iter().enumerate().filter(|(_, used)| !**used) {
+            lints.push(Lint::new(class_name.clone(), method.signature().clone(), None, LintKind::UnusedLocal { index }));
+        }
+    }
+
+    fn lint_references(&self, class_name: &ClassName, method: &Method, reachable: &[bool], lints: &mut Vec<Lint>) {
+        for (pc, instruction) in method.code().instructions().enumerate() {
+            if !reachable[pc] {
+                continue;
+            }
+
+            let kind = match instruction {
+                Instruction::GETSTATIC(field_ref) | Instruction::PUTSTATIC(field_ref) => {
+                    self.check_field(field_ref, true)
+                }
+                Instruction::GETFIELD(field_ref) | Instruction::PUTFIELD(field_ref) => {
+                    self.check_field(field_ref, false)
+                }
+                Instruction::INVOKESTATIC(method_ref) if Native::find(method_ref).is_some() => None,
+                Instruction::INVOKESTATIC(method_ref) => self.check_method(method_ref, true),
+                Instruction::INVOKEVIRTUAL(method_ref) => self.check_method(method_ref, false),
+                Instruction::NEW(class_name) if !self.classes.contains_key(class_name) => {
+                    Some(LintKind::MissingClass { class_name: class_name.clone() })
+                }
+                _ => None,
+            };
+
+            if let Some(kind) = kind {
+                lints.push(Lint::new(class_name.clone(), method.signature().clone(), Some(pc), kind));
+            }
+        }
+    }
+
+    fn check_field(&self, field_ref: &FieldRef, is_static: bool) -> Option<LintKind> {
+        let class = match self.classes.get(field_ref.class_name()) {
+            None => return Some(LintKind::MissingClass { class_name: field_ref.class_name().clone() }),
+            Some(class) => class,
+        };
+
+        let field = if is_static {
+            class.static_field(field_ref.signature())
+        } else {
+            class.instance_field(field_ref.signature())
+        };
+
+        match field {
+            Ok(_) => None,
+            Err(_) => Some(LintKind::MissingField { field_ref: field_ref.clone() }),
+        }
+    }
+
+    fn check_method(&self, method_ref: &MethodRef, is_static: bool) -> Option<LintKind> {
+        self.method(method_ref, is_static).err()
+    }
+
+    fn method(&self, method_ref: &MethodRef, is_static: bool) -> Result<&Method, LintKind> {
+        let class = self.classes.get(method_ref.class_name())
+                        .ok_or_else(|| LintKind::MissingClass { class_name: method_ref.class_name().clone() })?;
+
+        let method = if is_static {
+            class.static_method(method_ref.signature())
+        } else {
+            class.instance_method(method_ref.signature())
+        };
+
+        method.map(|method| &**method)
+              .map_err(|_| LintKind::MissingMethod { method_ref: method_ref.clone() })
+    }
+
+    /// Returns all the methods transitively invoked from the given methods, including them.
+    fn invoked(&self, roots: Vec<(ClassName, MethodSig)>) -> HashSet<(ClassName, MethodSig)> {
+        let mut invoked: HashSet<_> = roots.iter().cloned().collect();
+        let mut pending = roots;
+
+        while let Some((class_name, signature)) = pending.pop() {
+            let method = self.classes[&class_name].methods()
+                                                  .find(|method| *method.signature() == signature)
+                                                  .unwrap();
+            let reachable = reachable(method.code());
+
+            for (pc, instruction) in method.code().instructions().enumerate() {
+                let (method_ref, is_static) = match instruction {
+                    Instruction::INVOKESTATIC(method_ref) => (method_ref, true),
+                    Instruction::INVOKEVIRTUAL(method_ref) => (method_ref, false),
+                    _ => continue,
+                };
+
+                if !reachable[pc] || self.method(method_ref, is_static).is_err() {
+                    continue;
+                }
+
+                let callee = (method_ref.class_name().clone(), method_ref.signature().clone());
+
+                if invoked.insert(callee.clone()) {
+                    pending.push(callee);
+                }
+            }
+        }
+
+        invoked
+    }
+}
+
+
+/// Marks instructions which can be reached from the method entry.
+fn reachable(code: &Code) -> Vec<bool> {
+    let instructions: Vec<_> = code.instructions().collect();
+    let mut reachable = vec![false; instructions.len()];
+    let mut pending = vec![0];
+
+    while let Some(pc) = pending.pop() {
+        if pc >= instructions.len() || reachable[pc] {
+            continue;
+        }
+
+        reachable[pc] = true;
+
+        match instructions[pc] {
+            Instruction::IRETURN | Instruction::LRETURN | Instruction::FRETURN |
+            Instruction::DRETURN | Instruction::ARETURN | Instruction::RETURN => continue,
+            Instruction::GOTO(_) => {}
+            _ => pending.push(pc + 1),
+        }
+
+        if let Some(offset) = instructions[pc].branch_offset() {
+            let target = pc as isize + offset as isize;
+
+            if target >= 0 {
+                pending.push(target as usize);
+            }
+        }
+    }
+
+    reachable
+}
+
+
+/// Returns the index and the type of the local variable accessed by the instruction.
+fn local_access(instruction: &Instruction) -> Option<(usize, ValueType)> {
+    let access = match instruction {
+        Instruction::ILOAD(index) | Instruction::ISTORE(index) | Instruction::IINC(index, _) => {
+            (*index as usize, ValueType::Int)
+        }
+        Instruction::LLOAD(index) | Instruction::LSTORE(index) => (*index as usize, ValueType::Long),
+        Instruction::FLOAD(index) | Instruction::FSTORE(index) => (*index as usize, ValueType::Float),
+        Instruction::DLOAD(index) | Instruction::DSTORE(index) => (*index as usize, ValueType::Double),
+        Instruction::ALOAD(index) | Instruction::ASTORE(index) => (*index as usize, ValueType::AnyReference),
+        Instruction::ILOAD_0 | Instruction::ISTORE_0 => (0, ValueType::Int),
+        Instruction::ILOAD_1 | Instruction::ISTORE_1 => (1, ValueType::Int),
+        Instruction::ILOAD_2 | Instruction::ISTORE_2 => (2, ValueType::Int),
+        Instruction::ILOAD_3 | Instruction::ISTORE_3 => (3, ValueType::Int),
+        Instruction::LLOAD_0 | Instruction::LSTORE_0 => (0, ValueType::Long),
+        Instruction::LLOAD_1 | Instruction::LSTORE_1 => (1, ValueType::Long),
+        Instruction::LLOAD_2 | Instruction::LSTORE_2 => (2, ValueType::Long),
+        Instruction::LLOAD_3 | Instruction::LSTORE_3 => (3, ValueType::Long),
+        Instruction::FLOAD_0 | Instruction::FSTORE_0 => (0, ValueType::Float),
+        Instruction::FLOAD_1 | Instruction::FSTORE_1 => (1, ValueType::Float),
+        Instruction::FLOAD_2 | Instruction::FSTORE_2 => (2, ValueType::Float),
+        Instruction::FLOAD_3 | Instruction::FSTORE_3 => (3, ValueType::Float),
+        Instruction::DLOAD_0 | Instruction::DSTORE_0 => (0, ValueType::Double),
+        Instruction::DLOAD_1 | Instruction::DSTORE_1 => (1, ValueType::Double),
+        Instruction::DLOAD_2 | Instruction::DSTORE_2 => (2, ValueType::Double),
+        Instruction::DLOAD_3 | Instruction::DSTORE_3 => (3, ValueType::Double),
+        Instruction::ALOAD_0 | Instruction::ASTORE_0 => (0, ValueType::AnyReference),
+        Instruction::ALOAD_1 | Instruction::ASTORE_1 => (1, ValueType::AnyReference),
+        Instruction::ALOAD_2 | Instruction::ASTORE_2 => (2, ValueType::AnyReference),
+        Instruction::ALOAD_3 | Instruction::ASTORE_3 => (3, ValueType::AnyReference),
+        _ => return None,
+    };
+
+    Some(access)
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryInto;
+
+    use crate::vm::class::class::Class;
+    use crate::vm::class::name::ClassName;
+    use crate::vm::parse::error::CreateClassError;
+    use crate::vm::parse::parser::ClassFileParser;
+    use crate::vm::verify::lint::Linter;
+
+
+    fn parse(source: &str) -> Class {
+        let info = ClassFileParser::new(source).parse().unwrap();
+        info.try_into().map_err(|e: CreateClassError| e.to_string()).unwrap()
+    }
+
+
+    #[test]
+    fn lints() {
+        let main = parse("Main
+            METHOD
+            static void <clinit> () 0
+            RETURN
+            END
+            METHOD
+            static void main () 3
+            ICONST_1
+            ISTORE_0
+            INVOKESTATIC void Main used ()
+            GETSTATIC int Other missing
+            POP
+            RETURN
+            ICONST_0
+            POP
+            RETURN
+            END
+            METHOD
+            static void used () 0
+            NEW Missing
+            POP
+            RETURN
+            END
+            METHOD
+            static void unused () 0
+            INVOKESTATIC void Main <clinit> ()
+            RETURN
+            END
+        ");
+        let other = parse("Other
+            FIELD
+            static int value
+            METHOD
+            void <init> () 1
+            RETURN
+            END
+        ");
+
+        let lints: Vec<_> = Linter::new(vec![&main, &other])
+            .lint(Some(&ClassName::new("Main").unwrap()))
+            .iter()
+            .map(|lint| lint.to_string())
+            .collect();
+
+        assert_eq!(lints, vec![
+            "in method void main () of class Main at pc 6: 3 unreachable instructions",
+            "in method void main () of class Main: local variable slot 1 is never used",
+            "in method void main () of class Main: local variable slot 2 is never used",
+            "in method void main () of class Main at pc 3: referenced field int Other missing does not exist",
+            "in method void used () of class Main at pc 0: class Missing is not on the class path",
+            "in method void <clinit> () of class Main: class initialization method is never called",
+            "in method void unused () of class Main: method is never invoked from main",
+            "in method void <init> () of class Other: method is never invoked from main",
+        ]);
+    }
+}
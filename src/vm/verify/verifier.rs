@@ -0,0 +1,598 @@
+//! Load-time verification of method code.
+//!
+//! The verifier infers the types of the operand stack and the local variables
+//! at every instruction by a data-flow analysis over the control flow graph
+//! of the method. Code which could fail on a type or stack error
+//! during the execution is rejected before the class is used.
+
+use crate::vm::bytecode::instruction::Instruction;
+use crate::vm::class::class::Class;
+use crate::vm::class::descriptor::ReturnDesc;
+use crate::vm::class::method::Method;
+use crate::vm::class::name::ClassName;
+use crate::vm::memory::frame::Frame;
+use crate::vm::types::value::{ValueCategory, ValueType};
+use crate::vm::verify::error::{VerifyError, VerifyErrorKind};
+
+
+/// A verifier of all the methods of a class.
+pub struct Verifier<'a> {
+    class: &'a Class,
+}
+
+
+impl<'a> Verifier<'a> {
+    /// Creates a new verifier of the given class.
+    pub fn new(class: &'a Class) -> Self {
+        Verifier { class }
+    }
+
+    /// Verifies code of all the methods of the class.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VerifyError` describing the first rejected instruction.
+    pub fn verify(&self) -> Result<(), VerifyError> {
+        for method in self.class.methods() {
+            MethodVerifier::new(self.class.name(), method).verify()?;
+        }
+
+        Ok(())
+    }
+}
+
+
+/// A verifier of a single method code.
+struct MethodVerifier<'a> {
+    class_name: &'a ClassName,
+    method: &'a Method,
+    instructions: Vec<&'a Instruction>,
+    states: Vec<Option<State>>,
+    pending: Vec<usize>,
+}
+
+
+impl<'a> MethodVerifier<'a> {
+    fn new(class_name: &'a ClassName, method: &'a Method) -> Self {
+        let instructions: Vec<_> = method.code().instructions().collect();
+        let states = vec![None; instructions.len()];
+
+        MethodVerifier {
+            class_name,
+            method,
+            instructions,
+            states,
+            pending: Vec::new(),
+        }
+    }
+
+    fn verify(mut self) -> Result<(), VerifyError> {
+        self.states[0] = Some(self.initial_state());
+        self.pending.push(0);
+
+        while let Some(pc) = self.pending.pop() {
+            let mut state = self.states[pc].clone().unwrap();
+
+            let flow = self.execute(pc, &mut state)
+                           .map_err(|kind| self.error(pc, kind))?;
+
+            let successors = match flow {
+                Flow::Next => vec![self.next(pc)],
+                Flow::Branch(offset) => vec![self.next(pc), self.target(pc, offset)],
+                Flow::Goto(offset) => vec![self.target(pc, offset)],
+                Flow::Return => vec![],
+            };
+
+            for successor in successors {
+                let successor = successor.map_err(|kind| self.error(pc, kind))?;
+                self.merge(successor, &state)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates the state on the method entry - locals holds `this` and the parameters.
+    fn initial_state(&self) -> State {
+        let mut locals = vec![None; self.method.code().locals_size()];
+        let mut index = 0;
+
+        if !self.method.is_static() {
+            locals[index] = Some(ValueType::Reference(self.class_name.clone()));
+            index += 1;
+        }
+
+        for type_desc in self.method.signature().params_desc().type_descs() {
+            let value_type = type_desc.value_type();
+            let size = value_type.category().size();
+            locals[index] = Some(value_type);
+            index += size;
+        }
+
+        State {
+            stack: Vec::new(),
+            locals,
+        }
+    }
+
+    /// Merges the state flowing into the instruction at `pc`
+    /// and schedules the instruction for (re)verification if its state changed.
+    fn merge(&mut self, pc: usize, state: &State) -> Result<(), VerifyError> {
+        let merged = match &self.states[pc] {
+            None => state.clone(),
+            Some(current) => {
+                let merged = current.merge(state).map_err(|kind| self.error(pc, kind))?;
+
+                if merged == *current {
+                    return Ok(());
+                }
+
+                merged
+            }
+        };
+
+        self.states[pc] = Some(merged);
+        self.pending.push(pc);
+        Ok(())
+    }
+
+    fn next(&self, pc: usize) -> Result<usize, VerifyErrorKind> {
+        if pc + 1 >= self.instructions.len() {
+            return Err(VerifyErrorKind::FallsOffEnd);
+        }
+
+        Ok(pc + 1)
+    }
+
+    fn target(&self, pc: usize, offset: i16) -> Result<usize, VerifyErrorKind> {
+        let target = pc as isize + offset as isize;
+
+        if target < 0 || target as usize >= self.instructions.len() {
+            return Err(VerifyErrorKind::BranchOutOfBounds {
+                target,
+                len: self.instructions.len(),
+            });
+        }
+
+        Ok(target as usize)
+    }
+
+    fn error(&self, pc: usize, kind: VerifyErrorKind) -> VerifyError {
+        VerifyError::new(self.method.signature().clone(), pc, self.instructions[pc].mnemonic(), kind)
+    }
+}
+
+
+/// Abstract execution of the instructions.
+impl<'a> MethodVerifier<'a> {
+    /// Applies the effect of the instruction at `pc` on the given state.
+    fn execute(&self, pc: usize, state: &mut State) -> Result<Flow, VerifyErrorKind> {
+        let int = ValueType::Int;
+        let long = ValueType::Long;
+        let float = ValueType::Float;
+        let double = ValueType::Double;
+
+        match &self.instructions[pc] {
+            Instruction::ILOAD(index) => state.load(*index as usize, int)?,
+            Instruction::LLOAD(index) => state.load(*index as usize, long)?,
+            Instruction::FLOAD(index) => state.load(*index as usize, float)?,
+            Instruction::DLOAD(index) => state.load(*index as usize, double)?,
+            Instruction::ALOAD(index) => state.load(*index as usize, ValueType::AnyReference)?,
+            Instruction::ILOAD_0 => state.load(0, int)?,
+            Instruction::ILOAD_1 => state.load(1, int)?,
+            Instruction::ILOAD_2 => state.load(2, int)?,
+            Instruction::ILOAD_3 => state.load(3, int)?,
+            Instruction::LLOAD_0 => state.load(0, long)?,
+            Instruction::LLOAD_1 => state.load(1, long)?,
+            Instruction::LLOAD_2 => state.load(2, long)?,
+            Instruction::LLOAD_3 => state.load(3, long)?,
+            Instruction::FLOAD_0 => state.load(0, float)?,
+            Instruction::FLOAD_1 => state.load(1, float)?,
+            Instruction::FLOAD_2 => state.load(2, float)?,
+            Instruction::FLOAD_3 => state.load(3, float)?,
+            Instruction::DLOAD_0 => state.load(0, double)?,
+            Instruction::DLOAD_1 => state.load(1, double)?,
+            Instruction::DLOAD_2 => state.load(2, double)?,
+            Instruction::DLOAD_3 => state.load(3, double)?,
+            Instruction::ALOAD_0 => state.load(0, ValueType::AnyReference)?,
+            Instruction::ALOAD_1 => state.load(1, ValueType::AnyReference)?,
+            Instruction::ALOAD_2 => state.load(2, ValueType::AnyReference)?,
+            Instruction::ALOAD_3 => state.load(3, ValueType::AnyReference)?,
+
+            Instruction::ISTORE(index) => state.store(*index as usize, int)?,
+            Instruction::LSTORE(index) => state.store(*index as usize, long)?,
+            Instruction::FSTORE(index) => state.store(*index as usize, float)?,
+            Instruction::DSTORE(index) => state.store(*index as usize, double)?,
+            Instruction::ASTORE(index) => state.store(*index as usize, ValueType::AnyReference)?,
+            Instruction::ISTORE_0 => state.store(0, int)?,
+            Instruction::ISTORE_1 => state.store(1, int)?,
+            Instruction::ISTORE_2 => state.store(2, int)?,
+            Instruction::ISTORE_3 => state.store(3, int)?,
+            Instruction::LSTORE_0 => state.store(0, long)?,
+            Instruction::LSTORE_1 => state.store(1, long)?,
+            Instruction::LSTORE_2 => state.store(2, long)?,
+            Instruction::LSTORE_3 => state.store(3, long)?,
+            Instruction::FSTORE_0 => state.store(0, float)?,
+            Instruction::FSTORE_1 => state.store(1, float)?,
+            Instruction::FSTORE_2 => state.store(2, float)?,
+            Instruction::FSTORE_3 => state.store(3, float)?,
+            Instruction::DSTORE_0 => state.store(0, double)?,
+            Instruction::DSTORE_1 => state.store(1, double)?,
+            Instruction::DSTORE_2 => state.store(2, double)?,
+            Instruction::DSTORE_3 => state.store(3, double)?,
+            Instruction::ASTORE_0 => state.store(0, ValueType::AnyReference)?,
+            Instruction::ASTORE_1 => state.store(1, ValueType::AnyReference)?,
+            Instruction::ASTORE_2 => state.store(2, ValueType::AnyReference)?,
+            Instruction::ASTORE_3 => state.store(3, ValueType::AnyReference)?,
+
+            Instruction::POP => { state.take(1)?; }
+            Instruction::POP2 => { state.take(2)?; }
+            Instruction::DUP => state.dup_skip(1, 0)?,
+            Instruction::DUP_X1 => state.dup_skip(1, 1)?,
+            Instruction::DUP_X2 => state.dup_skip(1, 2)?,
+            Instruction::DUP2 => state.dup_skip(2, 0)?,
+            Instruction::DUP2_X1 => state.dup_skip(2, 1)?,
+            Instruction::DUP2_X2 => state.dup_skip(2, 2)?,
+            Instruction::SWAP => {
+                let value1 = state.take(1)?;
+                let value2 = state.take(1)?;
+                state.stack.extend(value1);
+                state.stack.extend(value2);
+            }
+
+            Instruction::IINC(index, _) => {
+                state.load(*index as usize, int)?;
+                state.pop(&ValueType::Int)?;
+            }
+
+            Instruction::IRETURN => return self.execute_return(state, Some(int)),
+            Instruction::LRETURN => return self.execute_return(state, Some(long)),
+            Instruction::FRETURN => return self.execute_return(state, Some(float)),
+            Instruction::DRETURN => return self.execute_return(state, Some(double)),
+            Instruction::ARETURN => return self.execute_return(state, Some(ValueType::AnyReference)),
+            Instruction::RETURN => return self.execute_return(state, None),
+
+            // the rest have a fixed stack effect given by the instruction metadata
+            instruction => {
+                let effect = instruction.stack_effect().expect("only stack manipulation has no stack effect");
+
+                for value_type in effect.consumed().iter().rev() {
+                    state.pop(value_type)?;
+                }
+
+                for value_type in effect.produced() {
+                    state.push(value_type.clone())?;
+                }
+
+                if let Instruction::GOTO(offset) = instruction {
+                    return Ok(Flow::Goto(*offset));
+                }
+
+                if let Some(offset) = instruction.branch_offset() {
+                    return Ok(Flow::Branch(offset));
+                }
+            }
+        }
+
+        Ok(Flow::Next)
+    }
+
+    fn execute_return(&self, state: &mut State, value_type: Option<ValueType>) -> Result<Flow, VerifyErrorKind> {
+        let return_desc = self.method.signature().return_desc();
+
+        match (return_desc, value_type) {
+            (ReturnDesc::Void, None) => {}
+            (ReturnDesc::NonVoid(type_desc), Some(value_type))
+            if is_assignable(&value_type, &type_desc.value_type()) => {
+                state.pop(&type_desc.value_type())?;
+            }
+            _ => return Err(VerifyErrorKind::InvalidReturn {
+                expected: return_desc.clone()
+            }),
+        }
+
+        Ok(Flow::Return)
+    }
+}
+
+
+/// A control flow after an instruction.
+enum Flow {
+    Next,
+    Branch(i16),
+    Goto(i16),
+    Return,
+}
+
+
+/// Inferred types of the operand stack and the local variables.
+/// An undefined local or the second slot of a category 2 value is `None`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct State {
+    stack: Vec<ValueType>,
+    locals: Vec<Option<ValueType>>,
+}
+
+
+impl State {
+    fn stack_size(&self) -> usize {
+        self.stack.iter().map(|value_type| value_type.category().size()).sum()
+    }
+
+    fn push(&mut self, value_type: ValueType) -> Result<(), VerifyErrorKind> {
+        if self.stack_size() + value_type.category().size() > Frame::MAX_STACK {
+            return Err(VerifyErrorKind::StackOverflow { max: Frame::MAX_STACK });
+        }
+
+        self.stack.push(value_type);
+        Ok(())
+    }
+
+    /// Pops a value assignable to the `expected` type and returns its type.
+    fn pop(&mut self, expected: &ValueType) -> Result<ValueType, VerifyErrorKind> {
+        let found = self.stack.pop().ok_or(VerifyErrorKind::StackUnderflow)?;
+
+        if !is_assignable(expected, &found) {
+            return Err(VerifyErrorKind::StackTypeMismatch {
+                expected: expected.clone(),
+                found,
+            });
+        }
+
+        Ok(found)
+    }
+
+    /// Removes top values of the given total size and returns them in the stack order.
+    fn take(&mut self, size: usize) -> Result<Vec<ValueType>, VerifyErrorKind> {
+        let mut taken_size = 0;
+        let mut count = 0;
+
+        while taken_size < size {
+            count += 1;
+            let value_type = self.stack.get(self.stack.len().wrapping_sub(count))
+                                 .ok_or(VerifyErrorKind::StackUnderflow)?;
+            taken_size += value_type.category().size();
+        }
+
+        if taken_size != size {
+            return Err(VerifyErrorKind::SplitValue);
+        }
+
+        let len = self.stack.len();
+        Ok(self.stack.drain((len - count)..).collect())
+    }
+
+    fn dup_skip(&mut self, dup: usize, skip: usize) -> Result<(), VerifyErrorKind> {
+        let to_dup = self.take(dup)?;
+        let to_skip = self.take(skip)?;
+
+        if self.stack_size() + 2 * dup + skip > Frame::MAX_STACK {
+            return Err(VerifyErrorKind::StackOverflow { max: Frame::MAX_STACK });
+        }
+
+        self.stack.extend(to_dup.iter().cloned());
+        self.stack.extend(to_skip);
+        self.stack.extend(to_dup);
+        Ok(())
+    }
+
+    fn check_local(&self, index: usize, value_type: &ValueType) -> Result<(), VerifyErrorKind> {
+        if index + value_type.category().size() > self.locals.len() {
+            return Err(VerifyErrorKind::LocalOutOfBounds {
+                index,
+                size: self.locals.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Pushes a value of the local at `index`, which must be assignable to the `expected` type.
+    fn load(&mut self, index: usize, expected: ValueType) -> Result<(), VerifyErrorKind> {
+        self.check_local(index, &expected)?;
+
+        let found = self.locals[index].clone()
+                                      .ok_or(VerifyErrorKind::UndefinedLocal { index })?;
+
+        if !is_assignable(&expected, &found) {
+            return Err(VerifyErrorKind::LocalTypeMismatch {
+                index,
+                expected,
+                found,
+            });
+        }
+
+        self.push(found)
+    }
+
+    /// Pops a value assignable to the `expected` type and stores it into the local at `index`.
+    fn store(&mut self, index: usize, expected: ValueType) -> Result<(), VerifyErrorKind> {
+        self.check_local(index, &expected)?;
+        let value_type = self.pop(&expected)?;
+
+        // invalidate the previous value if it is of double category
+        if index != 0 {
+            if let Some(previous) = &self.locals[index - 1] {
+                if previous.category() == ValueCategory::Double {
+                    self.locals[index - 1] = None;
+                }
+            }
+        }
+
+        if value_type.category() == ValueCategory::Double {
+            self.locals[index + 1] = None;
+        }
+
+        self.locals[index] = Some(value_type);
+        Ok(())
+    }
+
+    /// Merges two states of the same instruction reached by different paths.
+    fn merge(&self, other: &State) -> Result<State, VerifyErrorKind> {
+        if self.stack.len() != other.stack.len() {
+            return Err(VerifyErrorKind::StackSizeMismatch {
+                size: self.stack_size(),
+                other: other.stack_size(),
+            });
+        }
+
+        let stack = self.stack.iter()
+                        .zip(other.stack.iter())
+                        .map(|(value_type, other)| {
+                            merge_types(value_type, other).ok_or_else(|| VerifyErrorKind::StackMergeMismatch {
+                                value_type: value_type.clone(),
+                                other: other.clone(),
+                            })
+                        })
+                        .collect::<Result<_, _>>()?;
+
+        let locals = self.locals.iter()
+                         .zip(other.locals.iter())
+                         .map(|(local, other)| match (local, other) {
+                             (Some(local), Some(other)) => merge_types(local, other),
+                             _ => None,
+                         })
+                         .collect();
+
+        Ok(State { stack, locals })
+    }
+}
+
+
+fn is_reference(value_type: &ValueType) -> bool {
+    matches!(value_type, ValueType::Null | ValueType::Reference(_) | ValueType::AnyReference)
+}
+
+
+/// Returns true if a value of the `found` type can be used where the `expected` type is required.
+/// A reference of unknown class is accepted as any reference, its class is checked at runtime.
+fn is_assignable(expected: &ValueType, found: &ValueType) -> bool {
+    match expected {
+        ValueType::AnyReference => is_reference(found),
+        ValueType::Reference(_) => {
+            *found == ValueType::Null || *found == ValueType::AnyReference || found == expected
+        }
+        _ => found == expected
+    }
+}
+
+
+/// Returns the most specific type of both the given types or `None` if there is not such type.
+fn merge_types(value_type: &ValueType, other: &ValueType) -> Option<ValueType> {
+    if value_type == other {
+        return Some(value_type.clone());
+    }
+
+    match (value_type, other) {
+        (ValueType::Null, other) if is_reference(other) => Some(other.clone()),
+        (value_type, ValueType::Null) if is_reference(value_type) => Some(value_type.clone()),
+        (value_type, other) if is_reference(value_type) && is_reference(other) => Some(ValueType::AnyReference),
+        _ => None
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryInto;
+
+    use crate::vm::class::class::Class;
+    use crate::vm::parse::error::CreateClassError;
+    use crate::vm::parse::parser::ClassFileParser;
+    use crate::vm::verify::error::{VerifyError, VerifyErrorKind};
+    use crate::vm::verify::verifier::Verifier;
+
+
+    fn verify(source: &str) -> Result<(), VerifyError> {
+        let info = ClassFileParser::new(source).parse().unwrap();
+        let class: Class = info.try_into().map_err(|e: CreateClassError| e.to_string()).unwrap();
+        Verifier::new(&class).verify()
+    }
+
+
+    fn verify_main(body: &str) -> Result<(), VerifyError> {
+        verify(&format!("Test\nMETHOD\nstatic void main () 4\n{}\nEND\n", body))
+    }
+
+
+    #[test]
+    fn valid_code() {
+        verify_main("
+            ICONST_5
+            ISTORE_0
+            LCONST_1
+            LSTORE_1
+        loop:
+            IINC 0 -1
+            ILOAD_0
+            IFNE loop
+            ACONST_NULL
+            IFNULL end
+            NEW Test
+            ASTORE_3
+        end: RETURN
+        ").unwrap();
+
+        verify("Test
+            FIELD
+            int value
+            METHOD
+            int get (long) 3
+            ALOAD_0
+            GETFIELD int Test value
+            LLOAD_1
+            L2I
+            IADD
+            IRETURN
+            END
+            METHOD
+            static void main () 1
+            NEW Test
+            DUP
+            LCONST_0
+            INVOKEVIRTUAL int Test get (long)
+            POP
+            ASTORE_0
+            RETURN
+            END
+        ").unwrap();
+    }
+
+
+    #[test]
+    fn invalid_code() {
+        let cases = vec![
+            ("IADD\nRETURN", 0, "operand stack underflow"),
+            ("ICONST_1\nFCONST_1\nIADD\nRETURN", 2, "expected int on the operand stack, found float"),
+            ("ILOAD_0\nRETURN", 0, "local variable 0 is not initialized"),
+            ("DCONST_0\nDSTORE_3\nRETURN", 1, "local variable index 3 is out of bounds, locals size is 4"),
+            ("LCONST_0\nLSTORE_0\nILOAD_1\nRETURN", 2, "local variable 1 is not initialized"),
+            ("LCONST_0\nPOP\nRETURN", 1, "operation would split a value of category 2 on the operand stack"),
+            ("ICONST_0\nIFEQ 5\nRETURN", 1, "branch target 6 is out of bounds, the code length is 3"),
+            ("NOP", 0, "execution falls off the end of the code"),
+            ("ICONST_0\nIRETURN", 1, "invalid return, the method returns void"),
+            ("ICONST_0\nIFEQ end\nICONST_1\nend: RETURN", 3,
+             "operand stack size differs between control flow paths: 0 and 1"),
+            ("ICONST_0\nICONST_0\nIFEQ end\nPOP\nFCONST_0\nend: RETURN", 5,
+             "operand stack types differ between control flow paths: int and float"),
+            ("loop: ICONST_0\nGOTO loop", 0, "operand stack size differs between control flow paths: 0 and 1"),
+        ];
+
+        for (body, pc, message) in cases {
+            let error = verify_main(body).unwrap_err();
+            assert_eq!((error.pc(), error.kind().to_string().as_str()), (pc, message), "{}", body);
+        }
+
+        let error = verify("Test
+            METHOD
+            float computeArea () 1
+            FCONST_1
+            END
+        ").unwrap_err();
+
+        assert!(matches!(error.kind(), VerifyErrorKind::FallsOffEnd));
+        assert_eq!(error.to_string(),
+                   "verification of method float computeArea () failed at pc 0 (FCONST_1): \
+                   execution falls off the end of the code");
+    }
+}